@@ -1,12 +1,14 @@
+use std::collections::HashMap;
+
 use chrono::{Duration, NaiveDateTime, Utc};
-use gemuki_service::query::GameQuery;
+use gemuki_service::query::{GameQuery, SteamAppQuery};
 use log::{error, info};
 use migration::sea_orm::DbConn;
 
-use crate::steam::{App, ApplistResponse};
-
-static GET_ALL_APPS_URL: &str =
-    "https://api.steampowered.com/ISteamApps/GetAppList/v0002/?format=json";
+use crate::{
+    fuzzy::{self, SearchIndex},
+    steam::{App, Price},
+};
 
 pub struct GameTitleCache {
     refresh_interval: Duration,
@@ -35,12 +37,12 @@ impl GameTitleCache {
         }
     }
 
-    pub fn last_refresh(&self) -> NaiveDateTime {
-        self.last_refresh
-    }
-
-    pub fn cache(&self) -> &[String] {
-        &self.cache
+    /// Searches the cached titles with a ranked fuzzy match.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<String> {
+        fuzzy::rank(query, self.cache.iter().map(String::as_str), limit)
+            .into_iter()
+            .map(str::to_owned)
+            .collect()
     }
 
     pub async fn update(&mut self, db: &DbConn) {
@@ -63,87 +65,113 @@ impl GameTitleCache {
     }
 }
 
+/// Result of [`SteamAppCache::match_title`].
+#[derive(Clone, Debug)]
+pub enum AppMatch {
+    Found(u32),
+    Ambiguous(Vec<App>),
+    NotFound,
+}
+
+/// In-memory snapshot of the steam app list. It is loaded from the database on startup and
+/// replaced as a whole by [`crate::tasks::refresh_steam_apps`].
 pub struct SteamAppCache {
-    refresh_interval: Duration,
-    last_refresh: NaiveDateTime,
     cache: Vec<App>,
+    index: SearchIndex,
 }
 
 impl SteamAppCache {
-    pub async fn init(refresh_interval: Duration) -> Self {
-        let titles = Self::get_apps().await;
-
+    pub fn new(apps: Vec<App>) -> Self {
         Self {
-            refresh_interval,
-            last_refresh: Utc::now().naive_utc(),
-            cache: titles,
+            index: SearchIndex::new(apps.iter().map(App::name)),
+            cache: apps,
         }
     }
 
-    async fn get_apps() -> Vec<App> {
-        let body = match Self::request_apps().await {
-            Ok(b) => b,
+    /// Loads the last stored snapshot of the app list.
+    pub async fn load(db: &DbConn) -> Self {
+        let apps = match SteamAppQuery::get_all(db).await {
+            Ok(a) => a.into_iter().map(App::from).collect(),
             Err(why) => {
-                error!("An error occured while trying to get all steam apps: {why}");
-                return Vec::new();
-            }
-        };
-
-        let apps_response = match serde_json::de::from_str::<ApplistResponse>(&body) {
-            Ok(r) => r,
-            Err(why) => {
-                error!("An error occured while trying to parse apps response: {why}");
-                return Vec::new();
+                error!("An error occured while trying to load steam apps: {why}");
+                Vec::new()
             }
         };
 
-        let apps: Vec<App> = apps_response
-            .applist()
-            .apps()
-            .iter()
-            .filter(|x| !x.name().trim().is_empty())
-            .map(|x| x.clone())
-            .collect();
+        info!("Loaded {} steam apps.", apps.len());
 
-        apps
+        Self::new(apps)
     }
 
-    async fn request_apps() -> Result<String, reqwest::Error> {
-        reqwest::get(GET_ALL_APPS_URL).await?.text().await
+    /// Finds an app whose name equals the given name, ignoring case and punctuation.
+    pub fn find_by_name(&self, name: &str) -> Option<&App> {
+        self.index
+            .candidates(name)
+            .into_iter()
+            .map(|x| &self.cache[x])
+            .find(|x| fuzzy::is_same_title(x.name(), name))
     }
 
-    pub fn last_refresh(&self) -> NaiveDateTime {
-        self.last_refresh
+    /// Matches a title against the app list. A title matches an app if it is the only candidate
+    /// or the only one with the same name, otherwise the best `limit` candidates are returned.
+    pub fn match_title(&self, title: &str, limit: usize) -> AppMatch {
+        let candidates = self.search(title, limit);
+        let same_titles = candidates
+            .iter()
+            .filter(|x| fuzzy::is_same_title(x.name(), title))
+            .collect::<Vec<&&App>>();
+
+        match (same_titles.as_slice(), candidates.as_slice()) {
+            ([app], _) => AppMatch::Found(app.appid()),
+            (_, [app]) => AppMatch::Found(app.appid()),
+            (_, []) => AppMatch::NotFound,
+            _ => AppMatch::Ambiguous(candidates.into_iter().cloned().collect()),
+        }
     }
 
-    pub fn cache(&self) -> &[App] {
-        &self.cache
-    }
+    /// Searches the apps with a ranked fuzzy match of their names.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<&App> {
+        let candidates = self
+            .index
+            .candidates(query)
+            .into_iter()
+            .map(|x| &self.cache[x]);
 
-    pub fn find_by_id(&self, appid: u32) -> Option<&App> {
-        self.cache.iter().find(|x| x.appid() == appid)
+        fuzzy::rank_by(query, candidates, |x| x.name(), limit)
     }
+}
 
-    pub fn find_by_name(&self, name: &str) -> Option<&App> {
-        self.cache.iter().find(|x| x.name() == name)
-    }
+/// Caches the prices of steam apps per store region.
+pub struct PriceCache {
+    max_age: Duration,
+    cache: HashMap<(u32, String), (NaiveDateTime, Price)>,
+}
 
-    pub async fn update(&mut self) {
-        let now: NaiveDateTime = Utc::now().naive_utc();
-        let delta = now - self.last_refresh;
+impl PriceCache {
+    pub fn new(max_age: Duration) -> Self {
+        Self {
+            max_age,
+            cache: HashMap::new(),
+        }
+    }
 
-        if delta.num_seconds() > self.refresh_interval.num_seconds() || self.cache.is_empty() {
-            self.cache = Self::get_apps().await;
-            self.last_refresh = now;
+    /// Gets the price of an app in a region if it is not older than the maximum age.
+    pub fn get(&self, appid: u32, country_code: &str) -> Option<Price> {
+        let now = Utc::now().naive_utc();
 
-            info!("Cache has been updated.");
-        }
+        self.cache
+            .get(&(appid, country_code.to_owned()))
+            .filter(|(date, _)| now - *date <= self.max_age)
+            .map(|(_, price)| price.clone())
     }
 
-    pub async fn force_update(&mut self) {
-        self.cache = Self::get_apps().await;
-        self.last_refresh = Utc::now().naive_utc();
+    /// Stores the price of an app in a region and drops outdated prices.
+    pub fn insert(&mut self, appid: u32, country_code: &str, price: Price) {
+        let now = Utc::now().naive_utc();
 
-        info!("Cache has been updated forcefully.");
+        self.cache
+            .retain(|_, (date, _)| now - *date <= self.max_age);
+        self.cache
+            .insert((appid, country_code.to_owned()), (now, price));
     }
 }
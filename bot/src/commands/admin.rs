@@ -0,0 +1,367 @@
+use chrono::{Duration, Utc};
+use gemuki_service::{
+    backup::Backup,
+    crypto::KeyCipher,
+    mutation::{BackupMutation, GameKeyMutation, UserSettingMutation},
+    query::BackupQuery,
+};
+use log::{error, info, warn};
+use migration::{Migrator, MigratorTrait};
+use poise::{
+    serenity_prelude::{Attachment, Color, CreateAttachment, CreateEmbed, User},
+    CreateReply,
+};
+
+use crate::{Data, PoiseError};
+
+type Context<'a> = poise::Context<'a, Data, PoiseError>;
+
+/// A command for administrating the bot.
+#[poise::command(
+    slash_command,
+    owners_only,
+    subcommands("rotate_key", "backup", "restore", "refresh_report", "claim_override")
+)]
+pub async fn admin(ctx: Context<'_>) -> Result<(), PoiseError> {
+    ctx.say("How did you manage to do this?").await?;
+    Ok(())
+}
+
+/// Re-encrypts all stored keys with a new master key read from `GEMUKI_NEW_MASTER_KEY_FILE`.
+#[poise::command(
+    slash_command,
+    owners_only,
+    dm_only,
+    name_localized("de", "rotate-key"),
+    name_localized("en-US", "rotate-key")
+)]
+pub async fn rotate_key(ctx: Context<'_>) -> Result<(), PoiseError> {
+    let db = &ctx.data().conn;
+
+    let new_master_key = match read_new_master_key().await {
+        Some(k) => k,
+        None => {
+            ctx.send(
+                CreateReply::default()
+                    .content("Write the new master key into the file set in `GEMUKI_NEW_MASTER_KEY_FILE` on the host first.")
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let new_cipher = KeyCipher::new(&new_master_key);
+    // Waits for all commands still encrypting or decrypting keys with the current master key.
+    let mut cipher = ctx.data().key_cipher.write().await;
+
+    let message = match GameKeyMutation::rotate_master_key(db, &cipher, &new_cipher).await {
+        Ok(rotated) => {
+            *cipher = new_cipher;
+            warn!("Rotated master key for {rotated} keys.");
+
+            format!("Re-encrypted `{rotated}` keys. Set `GEMUKI_MASTER_KEY` to the new master key and delete the key file before restarting the bot.")
+        }
+        Err(why) => {
+            error!("Could not rotate master key because of '{}'.", why);
+            "Could not rotate the master key because of an internal error. No keys have been changed.".to_owned()
+        }
+    };
+    drop(cipher);
+
+    ctx.send(CreateReply::default().content(message).ephemeral(true))
+        .await?;
+
+    Ok(())
+}
+
+/// Reads the new master key from the file named in `GEMUKI_NEW_MASTER_KEY_FILE`, so the secret
+/// never passes through discord. Returns `None` if the file is not set, missing or empty.
+async fn read_new_master_key() -> Option<String> {
+    let path = std::env::var("GEMUKI_NEW_MASTER_KEY_FILE").ok()?;
+    let content = match tokio::fs::read_to_string(&path).await {
+        Ok(c) => c,
+        Err(why) => {
+            error!("Could not read the new master key from '{path}' because of '{why}'.");
+            return None;
+        }
+    };
+
+    Some(content.trim().to_owned()).filter(|x| !x.is_empty())
+}
+
+/// Sends a backup of all games, keys, platforms and pools. Encrypted if a passphrase is given.
+#[poise::command(slash_command, owners_only, dm_only)]
+pub async fn backup(
+    ctx: Context<'_>,
+    #[description = "Passphrase to encrypt the backup with."] passphrase: Option<String>,
+) -> Result<(), PoiseError> {
+    let db = &ctx.data().conn;
+
+    ctx.defer_ephemeral().await?;
+
+    let backup = {
+        let cipher = ctx.data().key_cipher.read().await;
+        BackupQuery::create(db, &cipher, &schema_version()).await
+    };
+    let backup = match backup {
+        Ok(b) => b,
+        Err(why) => {
+            error!("Could not create backup because of '{}'.", why);
+            ctx.send(
+                CreateReply::default()
+                    .content("Could not create the backup because of an internal error.")
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let passphrase = passphrase.filter(|x| !x.is_empty());
+    let encrypted = passphrase.is_some();
+    let (games, game_keys) = (backup.games.len(), backup.game_keys.len());
+    // Deriving the key from the passphrase is slow, so it must not block the runtime.
+    let data =
+        tokio::task::spawn_blocking(move || backup.to_bytes(passphrase.as_deref())).await??;
+    let file_name = format!("gemuki_backup_{}.json", Utc::now().format("%Y%m%d_%H%M%S"));
+
+    info!("Created backup with {games} games and {game_keys} keys.");
+
+    let content = match encrypted {
+        true => format!(
+            "Backup of `{games}` games and `{game_keys}` keys. The backup is encrypted with your passphrase."
+        ),
+        false => format!(
+            "Backup of `{games}` games and `{game_keys}` keys. The backup contains all keys in plain text, keep it safe."
+        ),
+    };
+
+    ctx.send(
+        CreateReply::default()
+            .content(content)
+            .attachment(CreateAttachment::bytes(data, file_name))
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Restores a backup created with `/admin backup`. Existing games and keys are kept.
+#[poise::command(slash_command, owners_only, dm_only)]
+pub async fn restore(
+    ctx: Context<'_>,
+    #[description = "The backup file."] file: Attachment,
+    #[description = "Passphrase the backup has been encrypted with."] passphrase: Option<String>,
+) -> Result<(), PoiseError> {
+    let db = &ctx.data().conn;
+
+    ctx.defer_ephemeral().await?;
+
+    let data = file.download().await?;
+    let backup = tokio::task::spawn_blocking(move || {
+        Backup::from_bytes(&data, passphrase.as_deref(), &schema_version())
+    })
+    .await?;
+    let backup = match backup {
+        Ok(b) => b,
+        Err(why) => {
+            ctx.send(
+                CreateReply::default()
+                    .content(format!("Could not read the backup. {why}"))
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let report = {
+        let cipher = ctx.data().key_cipher.read().await;
+        BackupMutation::restore(db, &cipher, backup).await
+    };
+    let report = match report {
+        Ok(r) => r,
+        Err(why) => {
+            error!("Could not restore backup because of '{}'.", why);
+            ctx.send(
+                CreateReply::default()
+                    .content("Could not restore the backup because of an internal error. Nothing has been changed.")
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    warn!(
+        "Restored backup with {} games and {} keys.",
+        report.games.created, report.game_keys.created
+    );
+
+    ctx.data()
+        .game_title_cache
+        .lock()
+        .await
+        .force_update(db)
+        .await;
+
+    let summary = format!(
+        "Restored `{}` platforms, `{}` pools, `{}` games and `{}` keys. Kept `{}` platforms, `{}` pools, `{}` games and `{}` keys which already existed. `{}` conflicts.",
+        report.platforms.created,
+        report.pools.created,
+        report.games.created,
+        report.game_keys.created,
+        report.platforms.existing,
+        report.pools.existing,
+        report.games.existing,
+        report.game_keys.existing,
+        report.conflicts.len()
+    );
+
+    let reply = if report.conflicts.is_empty() {
+        CreateReply::default().content(summary)
+    } else {
+        CreateReply::default()
+            .content(summary)
+            .attachment(CreateAttachment::bytes(
+                report.conflicts.join("\n"),
+                "restore_conflicts.txt",
+            ))
+    };
+
+    ctx.send(reply.ephemeral(true)).await?;
+
+    Ok(())
+}
+
+/// Name of the latest migration, used as schema version of backups.
+fn schema_version() -> String {
+    Migrator::migrations()
+        .last()
+        .map(|x| x.name().to_owned())
+        .unwrap_or_default()
+}
+
+/// Maximum length of an embed field value allowed by discord.
+const MAX_FIELD_LENGTH: usize = 1024;
+
+/// Shows which games changed or failed during the last scheduled refresh of steam details.
+#[poise::command(
+    slash_command,
+    owners_only,
+    name_localized("de", "refresh-report"),
+    name_localized("en-US", "refresh-report")
+)]
+pub async fn refresh_report(ctx: Context<'_>) -> Result<(), PoiseError> {
+    let report = ctx.data().refresh_report.lock().await.clone();
+
+    let finish_date = match report.finish_date {
+        Some(d) => d,
+        None => {
+            ctx.send(
+                CreateReply::default()
+                    .content("The game metadata has not been refreshed yet.")
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let changed = report
+        .changed
+        .iter()
+        .map(|x| format!("- {x}"))
+        .collect::<Vec<String>>();
+    let failed = report
+        .failed
+        .iter()
+        .map(|(title, reason)| format!("- {title}: {reason}"))
+        .collect::<Vec<String>>();
+
+    let embed = CreateEmbed::new()
+        .colour(Color::DARK_BLUE)
+        .title("Metadata refresh")
+        .description(format!(
+            "Checked `{}` games <t:{}:R>.",
+            report.checked,
+            finish_date.timestamp()
+        ))
+        .field(
+            format!("Changed ({})", changed.len()),
+            list_field(&changed),
+            false,
+        )
+        .field(
+            format!("Failed ({})", failed.len()),
+            list_field(&failed),
+            false,
+        );
+
+    ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+
+    Ok(())
+}
+
+/// Lifts the claim limits of a user for some hours. Use 0 hours to remove the override.
+#[poise::command(
+    slash_command,
+    owners_only,
+    name_localized("de", "claim-override"),
+    name_localized("en-US", "claim-override")
+)]
+pub async fn claim_override(
+    ctx: Context<'_>,
+    #[description = "User whose claim limits are lifted."] user: User,
+    #[description = "How many hours the limits are lifted."]
+    #[min = 0]
+    hours: i64,
+) -> Result<(), PoiseError> {
+    let db = &ctx.data().conn;
+
+    let until = match hours {
+        0 => None,
+        h => Some(Utc::now() + Duration::hours(h)),
+    };
+    UserSettingMutation::set_claim_override(db, user.id.get(), until).await?;
+
+    let message = match until {
+        Some(until) => {
+            info!("Lifted the claim limits of {} until {until}.", user.id);
+            format!(
+                "Claim limits of <@{}> are lifted until <t:{}:f>.",
+                user.id,
+                until.timestamp()
+            )
+        }
+        None => format!("Claim limits apply to <@{}> again.", user.id),
+    };
+
+    ctx.send(CreateReply::default().content(message).ephemeral(true))
+        .await?;
+
+    Ok(())
+}
+
+/// Joins the lines of a list for an embed field. Lines which do not fit are left out.
+fn list_field(lines: &[String]) -> String {
+    if lines.is_empty() {
+        return "None".to_owned();
+    }
+
+    let mut value = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        let more = format!("\n... and {} more", lines.len() - i);
+        if value.len() + line.len() + 1 + more.len() > MAX_FIELD_LENGTH {
+            value.push_str(more.trim_start());
+            break;
+        }
+        value.push_str(line);
+        value.push('\n');
+    }
+
+    value
+}
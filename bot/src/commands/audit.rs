@@ -0,0 +1,46 @@
+use gemuki_service::query::AuditFilter;
+use poise::serenity_prelude::User;
+
+use crate::{
+    commands::{autocomplete_game, did_you_mean, find_game},
+    paginate, Data, PoiseError,
+};
+
+type Context<'a> = poise::Context<'a, Data, PoiseError>;
+
+/// Pages through the audit log of reveals, edits and deletions.
+#[poise::command(slash_command, owners_only)]
+pub async fn audit(
+    ctx: Context<'_>,
+    #[description = "Filter for the id of a gamekey."] gamekey_id: Option<i32>,
+    #[description = "Filter for the name of a game."]
+    #[autocomplete = "autocomplete_game"]
+    game: Option<String>,
+    #[description = "Filter for the user who caused the event."] user: Option<User>,
+) -> Result<(), PoiseError> {
+    let game_id = match game {
+        Some(title) => match find_game(ctx, &title).await? {
+            Some(g) => Some(g.id),
+            None => {
+                ctx.reply(format!(
+                    "Could not find a game with the title '{}'.{}",
+                    title,
+                    did_you_mean(ctx, &title).await
+                ))
+                .await?;
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+
+    let filter = AuditFilter {
+        game_key_id: gamekey_id,
+        game_id,
+        user_id: user.map(|x| x.id.get()),
+    };
+
+    paginate::paginate_audit_events(ctx, filter).await?;
+
+    Ok(())
+}
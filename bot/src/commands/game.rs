@@ -1,23 +1,39 @@
-use std::path::Path;
-
-use crate::{commands::autocomplete_game, paginate, steam, Data, PoiseError};
+use crate::{
+    cache::AppMatch,
+    commands::{
+        autocomplete_game, autocomplete_steam_app, country_code, did_you_mean, find_game,
+        find_price,
+        gamekey::{KeystateCoice, PlatformCoice},
+    },
+    exporter::{self, ExportColumn, ExportFormat},
+    paginate, refresh,
+    steam::App,
+    store, Data, PoiseError,
+};
 use chrono::Utc;
-use entity::game;
+use entity::{
+    game,
+    sea_orm_active_enums::{AuditAction, KeyState},
+};
 use gemuki_service::{
-    mutation::{GameKeyMutation, GameMutation},
-    query::{GameKeyQuery, GameQuery},
+    mutation::{AuditMutation, GameMutation},
+    query::{GameKeyQuery, GameQuery, PlatformQuery},
 };
-use log::{error, info, warn};
-use migration::sea_orm::DbConn;
+use log::{error, warn};
 use poise::{
-    serenity_prelude::{Color, CreateAttachment, CreateEmbed},
+    serenity_prelude::{
+        Color, ComponentInteractionCollector, ComponentInteractionDataKind, CreateActionRow,
+        CreateAttachment, CreateEmbed, CreateInteractionResponse, CreateInteractionResponseMessage,
+        CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption,
+    },
     CreateReply,
 };
-use tempfile::tempfile;
-use tokio::{fs, io::AsyncWriteExt};
 
 type Context<'a> = poise::Context<'a, Data, PoiseError>;
 
+/// Number of steam apps offered when a title is ambiguous.
+const MAX_STEAM_CANDIDATES: usize = 10;
+
 /// A command for managing games.
 #[poise::command(
     slash_command,
@@ -55,15 +71,24 @@ pub async fn details(
 ) -> Result<(), PoiseError> {
     let db = &ctx.data().conn;
 
-    if let Some(game) = GameQuery::get_by_title(db, &game).await? {
+    if let Some(game) = find_game(ctx, &game).await? {
         let key_count = GameKeyQuery::count_by_game(db, game.id).await?;
 
         let embed = CreateEmbed::new()
             .colour(Color::DARK_BLUE)
-            .title(game.title)
-            .description(game.description.unwrap_or("None".to_owned()))
+            .title(&game.title)
+            .description(game.description.as_deref().unwrap_or("None"))
             .field("Id", format!("{}", game.id), true)
             .field("Keys", key_count.to_string(), true);
+        let embed = match find_price(ctx, &game).await {
+            Some(price) => embed.field("Price", price, true),
+            None => embed,
+        };
+        let embed = store::add_store_fields(embed, &game);
+        let embed = match &game.metadata {
+            Some(metadata) => paginate::add_metadata_fields(embed, metadata),
+            None => embed,
+        };
         let embed = match game.image_link {
             Some(link) => embed.image(link),
             None => embed,
@@ -71,8 +96,12 @@ pub async fn details(
 
         ctx.send(CreateReply::default().embed(embed)).await?;
     } else {
-        ctx.reply(format!("Could not find a game with the title '{}'.", game))
-            .await?;
+        ctx.reply(format!(
+            "Could not find a game with the title '{}'.{}",
+            game,
+            did_you_mean(ctx, &game).await
+        ))
+        .await?;
     }
 
     Ok(())
@@ -90,14 +119,20 @@ pub async fn add(
 ) -> Result<(), PoiseError> {
     let db = &ctx.data().conn;
 
-    if let Some(_) = GameQuery::get_by_title(db, &title).await? {
+    if GameQuery::get_by_title(db, &title).await?.is_some() {
         ctx.reply("Could not add game because it already exists.")
             .await?;
         return Ok(());
     };
 
+    if GameQuery::get_trashed_by_title(db, &title).await?.is_some() {
+        ctx.reply("Could not add game because it is in the trash. Use `/trash restore` instead.")
+            .await?;
+        return Ok(());
+    };
+
     if let Some(link) = &image_link {
-        if let Err(why) = url::Url::parse(&link) {
+        if let Err(why) = url::Url::parse(link) {
             error!("Invalid url: {}", why);
             ctx.reply("The url you provided is invalid.").await?;
             return Ok(());
@@ -108,15 +143,26 @@ pub async fn add(
         id: 0,
         title,
         description,
-        image_link: image_link,
+        image_link,
         create_date: Utc::now(),
         create_user_id: ctx.author().id.into(),
         modify_date: None,
         modify_user_id: None,
+        deleted_date: None,
+        deleted_user_id: None,
+        metadata: None,
+        steam_appid: None,
+        gog_id: None,
+        epic_slug: None,
     };
 
     let message = match GameMutation::create(db, model).await {
-        Ok(_) => "Successfully added game.",
+        Ok(game) => {
+            AuditMutation::log_game(db, AuditAction::Create, ctx.author().id.get(), &game, None)
+                .await?;
+
+            "Successfully added game."
+        }
         Err(why) => {
             error!("Could not insert new game because of '{}'.", why);
             "Could not add game because of an internal server error."
@@ -139,35 +185,57 @@ pub async fn add(
 #[poise::command(slash_command, owners_only)]
 pub async fn quicksetup(
     ctx: Context<'_>,
-    #[description = "Title of the game you want to add."] title: String,
+    #[description = "Title of the game you want to add."]
+    #[autocomplete = "autocomplete_steam_app"]
+    title: String,
 ) -> Result<(), PoiseError> {
     let db = &ctx.data().conn;
 
-    if let Some(_) = GameQuery::get_by_title(db, &title).await? {
+    if GameQuery::get_by_title(db, &title).await?.is_some() {
         ctx.reply("Could not add game because it already exists.")
             .await?;
         return Ok(());
     };
 
-    let mut cache = ctx.data().steam_app_cache.lock().await;
-    cache.update().await;
+    if GameQuery::get_trashed_by_title(db, &title).await?.is_some() {
+        ctx.reply("Could not add game because it is in the trash. Use `/trash restore` instead.")
+            .await?;
+        return Ok(());
+    };
 
-    let app = match cache.find_by_name(&title) {
-        Some(app) => app,
-        None => {
+    let app_match = ctx
+        .data()
+        .steam_app_cache
+        .lock()
+        .await
+        .match_title(&title, MAX_STEAM_CANDIDATES);
+
+    let appid = match app_match {
+        AppMatch::Found(appid) => appid,
+        AppMatch::NotFound => {
             ctx.reply("The title you search for does not exist on steam.")
                 .await?;
             return Ok(());
         }
+        AppMatch::Ambiguous(candidates) => match pick_steam_app(ctx, &candidates).await? {
+            Some(appid) => appid,
+            None => return Ok(()),
+        },
     };
 
-    let app_details = match steam::get_app_details(app.appid()).await {
+    let country_code = country_code(ctx).await?;
+    let app_details = match ctx
+        .data()
+        .steam_client
+        .get_app_details(appid, &country_code)
+        .await
+    {
+        Ok(Some(details)) => details,
         Ok(None) => {
             ctx.reply("Could not retrieve game data from steam.")
                 .await?;
             return Ok(());
         }
-        Ok(details) => details.unwrap(),
         Err(why) => {
             error!("Could not retrieve game data from steam, {:?}", why);
             ctx.reply("Could not retrieve game data from steam.")
@@ -176,19 +244,42 @@ pub async fn quicksetup(
         }
     };
 
-    let model = game::Model {
-        id: 0,
-        title: app_details.name.clone(),
-        description: Some(app_details.short_description.clone()),
-        image_link: Some(app_details.header_image.clone()),
-        create_date: Utc::now(),
-        create_user_id: ctx.author().id.into(),
-        modify_date: None,
-        modify_user_id: None,
+    if GameQuery::get_by_title(db, &app_details.name)
+        .await?
+        .is_some()
+    {
+        ctx.reply(format!(
+            "Could not add game because `{}` already exists.",
+            app_details.name
+        ))
+        .await?;
+        return Ok(());
     };
 
+    if GameQuery::get_trashed_by_title(db, &app_details.name)
+        .await?
+        .is_some()
+    {
+        ctx.reply(format!(
+            "Could not add game because `{}` is in the trash. Use `/trash restore` instead.",
+            app_details.name
+        ))
+        .await?;
+        return Ok(());
+    };
+
+    let model = app_details.to_game(ctx.author().id.get());
+
     let message = match GameMutation::create(db, model).await {
-        Ok(_) => "Successfully added game.",
+        Ok(game) => {
+            AuditMutation::log_game(db, AuditAction::Create, ctx.author().id.get(), &game, None)
+                .await?;
+            if let Err(why) = refresh::record_price(db, appid, &app_details, &country_code).await {
+                warn!("Could not store the price of app {appid} because of '{why}'.");
+            }
+
+            "Successfully added game."
+        }
         Err(why) => {
             error!("Could not insert new game because of '{}'.", why);
             "Could not add game because of an internal server error."
@@ -207,7 +298,73 @@ pub async fn quicksetup(
     Ok(())
 }
 
+/// Lets the user pick one of several steam apps. Returns `None` if nothing has been picked in time.
+async fn pick_steam_app(ctx: Context<'_>, candidates: &[App]) -> Result<Option<u32>, PoiseError> {
+    let custom_id = format!("{}steam-app", ctx.id());
+
+    let options = candidates
+        .iter()
+        .map(|x| {
+            let label = x.name().chars().take(100).collect::<String>();
+            CreateSelectMenuOption::new(label, x.appid().to_string())
+                .description(format!("Steam app {}", x.appid()))
+        })
+        .collect();
+    let menu = CreateSelectMenu::new(&custom_id, CreateSelectMenuKind::String { options })
+        .placeholder("Select the game");
+
+    let reply = ctx
+        .send(
+            CreateReply::default()
+                .content("Found several games on steam, please pick one:")
+                .components(vec![CreateActionRow::SelectMenu(menu)]),
+        )
+        .await?;
+
+    let press = ComponentInteractionCollector::new(ctx)
+        .author_id(ctx.author().id)
+        .filter(move |press| press.data.custom_id == custom_id)
+        .timeout(std::time::Duration::from_secs(120))
+        .await;
+
+    let press = match press {
+        Some(p) => p,
+        None => {
+            reply
+                .edit(
+                    ctx,
+                    CreateReply::default()
+                        .content("No game has been selected.")
+                        .components(Vec::new()),
+                )
+                .await?;
+            return Ok(None);
+        }
+    };
+
+    let appid = match &press.data.kind {
+        ComponentInteractionDataKind::StringSelect { values } => {
+            values.first().and_then(|x| x.parse().ok())
+        }
+        _ => None,
+    };
+
+    press
+        .create_response(
+            ctx.serenity_context(),
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .content("Setting up the selected game.")
+                    .components(Vec::new()),
+            ),
+        )
+        .await?;
+
+    Ok(appid)
+}
+
 /// Edits details of a game.
+#[allow(clippy::too_many_arguments)]
 #[poise::command(slash_command, owners_only)]
 pub async fn edit(
     ctx: Context<'_>,
@@ -217,18 +374,22 @@ pub async fn edit(
     #[description = "Title of the game you want to edit."] title: Option<String>,
     #[description = "Description of the game you want to edit."] description: Option<String>,
     #[description = "Picture link for the image of the game."] image_link: Option<String>,
+    #[description = "Steam app id of the game."] steam_appid: Option<u32>,
+    #[description = "GOG id of the game, as in its store url."] gog_id: Option<String>,
+    #[description = "Epic Games slug of the game, as in its store url."] epic_slug: Option<String>,
 ) -> Result<(), PoiseError> {
     let db = &ctx.data().conn;
 
     if let Some(link) = &image_link {
-        if let Err(why) = url::Url::parse(&link) {
+        if let Err(why) = url::Url::parse(link) {
             error!("Invalid url: {}", why);
             ctx.reply("The url you provided is invalid.").await?;
             return Ok(());
         }
     }
 
-    if let Some(game) = GameQuery::get_by_title(db, &game).await? {
+    if let Some(game) = find_game(ctx, &game).await? {
+        let previous = game.clone();
         let model = game::Model {
             id: game.id,
             title: title.unwrap_or(game.title),
@@ -238,10 +399,28 @@ pub async fn edit(
             create_user_id: game.create_user_id,
             modify_date: Some(Utc::now()),
             modify_user_id: Some(ctx.author().id.into()),
+            deleted_date: game.deleted_date,
+            deleted_user_id: game.deleted_user_id,
+            metadata: game.metadata,
+            steam_appid: steam_appid.map(i64::from).or(game.steam_appid),
+            gog_id: gog_id.or(game.gog_id),
+            epic_slug: epic_slug.or(game.epic_slug),
         };
 
         let message = match GameMutation::update(db, model).await {
-            Ok(_) => "Successfully updated game.",
+            Ok(Some(updated)) => {
+                AuditMutation::log_game(
+                    db,
+                    AuditAction::Update,
+                    ctx.author().id.get(),
+                    &updated,
+                    Some(&previous),
+                )
+                .await?;
+
+                "Successfully updated game."
+            }
+            Ok(None) => "Could not find the game to update.",
             Err(why) => {
                 error!("Could not update game because of '{}'.", why);
                 "Could not update the game because of an internal error."
@@ -257,14 +436,18 @@ pub async fn edit(
 
         ctx.reply(message).await?;
     } else {
-        ctx.reply(format!("Could not find a game with title '{}'.", game))
-            .await?;
+        ctx.reply(format!(
+            "Could not find a game with title '{}'.{}",
+            game,
+            did_you_mean(ctx, &game).await
+        ))
+        .await?;
     }
 
     Ok(())
 }
 
-/// Removes a game entry. Use on own risk as it also clears KEYs connected to the game.
+/// Moves a game entry and all KEYs connected to it into the trash. Use `/trash restore` to undo.
 #[poise::command(slash_command, owners_only)]
 pub async fn remove(
     ctx: Context<'_>,
@@ -274,67 +457,116 @@ pub async fn remove(
 ) -> Result<(), PoiseError> {
     let db = &ctx.data().conn;
 
-    if let Some(game) = GameQuery::get_by_title(db, &game).await? {
-        let deleted_keys = GameKeyMutation::delete_by_game(db, game.id).await?;
-        let deleted_games = GameMutation::delete(db, game.id).await?;
+    if let Some(game) = find_game(ctx, &game).await? {
+        let (trashed_keys, trashed_games) = {
+            let cipher = ctx.data().key_cipher.read().await;
+            let game_keys = GameKeyQuery::get_all_of_game(db, &cipher, game.id).await?;
+
+            let trashed = GameMutation::trash(db, game.id, ctx.author().id.get()).await?;
+
+            for game_key in &game_keys {
+                AuditMutation::log_game_key(
+                    db,
+                    &cipher,
+                    AuditAction::Delete,
+                    ctx.author().id.get(),
+                    game_key,
+                    Some(game_key),
+                )
+                .await?;
+            }
+            trashed
+        };
+        AuditMutation::log_game(
+            db,
+            AuditAction::Delete,
+            ctx.author().id.get(),
+            &game,
+            Some(&game),
+        )
+        .await?;
+
+        ctx.data()
+            .game_title_cache
+            .lock()
+            .await
+            .force_update(db)
+            .await;
 
         ctx.reply(format!(
-            "Deleted `{}` keys and `{}` games.",
-            deleted_keys.rows_affected, deleted_games.rows_affected
+            "Moved `{}` keys and `{}` games to the trash.",
+            trashed_keys, trashed_games
         ))
         .await?;
 
-        warn!("Deleted game with title '{}'.", game.title);
+        warn!("Trashed game with title '{}'.", game.title);
     } else {
-        ctx.reply(format!("No game with title {} found.", game))
-            .await?;
-    }
-
-    Ok(())
-}
-
-/// Exports a list of all games which have unused keys.
-#[poise::command(slash_command, owners_only)]
-pub async fn export(ctx: Context<'_>) -> Result<(), PoiseError> {
-    let db = &ctx.data().conn;
-
-    let file_id: uuid::Uuid = uuid::Uuid::new_v4();
-    let file_name = format!("games_{}.csv", file_id);
-
-    write_to_file(db, ctx.author().id.get(), &file_name).await?;
-
-    {
-        let file = fs::File::open(&file_name).await?;
-        let attachment = CreateAttachment::file(&file, &file_name).await?;
-
-        ctx.send(
-            CreateReply::default()
-                .content(format!("Found games:"))
-                .attachment(attachment),
-        )
+        ctx.reply(format!(
+            "No game with title {} found.{}",
+            game,
+            did_you_mean(ctx, &game).await
+        ))
         .await?;
     }
 
-    fs::remove_file(&file_name).await?;
-
     Ok(())
 }
 
-async fn write_to_file(
-    db: &DbConn,
-    user_id: u64,
-    path: impl AsRef<Path>,
+/// Exports your games with keys as csv, json or markdown table.
+#[allow(clippy::too_many_arguments)]
+#[poise::command(slash_command, owners_only)]
+pub async fn export(
+    ctx: Context<'_>,
+    #[description = "Format of the export. Defaults to csv."] format: Option<ExportFormat>,
+    #[description = "Only export keys of this platform."] platform: Option<PlatformCoice>,
+    #[description = "Only export keys in this state. Defaults to unused."] keystate: Option<
+        KeystateCoice,
+    >,
+    #[description = "Adds the platforms of the keys."] show_platform: Option<bool>,
+    #[description = "Adds the number of keys."] show_key_count: Option<bool>,
+    #[description = "Adds the earliest expiration date of the keys."] show_expiration: Option<bool>,
+    #[description = "Adds a store link."] show_store_link: Option<bool>,
 ) -> Result<(), PoiseError> {
-    let mut file = fs::File::create(&path).await?;
-
-    file.write(b"game_title;create_date\n").await?;
-
-    for game in GameQuery::get_all_games_with_keys(db, user_id).await? {
-        file.write(format!("{};{}\n", game.title, game.create_date).as_bytes())
+    let db = &ctx.data().conn;
+    let format = format.unwrap_or(ExportFormat::Csv);
+
+    let platform_id = match platform {
+        Some(platform) => match PlatformQuery::get_by_name(db, &platform.to_string()).await? {
+            Some(p) => Some(p.id),
+            None => {
+                ctx.reply(format!("The platform `{}` does not exist.", platform))
+                    .await?;
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+    let keystate = keystate.map(KeyState::from).unwrap_or(KeyState::Unused);
+
+    let columns = [
+        (show_platform, ExportColumn::Platform),
+        (show_key_count, ExportColumn::KeyCount),
+        (show_expiration, ExportColumn::Expiration),
+        (show_store_link, ExportColumn::StoreLink),
+    ]
+    .into_iter()
+    .filter(|(show, _)| show.unwrap_or_default())
+    .map(|(_, column)| column)
+    .collect::<Vec<ExportColumn>>();
+
+    let keys =
+        GameKeyQuery::get_all_of_user(db, ctx.author().id.get(), platform_id, Some(keystate))
             .await?;
-    }
-
-    file.flush().await?;
+    let rows = exporter::build_rows(keys);
+    let data = exporter::export(&rows, &columns, format)?;
+    let file_name = format!("games.{}", format.file_extension());
+
+    ctx.send(
+        CreateReply::default()
+            .content(format!("Found `{}` games:", rows.len()))
+            .attachment(CreateAttachment::bytes(data, file_name)),
+    )
+    .await?;
 
     Ok(())
 }
@@ -1,31 +1,63 @@
-use chrono::{DateTime, Utc};
-use entity::game_key;
+use std::fmt::Display;
+
+use chrono::{DateTime, Duration, Utc};
+use entity::{
+    game_key,
+    sea_orm_active_enums::{AuditAction, KeyState},
+};
 use gemuki_service::{
-    mutation::GameKeyMutation,
+    import::{self, KeyImportOutcome},
+    mutation::{AuditMutation, GameKeyMutation},
+    policy::{ClaimDenial, ClaimOutcome},
     query::{GameKeyModel, GameKeyQuery, GameQuery, PlatformQuery},
 };
 use log::{error, warn};
-use poise::{serenity_prelude::CreateEmbed, CreateReply};
-use rand::Rng;
+use poise::{
+    serenity_prelude::{Attachment, CreateAttachment, CreateEmbed, User},
+    CreateReply,
+};
+use rand::seq::SliceRandom;
 
-use crate::{commands::autocomplete_game, paginate, Data};
+use crate::{
+    commands::{autocomplete_game, did_you_mean, find_game, pool_member},
+    gift,
+    importer::{self, PreparedImport},
+    paginate, store, wishlist, Data,
+};
 
 type PoiseError = Box<dyn std::error::Error + Send + Sync>;
 type Context<'a> = poise::Context<'a, Data, PoiseError>;
 
+/// Maximum size of an imported file in bytes.
+const MAX_IMPORT_SIZE: u32 = 1024 * 1024;
+/// Maximum length of a discord message.
+const MAX_MESSAGE_LENGTH: usize = 2000;
+
 #[derive(Debug, poise::ChoiceParameter)]
 pub enum KeystateCoice {
     #[name = "Unused"]
     Unused,
     #[name = "Used"]
     Used,
+    #[name = "Reserved"]
+    Reserved,
+    #[name = "Gifted"]
+    Gifted,
+    #[name = "Invalid"]
+    Invalid,
+    #[name = "Expired"]
+    Expired,
 }
 
-impl ToString for KeystateCoice {
-    fn to_string(&self) -> String {
-        match self {
-            KeystateCoice::Unused => "Unused".to_owned(),
-            KeystateCoice::Used => "Used".to_owned(),
+impl From<KeystateCoice> for KeyState {
+    fn from(value: KeystateCoice) -> Self {
+        match value {
+            KeystateCoice::Unused => KeyState::Unused,
+            KeystateCoice::Used => KeyState::Used,
+            KeystateCoice::Reserved => KeyState::Reserved,
+            KeystateCoice::Gifted => KeyState::Gifted,
+            KeystateCoice::Invalid => KeyState::Invalid,
+            KeystateCoice::Expired => KeyState::Expired,
         }
     }
 }
@@ -42,13 +74,13 @@ pub enum PlatformCoice {
     EA,
 }
 
-impl ToString for PlatformCoice {
-    fn to_string(&self) -> String {
+impl Display for PlatformCoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            PlatformCoice::Steam => "Steam".to_owned(),
-            PlatformCoice::Epic => "Epic Games".to_owned(),
-            PlatformCoice::UPlay => "Ubisoft Connect".to_owned(),
-            PlatformCoice::EA => "EA Play".to_owned(),
+            PlatformCoice::Steam => write!(f, "Steam"),
+            PlatformCoice::Epic => write!(f, "Epic Games"),
+            PlatformCoice::UPlay => write!(f, "Ubisoft Connect"),
+            PlatformCoice::EA => write!(f, "EA Play"),
         }
     }
 }
@@ -60,11 +92,15 @@ impl ToString for PlatformCoice {
         "list",
         "details",
         "add",
+        "import",
+        "import_order",
         "remove",
         "edit",
         "claim",
         "claim_random",
-        "quickclaim"
+        "quickclaim",
+        "gift",
+        "reserve"
     )
 )]
 pub async fn gamekey(ctx: Context<'_>) -> Result<(), PoiseError> {
@@ -84,20 +120,28 @@ pub async fn list(
 ) -> Result<(), PoiseError> {
     let db = &ctx.data().conn;
 
-    let game = match GameQuery::get_by_title(db, &game).await? {
+    let game = match find_game(ctx, &game).await? {
         Some(g) => g,
         None => {
-            ctx.reply("Could not find game.").await?;
+            ctx.reply(format!(
+                "Could not find game.{}",
+                did_you_mean(ctx, &game).await
+            ))
+            .await?;
             return Ok(());
         }
     };
-    let game_keys = GameKeyQuery::get_all_by_game(db, game.id, ctx.author().id.get()).await?;
+    let member = pool_member(ctx).await;
+    let game_keys = {
+        let cipher = ctx.data().key_cipher.read().await;
+        GameKeyQuery::get_all_by_game(db, &cipher, game.id, &member).await?
+    };
 
-    let game_keys = match keystate {
-        Some(choice) => game_keys
+    let game_keys = match keystate.map(KeyState::from) {
+        Some(keystate) => game_keys
             .iter()
-            .filter(|x| x.game_key().keystate == choice.to_string())
-            .map(|x| x.clone())
+            .filter(|x| x.game_key().keystate == keystate)
+            .cloned()
             .collect::<Vec<GameKeyModel>>(),
         None => game_keys,
     };
@@ -105,8 +149,8 @@ pub async fn list(
     let game_keys = match platform {
         Some(choice) => game_keys
             .iter()
-            .filter(|x| x.game_key().keystate == choice.to_string())
-            .map(|x| x.clone())
+            .filter(|x| x.platform().name == choice.to_string())
+            .cloned()
             .collect::<Vec<GameKeyModel>>(),
         None => game_keys,
     };
@@ -128,7 +172,12 @@ pub async fn details(
 ) -> Result<(), PoiseError> {
     let db = &ctx.data().conn;
 
-    let game_key = match GameKeyQuery::get_one(db, gamekey_id, ctx.author().id.get()).await? {
+    let member = pool_member(ctx).await;
+    let game_key = {
+        let cipher = ctx.data().key_cipher.read().await;
+        GameKeyQuery::get_one_accessible(db, &cipher, gamekey_id, &member).await?
+    };
+    let game_key = match game_key {
         Some(g) => g,
         None => {
             ctx.say(format!("The gamekey `{}` does not exist.", gamekey_id))
@@ -158,9 +207,9 @@ pub async fn details(
 
     let embed = CreateEmbed::new()
         .title(format!("{} Key-Id {}", game.title, game_key.id))
-        .description(game.description.unwrap_or("None".to_owned()))
-        .field("Platform", platform.name, true)
-        .field("State", game_key.keystate, true)
+        .description(game.description.as_deref().unwrap_or("None"))
+        .field("Platform", &platform.name, true)
+        .field("State", game_key.keystate.to_string(), true)
         .field(
             "Expiration date",
             game_key
@@ -184,11 +233,24 @@ pub async fn details(
             game_key.modify_user_id.unwrap_or(0).to_string(),
             false,
         );
-
-    let embed = match game_key.page_link {
-        Some(link) => embed.url(link),
+    let embed = match game_key.recipient_user_id {
+        Some(recipient) => embed.field("Gifted to", format!("<@{recipient}>"), false),
+        None => embed,
+    };
+    let embed = match (game_key.reserved_user_id, game_key.reserved_until) {
+        (Some(holder), Some(until)) => embed.field(
+            "Reserved for",
+            format!("<@{holder}> until <t:{}:f>", until.timestamp()),
+            false,
+        ),
+        _ => embed,
+    };
+    let embed = match game_key.source {
+        Some(source) => embed.field("Source", source, false),
         None => embed,
     };
+    let embed = store::add_key_store_fields(embed, &game, &platform.name, game_key.page_link);
+
     let embed = match game.image_link {
         Some(link) => embed.image(link),
         None => embed,
@@ -200,6 +262,7 @@ pub async fn details(
 }
 
 /// Adds a gamekey for to a game.
+#[allow(clippy::too_many_arguments)]
 #[poise::command(slash_command, dm_only)]
 pub async fn add(
     ctx: Context<'_>,
@@ -216,12 +279,16 @@ pub async fn add(
 ) -> Result<(), PoiseError> {
     let db = &ctx.data().conn;
 
-    let game = match GameQuery::get_by_title(db, &game).await? {
+    let game = match find_game(ctx, &game).await? {
         Some(g) => g,
         None => {
             ctx.send(
                 CreateReply::default()
-                    .content(format!("The game `{}` does not exist.", game))
+                    .content(format!(
+                        "The game `{}` does not exist.{}",
+                        game,
+                        did_you_mean(ctx, &game).await
+                    ))
                     .ephemeral(true),
             )
             .await?;
@@ -233,10 +300,7 @@ pub async fn add(
         None => {
             ctx.send(
                 CreateReply::default()
-                    .content(format!(
-                        "The platform `{}` does not exist.",
-                        platform.to_string()
-                    ))
+                    .content(format!("The platform `{}` does not exist.", platform))
                     .ephemeral(true),
             )
             .await?;
@@ -249,18 +313,56 @@ pub async fn add(
         game_id: game.id,
         platform_id: platform.id,
         value,
-        keystate: keystate.to_string(),
-        page_link: page_link,
-        notes: notes,
+        keystate: keystate.into(),
+        page_link,
+        notes,
         create_date: Utc::now(),
         create_user_id: ctx.author().id.into(),
         modify_date: None,
         modify_user_id: None,
         expiration_date: expiration_date.map(|x| x.naive_utc()),
+        deleted_date: None,
+        deleted_user_id: None,
+        recipient_user_id: None,
+        source: None,
+        pool_id: None,
+        reserved_user_id: None,
+        reserved_until: None,
+    };
+
+    let created = {
+        let cipher = ctx.data().key_cipher.read().await;
+        match GameKeyMutation::create(db, &cipher, model).await {
+            Ok(game_key) => {
+                AuditMutation::log_game_key(
+                    db,
+                    &cipher,
+                    AuditAction::Create,
+                    ctx.author().id.get(),
+                    &game_key,
+                    None,
+                )
+                .await?;
+                Ok(game_key)
+            }
+            Err(why) => Err(why),
+        }
     };
+    let message = match created {
+        Ok(game_key) => {
+            if let Err(why) = wishlist::notify_wishing_users(
+                ctx.http(),
+                db,
+                ctx.author().id.get(),
+                std::slice::from_ref(&game_key),
+            )
+            .await
+            {
+                error!("Could not notify wishing users because of '{why}'.");
+            }
 
-    let message = match GameKeyMutation::create(db, model).await {
-        Ok(_) => "Successfully added key.",
+            "Successfully added key."
+        }
         Err(why) => {
             error!("Could not insert new game because of '{}'.", why);
             "Could not add game because of an internal server error."
@@ -273,7 +375,192 @@ pub async fn add(
     Ok(())
 }
 
-/// Removes a gamekey from a game. Use at own risk as the key gets deleted.
+/// Imports many keys at once from a csv file.
+///
+/// The file needs the columns `game_title, platform, key, page_link, notes, expiration_date`.
+#[poise::command(slash_command, dm_only)]
+pub async fn import(
+    ctx: Context<'_>,
+    #[description = "Csv file with the columns game_title, platform, key, page_link, notes, expiration_date."]
+    file: Attachment,
+    #[description = "Creates games which do not exist yet."] create_missing: Option<bool>,
+) -> Result<(), PoiseError> {
+    if file.size > MAX_IMPORT_SIZE {
+        ctx.send(
+            CreateReply::default()
+                .content("The file is too large. Please split it into smaller files.")
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    ctx.defer_ephemeral().await?;
+
+    let data = file.download().await?;
+    let (rows, failed) = import::parse_csv(&data);
+
+    import_rows(
+        ctx,
+        PreparedImport {
+            rows,
+            failed,
+            new_games: Vec::new(),
+        },
+        create_missing.unwrap_or_default(),
+    )
+    .await
+}
+
+/// Imports the keys of a saved Humble Bundle order json or Fanatical order export.
+#[poise::command(
+    slash_command,
+    dm_only,
+    name_localized("de", "import-order"),
+    name_localized("en-US", "import-order")
+)]
+pub async fn import_order(
+    ctx: Context<'_>,
+    #[description = "Saved order json of Humble Bundle or Fanatical."] file: Attachment,
+) -> Result<(), PoiseError> {
+    if file.size > MAX_IMPORT_SIZE {
+        ctx.send(
+            CreateReply::default()
+                .content("The file is too large. Please split it into smaller files.")
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    ctx.defer_ephemeral().await?;
+
+    let data = file.download().await?;
+    let keys = match importer::parse_order(&data) {
+        Ok(k) => k,
+        Err(why) => {
+            ctx.send(
+                CreateReply::default()
+                    .content(format!("Could not read the order. {why}"))
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let prepared = importer::prepare_import(ctx.data(), ctx.author().id.get(), keys).await?;
+
+    import_rows(ctx, prepared, false).await
+}
+
+/// Imports prepared rows and replies with a report of every row.
+async fn import_rows(
+    ctx: Context<'_>,
+    prepared: PreparedImport,
+    create_missing: bool,
+) -> Result<(), PoiseError> {
+    let db = &ctx.data().conn;
+    let cipher = ctx.data().key_cipher.read().await;
+
+    let report = GameKeyMutation::import(
+        db,
+        &cipher,
+        ctx.author().id.get(),
+        prepared.rows,
+        create_missing,
+        prepared.new_games,
+    )
+    .await;
+    let mut report = match report {
+        Ok(r) => r,
+        Err(why) => {
+            error!("Could not import keys because of '{}'.", why);
+            drop(cipher);
+            ctx.send(
+                CreateReply::default()
+                    .content("Could not import keys because of an internal server error. No keys have been added.")
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+    report.results.extend(prepared.failed);
+    report.results.sort_by_key(|x| x.line);
+
+    for game in &report.created_games {
+        AuditMutation::log_game(db, AuditAction::Create, ctx.author().id.get(), game, None).await?;
+    }
+
+    let mut created_keys = Vec::new();
+    for result in &report.results {
+        if let KeyImportOutcome::Created(game_key) = &result.outcome {
+            AuditMutation::log_game_key(
+                db,
+                &cipher,
+                AuditAction::Create,
+                ctx.author().id.get(),
+                game_key,
+                None,
+            )
+            .await?;
+            created_keys.push(game_key.as_ref().clone());
+        }
+    }
+    drop(cipher);
+
+    if let Err(why) =
+        wishlist::notify_wishing_users(ctx.http(), db, ctx.author().id.get(), &created_keys).await
+    {
+        error!("Could not notify wishing users because of '{why}'.");
+    }
+
+    if !report.created_games.is_empty() {
+        ctx.data()
+            .game_title_cache
+            .lock()
+            .await
+            .force_update(db)
+            .await;
+    }
+
+    let summary = format!(
+        "Created `{}` keys, skipped `{}` duplicates, `{}` failed. Created `{}` games.",
+        report.count_created(),
+        report.count_duplicates(),
+        report.count_failed(),
+        report.created_games.len()
+    );
+    let details = report
+        .results
+        .iter()
+        .map(|x| {
+            let outcome = match &x.outcome {
+                KeyImportOutcome::Created(k) => format!("created key `{}`", k.id),
+                KeyImportOutcome::Duplicate => "skipped, the key already exists".to_owned(),
+                KeyImportOutcome::Failed(why) => format!("failed, {why}"),
+            };
+            format!("Line {} `{}`: {}", x.line, x.game_title, outcome)
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let content = format!("{summary}\n{details}");
+    let reply = if content.len() > MAX_MESSAGE_LENGTH {
+        CreateReply::default()
+            .content(summary)
+            .attachment(CreateAttachment::bytes(details, "import_report.txt"))
+    } else {
+        CreateReply::default().content(content)
+    };
+
+    ctx.send(reply.ephemeral(true)).await?;
+
+    Ok(())
+}
+
+/// Moves a gamekey into the trash. Use `/trash restore` to undo.
 #[poise::command(slash_command)]
 pub async fn remove(
     ctx: Context<'_>,
@@ -281,17 +568,40 @@ pub async fn remove(
 ) -> Result<(), PoiseError> {
     let db = &ctx.data().conn;
 
-    let deleted_keys = GameKeyMutation::delete(db, gamekey_id, ctx.author().id.get()).await?;
+    let trashed_keys = {
+        let cipher = ctx.data().key_cipher.read().await;
+        let game_key =
+            GameKeyQuery::get_one(db, &cipher, gamekey_id, ctx.author().id.get()).await?;
+        let trashed_keys = GameKeyMutation::trash(db, gamekey_id, ctx.author().id.get()).await?;
+
+        if let Some(game_key) = game_key.filter(|_| trashed_keys.rows_affected > 0) {
+            AuditMutation::log_game_key(
+                db,
+                &cipher,
+                AuditAction::Delete,
+                ctx.author().id.get(),
+                &game_key,
+                Some(&game_key),
+            )
+            .await?;
+        }
 
-    ctx.reply(format!("Deleted `{}` keys.", deleted_keys.rows_affected))
-        .await?;
+        trashed_keys
+    };
+
+    ctx.reply(format!(
+        "Moved `{}` keys to the trash.",
+        trashed_keys.rows_affected
+    ))
+    .await?;
 
-    warn!("Deleted game with id '{gamekey_id}'.");
+    warn!("Trashed gamekey with id '{gamekey_id}'.");
 
     Ok(())
 }
 
 /// Edits the details of a game key.
+#[allow(clippy::too_many_arguments)]
 #[poise::command(slash_command)]
 pub async fn edit(
     ctx: Context<'_>,
@@ -310,7 +620,7 @@ pub async fn edit(
     let db = &ctx.data().conn;
 
     if let Some(page_link) = &page_link {
-        if let Err(why) = url::Url::parse(&page_link) {
+        if let Err(why) = url::Url::parse(page_link) {
             error!("Invalid url: {}", why);
 
             ctx.send(
@@ -324,14 +634,22 @@ pub async fn edit(
         }
     }
 
-    if let Some(game_key) = GameKeyQuery::get_one(db, id, ctx.author().id.get()).await? {
+    let game_key = {
+        let cipher = ctx.data().key_cipher.read().await;
+        GameKeyQuery::get_one(db, &cipher, id, ctx.author().id.get()).await?
+    };
+
+    if let Some(game_key) = game_key {
         let game_id = if let Some(game) = game {
-            match GameQuery::get_by_title(db, &game).await? {
+            match find_game(ctx, &game).await? {
                 Some(g) => g.id,
                 None => {
                     ctx.send(
                         CreateReply::default()
-                            .content("Could not find game.")
+                            .content(format!(
+                                "Could not find game.{}",
+                                did_you_mean(ctx, &game).await
+                            ))
                             .ephemeral(true),
                     )
                     .await?;
@@ -348,10 +666,7 @@ pub async fn edit(
                 None => {
                     ctx.send(
                         CreateReply::default()
-                            .content(format!(
-                                "The platform `{}` does not exist.",
-                                platform.to_string()
-                            ))
+                            .content(format!("The platform `{}` does not exist.", platform))
                             .ephemeral(true),
                     )
                     .await?;
@@ -362,12 +677,13 @@ pub async fn edit(
             game_key.platform_id
         };
 
+        let previous = game_key.clone();
         let model = game_key::Model {
             id,
-            game_id: game_id,
-            platform_id: platform_id,
+            game_id,
+            platform_id,
             value: value.unwrap_or(game_key.value),
-            keystate: keystate.map(|x| x.to_string()).unwrap_or(game_key.keystate),
+            keystate: keystate.map(KeyState::from).unwrap_or(game_key.keystate),
             page_link: page_link.or(game_key.page_link),
             notes: notes.or(game_key.notes),
             create_date: game_key.create_date,
@@ -375,10 +691,35 @@ pub async fn edit(
             modify_date: Some(Utc::now()),
             modify_user_id: Some(ctx.author().id.into()),
             expiration_date: expiration_date.map(|x| x.naive_utc()),
+            deleted_date: game_key.deleted_date,
+            deleted_user_id: game_key.deleted_user_id,
+            recipient_user_id: game_key.recipient_user_id,
+            source: game_key.source,
+            pool_id: game_key.pool_id,
+            reserved_user_id: game_key.reserved_user_id,
+            reserved_until: game_key.reserved_until,
+        };
+
+        let updated = {
+            let cipher = ctx.data().key_cipher.read().await;
+            let updated = GameKeyMutation::update(db, &cipher, model).await;
+            if let Ok(Some(updated)) = &updated {
+                AuditMutation::log_game_key(
+                    db,
+                    &cipher,
+                    AuditAction::Update,
+                    ctx.author().id.get(),
+                    updated,
+                    Some(&previous),
+                )
+                .await?;
+            }
+            updated
         };
 
-        let message = match GameKeyMutation::update(db, model).await {
-            Ok(_) => "Successfully updated gamekey.",
+        let message = match updated {
+            Ok(Some(_)) => "Successfully updated gamekey.",
+            Ok(None) => "Could not find the gamekey to update.",
             Err(why) => {
                 error!("Could not update gamekey because of '{}'.", why);
                 "Could not update the gamekey because of an internal error."
@@ -400,14 +741,19 @@ pub async fn edit(
 }
 
 /// Claims a key. Sends the key value hidden behind a spoiler into the channel.
-#[poise::command(slash_command, owners_only)]
+#[poise::command(slash_command)]
 pub async fn claim(
     ctx: Context<'_>,
     #[description = "Id of the key you want to claim."] gamekey_id: i32,
 ) -> Result<(), PoiseError> {
     let db = &ctx.data().conn;
 
-    let mut game_key = match GameKeyQuery::get_one(db, gamekey_id, ctx.author().id.get()).await? {
+    let member = pool_member(ctx).await;
+    let game_key = {
+        let cipher = ctx.data().key_cipher.read().await;
+        GameKeyQuery::get_one_accessible(db, &cipher, gamekey_id, &member).await?
+    };
+    let game_key = match game_key {
         Some(g) => g,
         None => {
             ctx.send(
@@ -420,16 +766,6 @@ pub async fn claim(
         }
     };
 
-    if game_key.keystate == "Used" {
-        ctx.send(
-            CreateReply::default()
-                .content(format!("The key `{}` is already used.", gamekey_id))
-                .ephemeral(true),
-        )
-        .await?;
-        return Ok(());
-    }
-
     if let Some(expiration_date) = game_key.expiration_date {
         if expiration_date < Utc::now().naive_utc() {
             ctx.send(
@@ -442,22 +778,44 @@ pub async fn claim(
         }
     }
 
-    let reply = CreateReply::default()
-        .content(format!("Your key: `{}`", game_key.value))
-        .ephemeral(true);
-
-    game_key.keystate = "Used".to_owned();
-    game_key.modify_date = Some(Utc::now());
-    game_key.modify_user_id = Some(ctx.author().id.into());
-
-    GameKeyMutation::update(db, game_key).await?;
+    let outcome = {
+        let cipher = ctx.data().key_cipher.read().await;
+        let policy = &ctx.data().claim_policy;
+        GameKeyMutation::claim(db, &cipher, game_key.id, &member, policy).await?
+    };
+    let game_key = match outcome {
+        ClaimOutcome::Claimed(k) => *k,
+        ClaimOutcome::Denied(denial) => {
+            ctx.send(
+                CreateReply::default()
+                    .content(denial_message(&denial))
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+        ClaimOutcome::Unavailable => {
+            ctx.send(
+                CreateReply::default()
+                    .content(format!("The key `{}` is already used.", gamekey_id))
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
 
-    ctx.send(reply).await?;
+    ctx.send(
+        CreateReply::default()
+            .content(format!("Your key: `{}`", game_key.value))
+            .ephemeral(true),
+    )
+    .await?;
     Ok(())
 }
 
 /// Claims a key from a game. Sends the key value hidden behind a spoiler into the channel.
-#[poise::command(slash_command, owners_only)]
+#[poise::command(slash_command)]
 pub async fn quickclaim(
     ctx: Context<'_>,
     #[description = "Name of the game you want to claim a key from."]
@@ -466,12 +824,15 @@ pub async fn quickclaim(
 ) -> Result<(), PoiseError> {
     let db = &ctx.data().conn;
 
-    let game_id = match GameQuery::get_by_title(db, &game).await? {
+    let game_id = match find_game(ctx, &game).await? {
         Some(g) => g.id,
         None => {
             ctx.send(
                 CreateReply::default()
-                    .content("Could not find game.")
+                    .content(format!(
+                        "Could not find game.{}",
+                        did_you_mean(ctx, &game).await
+                    ))
                     .ephemeral(true),
             )
             .await?;
@@ -479,34 +840,43 @@ pub async fn quickclaim(
         }
     };
 
-    if let Some(mut game_key) = GameKeyQuery::get_all_by_game(db, game_id, ctx.author().id.get())
-        .await?
-        .iter()
-        .filter(|x| x.game_key().keystate == "Unused")
+    let member = pool_member(ctx).await;
+    let game_keys = {
+        let cipher = ctx.data().key_cipher.read().await;
+        GameKeyQuery::get_all_by_game(db, &cipher, game_id, &member).await?
+    };
+    let mut candidates = game_keys
+        .into_iter()
+        .map(|x| x.game_key().clone())
+        .filter(|x| x.keystate == KeyState::Unused)
         .filter(|x| {
-            x.game_key().expiration_date.is_none()
-                || x.game_key().expiration_date.unwrap() > Utc::now().naive_utc()
+            x.expiration_date.is_none() || x.expiration_date.unwrap() > Utc::now().naive_utc()
         })
-        .map(|x| x.game_key().clone())
-        .next()
-    {
-        let reply = CreateReply::default()
-            .content(format!("Your key: `{}`", game_key.value))
-            .ephemeral(true);
-
-        game_key.keystate = "Used".to_owned();
-        game_key.modify_date = Some(Utc::now());
-        game_key.modify_user_id = Some(ctx.author().id.into());
-
-        GameKeyMutation::update(db, game_key).await?;
+        .collect::<Vec<game_key::Model>>();
+    // Own keys are not limited by the claim policy, so they are used up first.
+    candidates.sort_by_key(|x| x.create_user_id != member.user_id as i64);
+
+    // Another claim may win the race for a key, so fall through to the next candidate.
+    let policy = &ctx.data().claim_policy;
+    for candidate in candidates {
+        let outcome = {
+            let cipher = ctx.data().key_cipher.read().await;
+            GameKeyMutation::claim(db, &cipher, candidate.id, &member, policy).await?
+        };
+        let content = match outcome {
+            ClaimOutcome::Claimed(game_key) => format!("Your key: `{}`", game_key.value),
+            ClaimOutcome::Denied(denial) => denial_message(&denial),
+            ClaimOutcome::Unavailable => continue,
+        };
 
-        ctx.send(reply).await?;
+        ctx.send(CreateReply::default().content(content).ephemeral(true))
+            .await?;
         return Ok(());
     }
 
     ctx.send(
         CreateReply::default()
-            .content(format!("No unused keys for this game found."))
+            .content("No unused keys for this game found.")
             .ephemeral(true),
     )
     .await?;
@@ -517,33 +887,123 @@ pub async fn quickclaim(
 /// Claims a key. Sends the key value hidden behind a spoiler into the channel.
 #[poise::command(
     slash_command,
-    owners_only,
     name_localized("de", "claim-random"),
     name_localized("en-US", "claim-random")
 )]
 pub async fn claim_random(ctx: Context<'_>) -> Result<(), PoiseError> {
     let db = &ctx.data().conn;
 
-    let gamekeys = GameKeyQuery::get_all_ids(db, ctx.author().id.get()).await?;
+    let member = pool_member(ctx).await;
+    let mut gamekeys = GameKeyQuery::get_all_ids(db, &member).await?;
+    gamekeys.shuffle(&mut rand::rng());
+
+    // Another claim may win the race for a key, so fall through to the next one.
+    let policy = &ctx.data().claim_policy;
+    let mut denial = None;
+    for gamekey_id in gamekeys {
+        let outcome = {
+            let cipher = ctx.data().key_cipher.read().await;
+            GameKeyMutation::claim(db, &cipher, gamekey_id, &member, policy).await?
+        };
+        let game_key = match outcome {
+            ClaimOutcome::Claimed(k) => *k,
+            ClaimOutcome::Denied(d) if d.blocks_all() => {
+                denial = Some(d);
+                break;
+            }
+            ClaimOutcome::Denied(d) => {
+                denial = Some(d);
+                continue;
+            }
+            ClaimOutcome::Unavailable => continue,
+        };
+
+        let title = GameQuery::get_one(db, game_key.game_id)
+            .await?
+            .map(|x| x.title)
+            .unwrap_or("Unknown game".to_owned());
 
-    if gamekeys.is_empty() {
         ctx.send(
             CreateReply::default()
-                .content("No more gamekeys are available.")
+                .content(format!("Your key: `{}` for `{}`", game_key.value, title))
                 .ephemeral(true),
         )
         .await?;
+        return Ok(());
+    }
 
+    if let Some(denial) = denial {
+        ctx.send(
+            CreateReply::default()
+                .content(denial_message(&denial))
+                .ephemeral(true),
+        )
+        .await?;
         return Ok(());
     }
 
-    let random_number = {
-        let mut rng = rand::rng();
-        rng.random_range(0..gamekeys.len())
-    };
-    let gamekey_id = gamekeys[random_number];
+    ctx.send(
+        CreateReply::default()
+            .content("No more gamekeys are available.")
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Gifts a key to another user. The user can accept the gift to get the key via DM.
+#[poise::command(slash_command)]
+pub async fn gift(
+    ctx: Context<'_>,
+    #[description = "Id of the key you want to gift."] gamekey_id: i32,
+    #[description = "User you want to gift the key to."] user: User,
+) -> Result<(), PoiseError> {
+    let content = gift::offer_key(
+        ctx.http(),
+        &ctx.data().conn,
+        &ctx.data().key_cipher,
+        ctx.author().id.get(),
+        gamekey_id,
+        &user,
+    )
+    .await?;
+
+    ctx.send(CreateReply::default().content(content).ephemeral(true))
+        .await?;
+
+    Ok(())
+}
+
+/// Reserves one of your keys so nobody else can claim it. The reservation is released when it ends.
+#[poise::command(slash_command)]
+pub async fn reserve(
+    ctx: Context<'_>,
+    #[description = "Id of the key you want to reserve."] gamekey_id: i32,
+    #[description = "User who may claim the key, yourself if empty."] user: Option<User>,
+    #[description = "Duration of the reservation in hours, 24 if empty."]
+    #[min = 1]
+    #[max = 720]
+    duration_hours: Option<u32>,
+) -> Result<(), PoiseError> {
+    let db = &ctx.data().conn;
+
+    let holder = user.as_ref().unwrap_or(ctx.author());
+    if holder.bot {
+        ctx.send(
+            CreateReply::default()
+                .content("You can not reserve a key for a bot.")
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(());
+    }
 
-    let mut game_key = match GameKeyQuery::get_one(db, gamekey_id, ctx.author().id.get()).await? {
+    let previous = {
+        let cipher = ctx.data().key_cipher.read().await;
+        GameKeyQuery::get_one(db, &cipher, gamekey_id, ctx.author().id.get()).await?
+    };
+    let previous = match previous {
         Some(g) => g,
         None => {
             ctx.send(
@@ -556,26 +1016,91 @@ pub async fn claim_random(ctx: Context<'_>) -> Result<(), PoiseError> {
         }
     };
 
-    let game = GameQuery::get_one(db, game_key.game_id)
-        .await?
-        .expect(&format!(
-            "Game with id {} has been deleted.",
-            game_key.game_id
-        ));
+    let until = Utc::now() + Duration::hours(duration_hours.unwrap_or(24).into());
+    if previous
+        .expiration_date
+        .is_some_and(|x| x < until.naive_utc())
+    {
+        ctx.send(
+            CreateReply::default()
+                .content("The key expires before the reservation ends.")
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(());
+    }
 
-    let reply = CreateReply::default()
-        .content(format!(
-            "Your key: `{}` for `{}`",
-            game_key.value, game.title
-        ))
-        .ephemeral(true);
+    let reserved = {
+        let cipher = ctx.data().key_cipher.read().await;
+        let reserved = GameKeyMutation::reserve(
+            db,
+            &cipher,
+            gamekey_id,
+            ctx.author().id.get(),
+            holder.id.get(),
+            until,
+        )
+        .await?;
+        if let Some(game_key) = &reserved {
+            AuditMutation::log_game_key(
+                db,
+                &cipher,
+                AuditAction::Update,
+                ctx.author().id.get(),
+                game_key,
+                Some(&previous),
+            )
+            .await?;
+        }
+        reserved
+    };
 
-    game_key.keystate = "Used".to_owned();
-    game_key.modify_date = Some(Utc::now());
-    game_key.modify_user_id = Some(ctx.author().id.into());
+    if reserved.is_none() {
+        ctx.send(
+            CreateReply::default()
+                .content(format!(
+                    "The key `{}` is already used, reserved or gifted.",
+                    gamekey_id
+                ))
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(());
+    }
 
-    GameKeyMutation::update(db, game_key).await?;
+    ctx.send(
+        CreateReply::default()
+            .content(format!(
+                "Reserved the key `{}` for <@{}> until <t:{}:f>. It can be claimed with `/gamekey claim {}`.",
+                gamekey_id,
+                holder.id,
+                until.timestamp(),
+                gamekey_id
+            ))
+            .ephemeral(true),
+    )
+    .await?;
 
-    ctx.send(reply).await?;
     Ok(())
 }
+
+/// Explains why a key can not be claimed, including when the user can claim again.
+pub fn denial_message(denial: &ClaimDenial) -> String {
+    match denial {
+        ClaimDenial::Quota { limit, next_claim } => format!(
+            "You already claimed `{limit}` shared keys recently. You can claim again <t:{}:R>.",
+            next_claim.timestamp()
+        ),
+        ClaimDenial::GameLimit { limit } => {
+            format!("You already claimed `{limit}` shared keys of this game.")
+        }
+        ClaimDenial::AccountAge { allowed_from } => format!(
+            "Your discord account is too new to claim shared keys. You can claim <t:{}:R>.",
+            allowed_from.timestamp()
+        ),
+        ClaimDenial::MembershipAge { allowed_from } => format!(
+            "You joined this server too recently to claim shared keys. You can claim <t:{}:R>.",
+            allowed_from.timestamp()
+        ),
+    }
+}
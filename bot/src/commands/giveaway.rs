@@ -0,0 +1,143 @@
+use chrono::{Duration, Utc};
+use entity::{
+    giveaway,
+    sea_orm_active_enums::{GiveawayState, KeyState},
+};
+use gemuki_service::{
+    mutation::{GameKeyMutation, GiveawayMutation},
+    query::{GameKeyQuery, GameQuery, GiveawayQuery},
+};
+use poise::CreateReply;
+
+use crate::{
+    giveaway::{create_enter_button, create_giveaway_embed},
+    Data, PoiseError,
+};
+
+type Context<'a> = poise::Context<'a, Data, PoiseError>;
+
+/// A command for hosting giveaways of keys.
+#[poise::command(slash_command, guild_only, subcommands("start"))]
+pub async fn giveaway(ctx: Context<'_>) -> Result<(), PoiseError> {
+    ctx.say("How did you manage to do this?").await?;
+    Ok(())
+}
+
+/// Starts a giveaway for one of your keys in this channel.
+#[poise::command(slash_command, guild_only)]
+pub async fn start(
+    ctx: Context<'_>,
+    #[description = "Id of the key you want to give away."] gamekey_id: i32,
+    #[description = "Duration of the giveaway in minutes."]
+    #[min = 1]
+    #[max = 43200]
+    duration_minutes: u32,
+) -> Result<(), PoiseError> {
+    let db = &ctx.data().conn;
+
+    let guild_id = match ctx.guild_id() {
+        Some(g) => g,
+        None => return Ok(()),
+    };
+
+    let game_key = {
+        let cipher = ctx.data().key_cipher.read().await;
+        GameKeyQuery::get_one(db, &cipher, gamekey_id, ctx.author().id.get()).await?
+    };
+    let game_key = match game_key {
+        Some(k) => k,
+        None => {
+            ctx.send(
+                CreateReply::default()
+                    .content(format!("The key `{}` does not exist.", gamekey_id))
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    if game_key.keystate != KeyState::Unused {
+        ctx.send(
+            CreateReply::default()
+                .content(format!("The key `{}` is already used.", gamekey_id))
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let end_date = Utc::now() + Duration::minutes(duration_minutes.into());
+
+    if let Some(expiration_date) = game_key.expiration_date {
+        if expiration_date < end_date.naive_utc() {
+            ctx.send(
+                CreateReply::default()
+                    .content("The key expires before the giveaway ends.")
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+    }
+
+    if GiveawayQuery::get_running_by_game_key(db, gamekey_id)
+        .await?
+        .is_some()
+    {
+        ctx.send(
+            CreateReply::default()
+                .content(format!("The key `{}` is already given away.", gamekey_id))
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let game = GameQuery::get_one(db, game_key.game_id).await?;
+
+    let reserved = {
+        let cipher = ctx.data().key_cipher.read().await;
+        GameKeyMutation::reserve_for_giveaway(db, &cipher, game_key.id, ctx.author().id.get())
+            .await?
+    };
+    let game_key = match reserved {
+        Some(k) => k,
+        None => {
+            ctx.send(
+                CreateReply::default()
+                    .content(format!("The key `{}` is no longer available.", gamekey_id))
+                    .ephemeral(true),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let model = giveaway::Model {
+        id: 0,
+        game_key_id: game_key.id,
+        guild_id: guild_id.get() as i64,
+        channel_id: ctx.channel_id().get() as i64,
+        message_id: None,
+        host_user_id: ctx.author().id.get() as i64,
+        state: GiveawayState::Running,
+        end_date,
+        winner_user_id: None,
+        create_date: Utc::now(),
+    };
+    let giveaway = GiveawayMutation::create(db, model).await?;
+
+    let reply = ctx
+        .send(
+            CreateReply::default()
+                .embed(create_giveaway_embed(game.as_ref(), &giveaway))
+                .components(vec![create_enter_button(giveaway.id)]),
+        )
+        .await?;
+    let message = reply.message().await?;
+
+    GiveawayMutation::set_message_id(db, giveaway.id, message.id.get()).await?;
+
+    Ok(())
+}
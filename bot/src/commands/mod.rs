@@ -1,13 +1,33 @@
+pub mod admin;
+pub mod audit;
 pub mod game;
 pub mod gamekey;
+pub mod giveaway;
+pub mod pool;
+pub mod settings;
 pub mod statistic;
+pub mod trash;
 pub mod version;
+pub mod wishlist;
 
-use crate::{Data, PoiseError};
-use poise::serenity_prelude::futures::{self, Stream, StreamExt};
+use crate::{fuzzy, steam, Data, PoiseError};
+
+use chrono::DateTime;
+
+use gemuki_service::{
+    pool::PoolMember,
+    query::{GameQuery, UserSettingQuery},
+};
+use log::error;
+use poise::serenity_prelude::futures::{self, Stream};
 
 pub type Context<'a> = poise::Context<'a, Data, PoiseError>;
 
+/// Maximum number of choices discord accepts for an autocomplete.
+const MAX_AUTOCOMPLETE_CHOICES: usize = 25;
+/// Number of titles suggested when a game could not be found.
+const MAX_SUGGESTIONS: usize = 3;
+
 async fn autocomplete_game<'a>(
     ctx: Context<'_>,
     partial: &'a str,
@@ -17,11 +37,121 @@ async fn autocomplete_game<'a>(
 
     cache.update(db).await;
 
-    let title = cache.cache().to_vec();
+    futures::stream::iter(cache.search(partial, MAX_AUTOCOMPLETE_CHOICES))
+}
+
+async fn autocomplete_steam_app<'a>(
+    ctx: Context<'_>,
+    partial: &'a str,
+) -> impl Stream<Item = String> + 'a {
+    let cache = ctx.data().steam_app_cache.lock().await;
+
+    let mut names = match partial.trim().is_empty() {
+        true => Vec::new(),
+        false => cache
+            .search(partial, MAX_AUTOCOMPLETE_CHOICES)
+            .into_iter()
+            .map(|x| x.name().to_owned())
+            .collect::<Vec<String>>(),
+    };
+    names.dedup();
+
+    futures::stream::iter(names)
+}
+
+/// Finds a game by its title. Falls back to the best cached title if it only differs in case or punctuation.
+async fn find_game(
+    ctx: Context<'_>,
+    title: &str,
+) -> Result<Option<entity::game::Model>, PoiseError> {
+    let db = &ctx.data().conn;
+
+    if let Some(game) = GameQuery::get_by_title(db, title).await? {
+        return Ok(Some(game));
+    }
+
+    let best = ctx.data().game_title_cache.lock().await.search(title, 1);
+    match best.first() {
+        Some(best) if fuzzy::is_same_title(best, title) => {
+            Ok(GameQuery::get_by_title(db, best).await?)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Gets the author as pool member, including the guild and roles of the current channel.
+pub async fn pool_member(ctx: Context<'_>) -> PoolMember {
+    let mut member = PoolMember::new(ctx.author().id.get());
+    member.guild_id = ctx.guild_id().map(|x| x.get());
+    member.account_date =
+        DateTime::from_timestamp(ctx.author().id.created_at().unix_timestamp(), 0);
+    if let Some(author) = ctx.author_member().await {
+        member.role_ids = author.roles.iter().map(|x| x.get()).collect();
+        member.join_date = author
+            .joined_at
+            .and_then(|x| DateTime::from_timestamp(x.unix_timestamp(), 0));
+    }
+
+    member
+}
+
+/// Gets the country code of the steam store region of the user, falling back to the default region.
+pub async fn country_code(ctx: Context<'_>) -> Result<String, PoiseError> {
+    let setting = UserSettingQuery::get(&ctx.data().conn, ctx.author().id.get()).await?;
+
+    Ok(setting
+        .and_then(|x| x.country_code)
+        .unwrap_or(ctx.data().steam_country.clone()))
+}
+
+/// Gets the current steam price of a game in the store region of the user.
+/// Returns `None` if the game has no steam app id or the price could not be retrieved.
+pub async fn find_price(ctx: Context<'_>, game: &entity::game::Model) -> Option<String> {
+    let appid = game.steam_appid.and_then(|x| u32::try_from(x).ok())?;
+    let country_code = match country_code(ctx).await {
+        Ok(c) => c,
+        Err(why) => {
+            error!("Could not get the country code of a user because of '{why}'.");
+            return None;
+        }
+    };
+
+    let data = ctx.data();
+    match steam::get_price(
+        data.steam_client.as_ref(),
+        &data.price_cache,
+        appid,
+        &country_code,
+    )
+    .await
+    {
+        Ok(price) => Some(format!("{price} ({})", country_code.to_uppercase())),
+        Err(why) => {
+            error!("Could not retrieve the price of app {appid} from steam, {why:?}");
+            None
+        }
+    }
+}
+
+/// Creates a hint with titles similar to a game which could not be found.
+async fn did_you_mean(ctx: Context<'_>, title: &str) -> String {
+    let suggestions = ctx
+        .data()
+        .game_title_cache
+        .lock()
+        .await
+        .search(title, MAX_SUGGESTIONS);
+
+    if suggestions.is_empty() {
+        return String::new();
+    }
 
-    futures::stream::iter(title)
-        .filter(move |name| {
-            futures::future::ready(name.to_lowercase().starts_with(&partial.to_lowercase()))
-        })
-        .map(|name| name.to_string())
+    format!(
+        " Did you mean {}?",
+        suggestions
+            .iter()
+            .map(|x| format!("`{x}`"))
+            .collect::<Vec<String>>()
+            .join(", ")
+    )
 }
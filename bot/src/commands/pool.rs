@@ -0,0 +1,286 @@
+use chrono::Utc;
+use entity::{
+    key_pool,
+    sea_orm_active_enums::{AuditAction, PoolKind},
+};
+use gemuki_service::{
+    mutation::{AuditMutation, KeyPoolMutation},
+    query::{GameKeyQuery, KeyPoolQuery},
+};
+use log::error;
+use poise::{
+    serenity_prelude::{
+        futures::{self, Stream},
+        CreateEmbed, Role,
+    },
+    CreateReply,
+};
+
+use crate::{
+    commands::{pool_member, MAX_AUTOCOMPLETE_CHOICES},
+    wishlist, Data, PoiseError,
+};
+
+type Context<'a> = poise::Context<'a, Data, PoiseError>;
+
+/// Maximum number of keys listed for a pool.
+const MAX_LISTED_KEYS: usize = 30;
+
+#[derive(Debug, poise::ChoiceParameter)]
+pub enum PoolKindChoice {
+    #[name = "Personal"]
+    Personal,
+    #[name = "Guild"]
+    Guild,
+    #[name = "Role"]
+    Role,
+}
+
+impl From<PoolKindChoice> for PoolKind {
+    fn from(value: PoolKindChoice) -> Self {
+        match value {
+            PoolKindChoice::Personal => PoolKind::Personal,
+            PoolKindChoice::Guild => PoolKind::Guild,
+            PoolKindChoice::Role => PoolKind::Role,
+        }
+    }
+}
+
+/// Pools of keys shared with a guild or a role.
+#[poise::command(slash_command, subcommands("create", "move_key", "list"))]
+pub async fn pool(ctx: Context<'_>) -> Result<(), PoiseError> {
+    ctx.say("How did you manage to do this?").await?;
+    Ok(())
+}
+
+/// Creates a key pool. Guild and role pools are shared with the guild the command is used in.
+#[poise::command(slash_command)]
+pub async fn create(
+    ctx: Context<'_>,
+    #[description = "Unique name of the pool."] name: String,
+    #[description = "Who may claim the keys of the pool."] kind: PoolKindChoice,
+    #[description = "Role whose members may claim the keys of a role pool."] role: Option<Role>,
+) -> Result<(), PoiseError> {
+    let db = &ctx.data().conn;
+    let kind = PoolKind::from(kind);
+
+    if KeyPoolQuery::get_by_name(db, &name).await?.is_some() {
+        reply(ctx, format!("A pool named `{name}` already exists.")).await?;
+        return Ok(());
+    }
+
+    let guild_id = match (kind, ctx.guild_id()) {
+        (PoolKind::Personal, _) => None,
+        (_, Some(guild_id)) => Some(guild_id.get() as i64),
+        (_, None) => {
+            reply(
+                ctx,
+                "Guild and role pools can only be created in a guild.".to_owned(),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+    let role_id = match (kind, role) {
+        (PoolKind::Role, Some(role)) => Some(role.id.get() as i64),
+        (PoolKind::Role, None) => {
+            reply(ctx, "Please choose the role of the pool.".to_owned()).await?;
+            return Ok(());
+        }
+        _ => None,
+    };
+
+    let model = key_pool::Model {
+        id: 0,
+        name,
+        kind,
+        owner_user_id: ctx.author().id.get() as i64,
+        guild_id,
+        role_id,
+        create_date: Utc::now(),
+    };
+
+    let message = match KeyPoolMutation::create(db, model).await {
+        Ok(pool) => format!(
+            "Created the pool `{}`. Use `/pool move` to add keys.",
+            pool.name
+        ),
+        Err(why) => {
+            error!("Could not create pool because of '{why}'.");
+            "Could not create the pool because of an internal server error.".to_owned()
+        }
+    };
+    reply(ctx, message).await?;
+
+    Ok(())
+}
+
+/// Moves one of your keys into a pool. Leave the pool empty to make the key personal again.
+#[poise::command(slash_command, rename = "move")]
+pub async fn move_key(
+    ctx: Context<'_>,
+    #[description = "Id of the key to move."] gamekey_id: i32,
+    #[description = "Name of the pool."]
+    #[autocomplete = "autocomplete_pool"]
+    pool: Option<String>,
+) -> Result<(), PoiseError> {
+    let db = &ctx.data().conn;
+    let member = pool_member(ctx).await;
+
+    let pool = match pool {
+        Some(name) => match KeyPoolQuery::get_by_name(db, &name).await? {
+            Some(p) => Some(p),
+            None => {
+                reply(ctx, format!("The pool `{name}` does not exist.")).await?;
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+
+    let moved = {
+        let cipher = ctx.data().key_cipher.read().await;
+        let previous = GameKeyQuery::get_one(db, &cipher, gamekey_id, member.user_id).await?;
+        let moved =
+            KeyPoolMutation::move_key(db, &cipher, gamekey_id, pool.as_ref(), &member).await?;
+        if let Some(moved) = &moved {
+            AuditMutation::log_game_key(
+                db,
+                &cipher,
+                AuditAction::Update,
+                member.user_id,
+                moved,
+                previous.as_ref(),
+            )
+            .await?;
+        }
+        moved
+    };
+
+    let message = match moved {
+        Some(moved) => {
+            if pool.is_some() {
+                if let Err(why) = wishlist::notify_wishing_users(
+                    ctx.http(),
+                    db,
+                    member.user_id,
+                    std::slice::from_ref(&moved),
+                )
+                .await
+                {
+                    error!("Could not notify wishing users because of '{why}'.");
+                }
+            }
+
+            match pool {
+                Some(pool) => format!("Moved the key `{gamekey_id}` into the pool `{}`.", pool.name),
+                None => format!("The key `{gamekey_id}` is personal again."),
+            }
+        }
+        None => format!(
+            "Could not move the key `{gamekey_id}`. You can only move your own keys into pools you can access."
+        ),
+    };
+    reply(ctx, message).await?;
+
+    Ok(())
+}
+
+/// Lists the pools you can access or the keys of a pool.
+#[poise::command(slash_command)]
+pub async fn list(
+    ctx: Context<'_>,
+    #[description = "Name of the pool whose keys you want to see."]
+    #[autocomplete = "autocomplete_pool"]
+    pool: Option<String>,
+) -> Result<(), PoiseError> {
+    let db = &ctx.data().conn;
+    let member = pool_member(ctx).await;
+
+    let embed = match pool {
+        None => {
+            let pools = KeyPoolQuery::get_accessible(db, &member)
+                .await?
+                .iter()
+                .map(|x| {
+                    format!(
+                        "- `{}` ({}, owned by <@{}>)",
+                        x.name, x.kind, x.owner_user_id
+                    )
+                })
+                .collect::<Vec<String>>();
+            let description = match pools.is_empty() {
+                true => "You can not access any pool here.".to_owned(),
+                false => pools.join("\n"),
+            };
+
+            CreateEmbed::new()
+                .title("Key pools")
+                .description(description)
+        }
+        Some(name) => {
+            let keys = match KeyPoolQuery::get_by_name(db, &name).await? {
+                Some(pool) => KeyPoolQuery::get_keys(db, &pool, &member).await?,
+                None => None,
+            };
+            let keys = match keys {
+                Some(k) => k,
+                None => {
+                    reply(ctx, format!("The pool `{name}` does not exist.")).await?;
+                    return Ok(());
+                }
+            };
+
+            let mut lines = keys
+                .iter()
+                .take(MAX_LISTED_KEYS)
+                .map(|(game_key, game, platform)| {
+                    format!(
+                        "`{}` {} ({}, {})",
+                        game_key.id, game.title, platform.name, game_key.keystate
+                    )
+                })
+                .collect::<Vec<String>>();
+            if keys.len() > MAX_LISTED_KEYS {
+                lines.push(format!("... and {} more", keys.len() - MAX_LISTED_KEYS));
+            }
+            let description = match lines.is_empty() {
+                true => "The pool is empty.".to_owned(),
+                false => lines.join("\n"),
+            };
+
+            CreateEmbed::new()
+                .title(format!("Keys of the pool {name}"))
+                .description(description)
+        }
+    };
+
+    ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+
+    Ok(())
+}
+
+async fn autocomplete_pool<'a>(
+    ctx: Context<'_>,
+    partial: &'a str,
+) -> impl Stream<Item = String> + 'a {
+    let member = pool_member(ctx).await;
+    let names = match KeyPoolQuery::get_accessible(&ctx.data().conn, &member).await {
+        Ok(pools) => pools
+            .into_iter()
+            .map(|x| x.name)
+            .filter(|x| x.to_lowercase().contains(&partial.to_lowercase()))
+            .take(MAX_AUTOCOMPLETE_CHOICES)
+            .collect::<Vec<String>>(),
+        Err(_) => Vec::new(),
+    };
+
+    futures::stream::iter(names)
+}
+
+async fn reply(ctx: Context<'_>, content: String) -> Result<(), PoiseError> {
+    ctx.send(CreateReply::default().content(content).ephemeral(true))
+        .await?;
+    Ok(())
+}
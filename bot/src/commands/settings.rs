@@ -0,0 +1,78 @@
+use gemuki_service::mutation::UserSettingMutation;
+use poise::CreateReply;
+
+use crate::{steam, Data, PoiseError};
+
+type Context<'a> = poise::Context<'a, Data, PoiseError>;
+
+/// Your personal settings.
+#[poise::command(slash_command, subcommands("region", "sharing"))]
+pub async fn settings(ctx: Context<'_>) -> Result<(), PoiseError> {
+    ctx.say("How did you manage to do this?").await?;
+    Ok(())
+}
+
+/// Sets the steam store region used for prices. Leave it empty to use the default region.
+#[poise::command(slash_command)]
+pub async fn region(
+    ctx: Context<'_>,
+    #[description = "Two letter country code of the store region, e.g. US or GB."]
+    country_code: Option<String>,
+) -> Result<(), PoiseError> {
+    let db = &ctx.data().conn;
+
+    let country_code = match country_code {
+        Some(code) => match steam::parse_country_code(&code) {
+            Some(c) => Some(c),
+            None => {
+                ctx.send(
+                    CreateReply::default()
+                        .content(format!("`{code}` is not a two letter country code."))
+                        .ephemeral(true),
+                )
+                .await?;
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+
+    UserSettingMutation::set_country_code(db, ctx.author().id.get(), country_code.clone()).await?;
+
+    let message = match country_code {
+        Some(c) => format!(
+            "Prices are now shown for the store region `{}`.",
+            c.to_uppercase()
+        ),
+        None => format!(
+            "Prices are now shown for the default store region `{}`.",
+            ctx.data().steam_country.to_uppercase()
+        ),
+    };
+
+    ctx.send(CreateReply::default().content(message).ephemeral(true))
+        .await?;
+
+    Ok(())
+}
+
+/// Sets whether keys you add are announced to users who have the game on their wishlist.
+#[poise::command(slash_command)]
+pub async fn sharing(
+    ctx: Context<'_>,
+    #[description = "Announce keys you add to users wishing for the game."] share_keys: bool,
+) -> Result<(), PoiseError> {
+    let db = &ctx.data().conn;
+
+    UserSettingMutation::set_share_keys(db, ctx.author().id.get(), share_keys).await?;
+
+    let message = match share_keys {
+        true => "Keys you add are now announced to users wishing for the game.",
+        false => "Keys you add are not announced to other users anymore.",
+    };
+
+    ctx.send(CreateReply::default().content(message).ephemeral(true))
+        .await?;
+
+    Ok(())
+}
@@ -1,11 +1,17 @@
-use gemuki_service::query::{GameKeyQuery, GameQuery};
+use gemuki_service::{
+    query::{GameKeyQuery, GameQuery, PriceSnapshotQuery},
+    value::Amounts,
+};
 use poise::{serenity_prelude::CreateEmbed, CreateReply};
 
-use crate::Data;
+use crate::{commands::country_code, Data};
 
 type PoiseError = Box<dyn std::error::Error + Send + Sync>;
 type Context<'a> = poise::Context<'a, Data, PoiseError>;
 
+/// Number of months shown in the value claimed from the keys of a user.
+static CLAIMED_MONTHS: usize = 6;
+
 /// Displays some statistics for the bot.
 #[poise::command(slash_command)]
 pub async fn statistics(ctx: Context<'_>) -> Result<(), PoiseError> {
@@ -21,6 +27,46 @@ pub async fn statistics(ctx: Context<'_>) -> Result<(), PoiseError> {
     let unused_keys_of_user = GameKeyQuery::count_unused_of_user(db, ctx.author().id.get()).await?;
     let used_keys_of_user = GameKeyQuery::count_used_of_user(db, ctx.author().id.get()).await?;
 
+    // Prices of the store region of the user are preferred, the default region fills the gaps.
+    let mut country_codes = vec![country_code(ctx).await?];
+    if !country_codes.contains(&ctx.data().steam_country) {
+        country_codes.push(ctx.data().steam_country.clone());
+    }
+    let unused_value =
+        PriceSnapshotQuery::estimate_unused_value(db, ctx.author().id.get(), &country_codes)
+            .await?;
+    let claimed_value =
+        PriceSnapshotQuery::estimate_claimed_value(db, ctx.author().id.get(), &country_codes)
+            .await?;
+
+    let mut unused_value_text = format_amounts(&unused_value.total);
+    if unused_value.unpriced > 0 {
+        unused_value_text.push_str(&format!(
+            "\n{} keys without known price",
+            unused_value.unpriced
+        ));
+    }
+    let platform_value_text = unused_value
+        .platforms
+        .iter()
+        .map(|(platform, amounts)| format!("{platform}: {}", format_amounts(amounts)))
+        .collect::<Vec<String>>()
+        .join("\n");
+    let mut claimed_value_text = claimed_value
+        .months
+        .iter()
+        .rev()
+        .take(CLAIMED_MONTHS)
+        .map(|(month, amounts)| format!("{month}: {}", format_amounts(amounts)))
+        .collect::<Vec<String>>()
+        .join("\n");
+    if claimed_value.unpriced > 0 {
+        claimed_value_text.push_str(&format!(
+            "\n{} keys without known price",
+            claimed_value.unpriced
+        ));
+    }
+
     let embed = CreateEmbed::new()
         .title("gemuki-bot statistics")
         .field("Total games", total_games.to_string(), true)
@@ -30,9 +76,37 @@ pub async fn statistics(ctx: Context<'_>) -> Result<(), PoiseError> {
         .field("Used keys", used_keys.to_string(), true)
         .field("Owned keys", total_keys_of_user.to_string(), true)
         .field("Owned unused keys", unused_keys_of_user.to_string(), true)
-        .field("Owned used keys", used_keys_of_user.to_string(), true);
+        .field("Owned used keys", used_keys_of_user.to_string(), true)
+        .field("Value of owned unused keys", unused_value_text, false)
+        .field("Value by platform", or_none(platform_value_text), false)
+        .field(
+            "Value claimed from owned keys",
+            or_none(claimed_value_text),
+            false,
+        );
 
     ctx.send(CreateReply::default().embed(embed)).await?;
 
     Ok(())
 }
+
+/// Formats amounts in the smallest unit of their currency, e.g. `123.45 EUR`.
+fn format_amounts(amounts: &Amounts) -> String {
+    if amounts.is_empty() {
+        return "0.00".to_string();
+    }
+
+    amounts
+        .iter()
+        .map(|(currency, amount)| format!("{}.{:02} {currency}", amount / 100, amount % 100))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn or_none(text: String) -> String {
+    if text.is_empty() {
+        "None".to_string()
+    } else {
+        text
+    }
+}
@@ -0,0 +1,269 @@
+use std::fmt::Display;
+
+use entity::sea_orm_active_enums::AuditAction;
+use gemuki_service::{
+    mutation::{AuditMutation, GameKeyMutation, GameMutation},
+    query::{GameKeyQuery, GameQuery},
+};
+use log::warn;
+use poise::{
+    serenity_prelude::{Color, CreateEmbed},
+    CreateReply,
+};
+
+use crate::{Data, PoiseError};
+
+type Context<'a> = poise::Context<'a, Data, PoiseError>;
+
+const MAX_LISTED_ENTRIES: usize = 20;
+
+#[derive(Debug, poise::ChoiceParameter)]
+pub enum TrashChoice {
+    #[name = "Game"]
+    Game,
+    #[name = "Gamekey"]
+    Gamekey,
+}
+
+impl Display for TrashChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TrashChoice::Game => write!(f, "game"),
+            TrashChoice::Gamekey => write!(f, "gamekey"),
+        }
+    }
+}
+
+/// A command for managing removed games and keys.
+#[poise::command(slash_command, subcommands("list", "restore", "purge"))]
+pub async fn trash(ctx: Context<'_>) -> Result<(), PoiseError> {
+    ctx.say("How did you manage to do this?").await?;
+    Ok(())
+}
+
+/// Lists all removed games and your removed keys.
+#[poise::command(slash_command)]
+pub async fn list(ctx: Context<'_>) -> Result<(), PoiseError> {
+    let db = &ctx.data().conn;
+    let retention = ctx.data().trash_retention;
+
+    let games = GameQuery::get_trashed(db).await?;
+    let game_keys = GameKeyQuery::get_trashed(db, ctx.author().id.get()).await?;
+
+    if games.is_empty() && game_keys.is_empty() {
+        ctx.send(
+            CreateReply::default()
+                .content("The trash is empty.")
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let game_lines = games
+        .iter()
+        .take(MAX_LISTED_ENTRIES)
+        .map(|x| {
+            format!(
+                "`{}` {} (purged <t:{}:R>)",
+                x.id,
+                x.title,
+                x.deleted_date
+                    .map(|d| d + retention)
+                    .unwrap_or_default()
+                    .timestamp()
+            )
+        })
+        .collect::<Vec<String>>();
+    let game_key_lines = game_keys
+        .iter()
+        .take(MAX_LISTED_ENTRIES)
+        .map(|(game_key, game)| {
+            format!(
+                "`{}` {} (purged <t:{}:R>)",
+                game_key.id,
+                game.as_ref()
+                    .map(|x| x.title.as_str())
+                    .unwrap_or("Unknown game"),
+                game_key
+                    .deleted_date
+                    .map(|d| d + retention)
+                    .unwrap_or_default()
+                    .timestamp()
+            )
+        })
+        .collect::<Vec<String>>();
+
+    let embed = CreateEmbed::new()
+        .colour(Color::DARK_RED)
+        .title("Trash")
+        .field(
+            format!("Games ({})", games.len()),
+            if game_lines.is_empty() {
+                "None".to_owned()
+            } else {
+                game_lines.join("\n")
+            },
+            false,
+        )
+        .field(
+            format!("Your gamekeys ({})", game_keys.len()),
+            if game_key_lines.is_empty() {
+                "None".to_owned()
+            } else {
+                game_key_lines.join("\n")
+            },
+            false,
+        );
+
+    ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+
+    Ok(())
+}
+
+/// Restores a removed game with its keys or a single removed key.
+#[poise::command(slash_command)]
+pub async fn restore(
+    ctx: Context<'_>,
+    #[description = "What to restore."] kind: TrashChoice,
+    #[description = "Id of the game or gamekey."] id: i32,
+) -> Result<(), PoiseError> {
+    let db = &ctx.data().conn;
+    let user_id = ctx.author().id.get();
+
+    let message = match kind {
+        TrashChoice::Game => {
+            if !is_owner(ctx) {
+                ctx.send(
+                    CreateReply::default()
+                        .content("Only owners of the bot can restore games.")
+                        .ephemeral(true),
+                )
+                .await?;
+                return Ok(());
+            }
+
+            match GameQuery::get_trashed_one(db, id).await? {
+                Some(game) => {
+                    let restored_keys = GameMutation::restore(db, id).await?.unwrap_or(0);
+                    AuditMutation::log_game(db, AuditAction::Restore, user_id, &game, None).await?;
+
+                    ctx.data()
+                        .game_title_cache
+                        .lock()
+                        .await
+                        .force_update(db)
+                        .await;
+
+                    format!(
+                        "Restored game `{}` with `{restored_keys}` keys.",
+                        game.title
+                    )
+                }
+                None => format!("The game `{id}` is not in the trash."),
+            }
+        }
+        TrashChoice::Gamekey => {
+            let cipher = ctx.data().key_cipher.read().await;
+            match GameKeyQuery::get_trashed_one(db, &cipher, id, user_id).await? {
+                Some(game_key) => {
+                    if GameQuery::get_one(db, game_key.game_id).await?.is_none() {
+                        format!(
+                            "The game of the key is in the trash. Restore the game `{}` first.",
+                            game_key.game_id
+                        )
+                    } else {
+                        GameKeyMutation::restore(db, id, user_id).await?;
+                        AuditMutation::log_game_key(
+                            db,
+                            &cipher,
+                            AuditAction::Restore,
+                            user_id,
+                            &game_key,
+                            None,
+                        )
+                        .await?;
+
+                        format!("Restored the key `{id}`.")
+                    }
+                }
+                None => format!("The key `{id}` is not in the trash."),
+            }
+        }
+    };
+
+    ctx.send(CreateReply::default().content(message).ephemeral(true))
+        .await?;
+
+    Ok(())
+}
+
+/// Permanently deletes a removed game with its keys or a single removed key.
+#[poise::command(slash_command)]
+pub async fn purge(
+    ctx: Context<'_>,
+    #[description = "What to purge."] kind: TrashChoice,
+    #[description = "Id of the game or gamekey."] id: i32,
+) -> Result<(), PoiseError> {
+    let db = &ctx.data().conn;
+    let user_id = ctx.author().id.get();
+
+    let message = match kind {
+        TrashChoice::Game => {
+            if !is_owner(ctx) {
+                ctx.send(
+                    CreateReply::default()
+                        .content("Only owners of the bot can purge games.")
+                        .ephemeral(true),
+                )
+                .await?;
+                return Ok(());
+            }
+
+            match GameQuery::get_trashed_one(db, id).await? {
+                Some(game) => {
+                    let (purged_keys, purged_games) = GameMutation::purge(db, id).await?;
+                    AuditMutation::log_game(db, AuditAction::Purge, user_id, &game, Some(&game))
+                        .await?;
+
+                    warn!("Purged game with title '{}'.", game.title);
+
+                    format!("Purged `{purged_keys}` keys and `{purged_games}` games.")
+                }
+                None => format!("The {kind} `{id}` is not in the trash."),
+            }
+        }
+        TrashChoice::Gamekey => {
+            let cipher = ctx.data().key_cipher.read().await;
+            match GameKeyQuery::get_trashed_one(db, &cipher, id, user_id).await? {
+                Some(game_key) => {
+                    let purged_keys = GameKeyMutation::purge(db, id, user_id).await?;
+                    AuditMutation::log_game_key(
+                        db,
+                        &cipher,
+                        AuditAction::Purge,
+                        user_id,
+                        &game_key,
+                        Some(&game_key),
+                    )
+                    .await?;
+
+                    warn!("Purged gamekey with id '{id}'.");
+
+                    format!("Purged `{}` keys.", purged_keys.rows_affected)
+                }
+                None => format!("The {kind} `{id}` is not in the trash."),
+            }
+        }
+    };
+
+    ctx.send(CreateReply::default().content(message).ephemeral(true))
+        .await?;
+
+    Ok(())
+}
+
+fn is_owner(ctx: Context<'_>) -> bool {
+    ctx.framework().options().owners.contains(&ctx.author().id)
+}
@@ -0,0 +1,199 @@
+use entity::{game, steam_app, wishlist};
+use gemuki_service::{
+    mutation::WishlistMutation,
+    query::{UserSettingQuery, WishlistQuery},
+};
+use poise::{
+    serenity_prelude::{
+        futures::{self, Stream},
+        CreateEmbed, CreateEmbedFooter,
+    },
+    CreateReply,
+};
+
+use crate::{
+    commands::{
+        autocomplete_game, autocomplete_steam_app, did_you_mean, find_game,
+        MAX_AUTOCOMPLETE_CHOICES,
+    },
+    Data, PoiseError,
+};
+
+type Context<'a> = poise::Context<'a, Data, PoiseError>;
+
+/// Games you wish for. You get a DM when someone adds a key for them.
+#[poise::command(slash_command, subcommands("add", "remove", "list"))]
+pub async fn wishlist(ctx: Context<'_>) -> Result<(), PoiseError> {
+    ctx.say("How did you manage to do this?").await?;
+    Ok(())
+}
+
+/// Adds a game to your wishlist. Use `steam_app` for games which are not added yet.
+#[poise::command(slash_command)]
+pub async fn add(
+    ctx: Context<'_>,
+    #[description = "Name of the game."]
+    #[autocomplete = "autocomplete_game"]
+    game: Option<String>,
+    #[description = "Name of the steam app, for games which are not added yet."]
+    #[autocomplete = "autocomplete_steam_app"]
+    steam_app: Option<String>,
+) -> Result<(), PoiseError> {
+    let db = &ctx.data().conn;
+
+    let (game_id, steam_appid, title) = match (game, steam_app) {
+        (Some(title), None) => match find_game(ctx, &title).await? {
+            Some(g) => (Some(g.id), g.steam_appid, g.title),
+            None => {
+                reply(
+                    ctx,
+                    format!(
+                        "The game `{title}` does not exist.{}",
+                        did_you_mean(ctx, &title).await
+                    ),
+                )
+                .await?;
+                return Ok(());
+            }
+        },
+        (None, Some(name)) => {
+            let app = ctx
+                .data()
+                .steam_app_cache
+                .lock()
+                .await
+                .find_by_name(&name)
+                .cloned();
+            match app {
+                Some(app) => (None, Some(i64::from(app.appid())), app.name().to_owned()),
+                None => {
+                    reply(ctx, format!("The steam app `{name}` does not exist.")).await?;
+                    return Ok(());
+                }
+            }
+        }
+        _ => {
+            reply(
+                ctx,
+                "Please choose either a game or a steam app.".to_owned(),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let message = match WishlistMutation::add(db, ctx.author().id.get(), game_id, steam_appid)
+        .await?
+    {
+        Some(_) => {
+            let mut message = format!("Added `{title}` to your wishlist.");
+            if steam_appid.is_none() {
+                message.push_str(" The game has no steam app id, so only keys for exactly this game are matched.");
+            }
+            message
+        }
+        None => format!("`{title}` already is on your wishlist."),
+    };
+    reply(ctx, message).await?;
+
+    Ok(())
+}
+
+/// Removes a game from your wishlist.
+#[poise::command(slash_command)]
+pub async fn remove(
+    ctx: Context<'_>,
+    #[description = "Name of the game on your wishlist."]
+    #[autocomplete = "autocomplete_wish"]
+    game: String,
+) -> Result<(), PoiseError> {
+    let db = &ctx.data().conn;
+
+    let wish = WishlistQuery::get_all_of_user(db, ctx.author().id.get())
+        .await?
+        .into_iter()
+        .find(|(wish, game_model, steam_app)| {
+            wish_title(wish, game_model.as_ref(), steam_app.as_ref()) == game
+        });
+
+    let message = match wish {
+        Some((wish, _, _)) => {
+            WishlistMutation::remove(db, ctx.author().id.get(), wish.id).await?;
+            format!("Removed `{game}` from your wishlist.")
+        }
+        None => format!("`{game}` is not on your wishlist."),
+    };
+    reply(ctx, message).await?;
+
+    Ok(())
+}
+
+/// Lists the games on your wishlist.
+#[poise::command(slash_command)]
+pub async fn list(ctx: Context<'_>) -> Result<(), PoiseError> {
+    let db = &ctx.data().conn;
+
+    let titles = WishlistQuery::get_all_of_user(db, ctx.author().id.get())
+        .await?
+        .iter()
+        .map(|(wish, game, steam_app)| {
+            format!("- {}", wish_title(wish, game.as_ref(), steam_app.as_ref()))
+        })
+        .collect::<Vec<String>>();
+
+    let description = match titles.is_empty() {
+        true => "Your wishlist is empty. Use `/wishlist add` to add games.".to_owned(),
+        false => titles.join("\n"),
+    };
+    let footer = match UserSettingQuery::shares_keys(db, ctx.author().id.get()).await? {
+        true => "Keys you add are announced to users wishing for the game.",
+        false => "Keys you add are only announced when you move them into a pool.",
+    };
+    let embed = CreateEmbed::new()
+        .title("Your wishlist")
+        .description(description)
+        .footer(CreateEmbedFooter::new(footer));
+
+    ctx.send(CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+
+    Ok(())
+}
+
+async fn autocomplete_wish<'a>(
+    ctx: Context<'_>,
+    partial: &'a str,
+) -> impl Stream<Item = String> + 'a {
+    let titles = match WishlistQuery::get_all_of_user(&ctx.data().conn, ctx.author().id.get()).await
+    {
+        Ok(wishes) => wishes
+            .iter()
+            .map(|(wish, game, steam_app)| wish_title(wish, game.as_ref(), steam_app.as_ref()))
+            .filter(|x| x.to_lowercase().contains(&partial.to_lowercase()))
+            .take(MAX_AUTOCOMPLETE_CHOICES)
+            .collect::<Vec<String>>(),
+        Err(_) => Vec::new(),
+    };
+
+    futures::stream::iter(titles)
+}
+
+/// Title of a wished game. Falls back to the steam app name for games which are not added yet.
+fn wish_title(
+    wish: &wishlist::Model,
+    game: Option<&game::Model>,
+    steam_app: Option<&steam_app::Model>,
+) -> String {
+    match (game, steam_app, wish.steam_appid) {
+        (Some(game), _, _) => game.title.clone(),
+        (None, Some(steam_app), _) => steam_app.name.clone(),
+        (None, None, Some(appid)) => format!("Steam app {appid}"),
+        (None, None, None) => format!("Unknown game {}", wish.id),
+    }
+}
+
+async fn reply(ctx: Context<'_>, content: String) -> Result<(), PoiseError> {
+    ctx.send(CreateReply::default().content(content).ephemeral(true))
+        .await?;
+    Ok(())
+}
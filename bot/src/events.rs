@@ -0,0 +1,35 @@
+use poise::serenity_prelude::{self as serenity, FullEvent, Interaction};
+
+use crate::{gift, giveaway, reminder, Data, PoiseError};
+
+/// Handles gateway events which are not bound to a running command, like buttons of
+/// messages which have to keep working after a restart.
+pub async fn event_handler(
+    ctx: &serenity::Context,
+    event: &FullEvent,
+    _framework: poise::FrameworkContext<'_, Data, PoiseError>,
+    data: &Data,
+) -> Result<(), PoiseError> {
+    if let FullEvent::InteractionCreate {
+        interaction: Interaction::Component(interaction),
+    } = event
+    {
+        let custom_id = interaction.data.custom_id.as_str();
+
+        if let Some(giveaway_id) = custom_id.strip_prefix(giveaway::ENTER_BUTTON_PREFIX) {
+            giveaway::handle_enter(ctx, data, interaction, giveaway_id).await?;
+        } else if let Some(gamekey_id) = custom_id.strip_prefix(gift::ACCEPT_BUTTON_PREFIX) {
+            gift::handle_response(ctx, data, interaction, gamekey_id, true).await?;
+        } else if let Some(gamekey_id) = custom_id.strip_prefix(gift::DECLINE_BUTTON_PREFIX) {
+            gift::handle_response(ctx, data, interaction, gamekey_id, false).await?;
+        } else if let Some(gamekey_id) = custom_id.strip_prefix(reminder::CLAIM_BUTTON_PREFIX) {
+            reminder::handle_claim(ctx, data, interaction, gamekey_id).await?;
+        } else if let Some(gamekey_id) = custom_id.strip_prefix(reminder::GIVE_AWAY_BUTTON_PREFIX) {
+            reminder::handle_give_away(ctx, interaction, gamekey_id).await?;
+        } else if let Some(gamekey_id) = custom_id.strip_prefix(reminder::RECIPIENT_SELECT_PREFIX) {
+            reminder::handle_recipient(ctx, data, interaction, gamekey_id).await?;
+        }
+    }
+
+    Ok(())
+}
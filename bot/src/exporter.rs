@@ -0,0 +1,314 @@
+//! Exports the games of a user as csv, json or markdown table.
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use entity::{game, game_key, platform};
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+use crate::store;
+
+#[derive(Error, Debug)]
+pub enum ExportError {
+    #[error("Failed to write csv: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("Failed to write json: {0}")]
+    Serde(#[from] serde_json::Error),
+    #[error("Failed to finish csv: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum ExportFormat {
+    #[name = "CSV"]
+    Csv,
+    #[name = "JSON"]
+    Json,
+    #[name = "Markdown"]
+    Markdown,
+}
+
+impl ExportFormat {
+    pub fn file_extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Markdown => "md",
+        }
+    }
+}
+
+/// Optional columns of an export. The title and create date of a game are always exported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportColumn {
+    Platform,
+    KeyCount,
+    Expiration,
+    StoreLink,
+}
+
+impl ExportColumn {
+    fn header(&self) -> &'static str {
+        match self {
+            ExportColumn::Platform => "platform",
+            ExportColumn::KeyCount => "key_count",
+            ExportColumn::Expiration => "expiration_date",
+            ExportColumn::StoreLink => "store_link",
+        }
+    }
+}
+
+/// A game with the summarized details of its keys.
+#[derive(Clone, Debug)]
+pub struct ExportRow {
+    pub title: String,
+    pub create_date: DateTime<Utc>,
+    pub platforms: Vec<String>,
+    pub key_count: usize,
+    /// The earliest expiration date of the keys.
+    pub expiration_date: Option<NaiveDateTime>,
+    pub store_link: Option<String>,
+}
+
+impl ExportRow {
+    fn value(&self, column: ExportColumn) -> String {
+        match column {
+            ExportColumn::Platform => self.platforms.join(", "),
+            ExportColumn::KeyCount => self.key_count.to_string(),
+            ExportColumn::Expiration => self
+                .expiration_date
+                .map(|x| x.to_string())
+                .unwrap_or_default(),
+            ExportColumn::StoreLink => self.store_link.clone().unwrap_or_default(),
+        }
+    }
+}
+
+/// Groups keys by their game. Keys are expected to be ordered by game title.
+pub fn build_rows(keys: Vec<(game_key::Model, game::Model, platform::Model)>) -> Vec<ExportRow> {
+    let mut rows: Vec<(i32, ExportRow)> = Vec::new();
+
+    for (game_key, game, platform) in keys {
+        let store_link = game_key
+            .page_link
+            .or_else(|| store::store_link(&game, &platform.name))
+            .or(platform.store_link);
+
+        let index = match rows.iter().position(|(id, _)| *id == game.id) {
+            Some(i) => i,
+            None => {
+                rows.push((
+                    game.id,
+                    ExportRow {
+                        title: game.title,
+                        create_date: game.create_date,
+                        platforms: Vec::new(),
+                        key_count: 0,
+                        expiration_date: None,
+                        store_link: None,
+                    },
+                ));
+                rows.len() - 1
+            }
+        };
+        let row = &mut rows[index].1;
+
+        if !row.platforms.contains(&platform.name) {
+            row.platforms.push(platform.name);
+        }
+        row.key_count += 1;
+        row.expiration_date = match (row.expiration_date, game_key.expiration_date) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        if row.store_link.is_none() {
+            row.store_link = store_link;
+        }
+    }
+
+    rows.into_iter().map(|(_, row)| row).collect()
+}
+
+/// Writes the rows in the given format.
+pub fn export(
+    rows: &[ExportRow],
+    columns: &[ExportColumn],
+    format: ExportFormat,
+) -> Result<Vec<u8>, ExportError> {
+    match format {
+        ExportFormat::Csv => export_csv(rows, columns),
+        ExportFormat::Json => export_json(rows, columns),
+        ExportFormat::Markdown => Ok(export_markdown(rows, columns).into_bytes()),
+    }
+}
+
+fn headers(columns: &[ExportColumn]) -> Vec<&'static str> {
+    ["game_title", "create_date"]
+        .into_iter()
+        .chain(columns.iter().map(|x| x.header()))
+        .collect()
+}
+
+fn values(row: &ExportRow, columns: &[ExportColumn]) -> Vec<String> {
+    [row.title.clone(), row.create_date.to_rfc3339()]
+        .into_iter()
+        .chain(columns.iter().map(|x| row.value(*x)))
+        .collect()
+}
+
+fn export_csv(rows: &[ExportRow], columns: &[ExportColumn]) -> Result<Vec<u8>, ExportError> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+
+    writer.write_record(headers(columns))?;
+    for row in rows {
+        writer.write_record(values(row, columns))?;
+    }
+
+    writer.into_inner().map_err(|x| x.into_error().into())
+}
+
+fn export_json(rows: &[ExportRow], columns: &[ExportColumn]) -> Result<Vec<u8>, ExportError> {
+    let objects = rows
+        .iter()
+        .map(|row| {
+            let mut object = Map::new();
+            object.insert("game_title".to_owned(), Value::from(row.title.clone()));
+            object.insert(
+                "create_date".to_owned(),
+                Value::from(row.create_date.to_rfc3339()),
+            );
+
+            for column in columns {
+                let value = match column {
+                    ExportColumn::Platform => Value::from(row.platforms.clone()),
+                    ExportColumn::KeyCount => Value::from(row.key_count),
+                    ExportColumn::Expiration => row
+                        .expiration_date
+                        .map(|x| Value::from(x.to_string()))
+                        .unwrap_or(Value::Null),
+                    ExportColumn::StoreLink => row
+                        .store_link
+                        .clone()
+                        .map(Value::from)
+                        .unwrap_or(Value::Null),
+                };
+                object.insert(column.header().to_owned(), value);
+            }
+
+            Value::Object(object)
+        })
+        .collect::<Vec<Value>>();
+
+    Ok(serde_json::to_vec_pretty(&objects)?)
+}
+
+fn export_markdown(rows: &[ExportRow], columns: &[ExportColumn]) -> String {
+    let headers = headers(columns);
+    let mut table = format!(
+        "| {} |\n|{}|\n",
+        headers.join(" | "),
+        vec![" --- "; headers.len()].join("|")
+    );
+
+    for row in rows {
+        let values = values(row, columns)
+            .iter()
+            .map(|x| escape_markdown(x))
+            .collect::<Vec<String>>();
+        table.push_str(&format!("| {} |\n", values.join(" | ")));
+    }
+
+    table
+}
+
+fn escape_markdown(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace(['\n', '\r'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(title: &str) -> ExportRow {
+        ExportRow {
+            title: title.to_owned(),
+            create_date: DateTime::parse_from_rfc3339("2026-10-18T12:00:00Z")
+                .unwrap()
+                .to_utc(),
+            platforms: vec!["Steam".to_owned(), "GOG".to_owned()],
+            key_count: 2,
+            expiration_date: None,
+            store_link: Some("https://example.com/a|b".to_owned()),
+        }
+    }
+
+    fn export_string(rows: &[ExportRow], columns: &[ExportColumn], format: ExportFormat) -> String {
+        String::from_utf8(export(rows, columns, format).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn csv_quotes_delimiters_and_quotes() {
+        let csv = export_string(
+            &[row("Portal, \"Still Alive\" Edition")],
+            &[ExportColumn::Platform, ExportColumn::KeyCount],
+            ExportFormat::Csv,
+        );
+
+        assert_eq!(
+            csv,
+            "game_title,create_date,platform,key_count\n\
+             \"Portal, \"\"Still Alive\"\" Edition\",2026-10-18T12:00:00+00:00,\"Steam, GOG\",2\n"
+        );
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let title = "Line\nbreak, \"quoted\"";
+        let csv = export(&[row(title)], &[], ExportFormat::Csv).unwrap();
+
+        let mut reader = csv::Reader::from_reader(csv.as_slice());
+        let record = reader.records().next().unwrap().unwrap();
+
+        assert_eq!(&record[0], title);
+    }
+
+    #[test]
+    fn markdown_escapes_pipes_and_line_breaks() {
+        let markdown = export_string(
+            &[row("A | B\\C\nD")],
+            &[ExportColumn::Expiration, ExportColumn::StoreLink],
+            ExportFormat::Markdown,
+        );
+
+        assert_eq!(
+            markdown,
+            "| game_title | create_date | expiration_date | store_link |\n\
+             | --- | --- | --- | --- |\n\
+             | A \\| B\\\\C D | 2026-10-18T12:00:00+00:00 |  | https://example.com/a\\|b |\n"
+        );
+    }
+
+    #[test]
+    fn json_keeps_values_unescaped() {
+        let json = export(
+            &[row("A | \"B\"")],
+            &[ExportColumn::Platform, ExportColumn::Expiration],
+            ExportFormat::Json,
+        )
+        .unwrap();
+
+        let value: Value = serde_json::from_slice(&json).unwrap();
+
+        assert_eq!(
+            value,
+            serde_json::json!([{
+                "game_title": "A | \"B\"",
+                "create_date": "2026-10-18T12:00:00+00:00",
+                "platform": ["Steam", "GOG"],
+                "expiration_date": null,
+            }])
+        );
+    }
+}
@@ -0,0 +1,297 @@
+//! Ranked fuzzy matching of titles.
+//!
+//! A title is scored by how well it matches a query: exact matches rank highest, followed by
+//! prefix, token prefix and substring matches. Titles whose words are only similar to the
+//! query, e.g. because of a typo, are ranked by their edit distance.
+
+use std::collections::BTreeMap;
+
+/// Minimum similarity of two words to count as a fuzzy match.
+const MIN_SIMILARITY: f64 = 0.6;
+
+/// Ranks the candidates by how well they match the query and returns at most `limit` of them.
+///
+/// Candidates which do not match at all are left out. An empty query matches every candidate,
+/// which are then sorted alphabetically.
+pub fn rank<'a, I>(query: &str, candidates: I, limit: usize) -> Vec<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    rank_by(query, candidates, |x| x, limit)
+}
+
+/// Like [`rank`], but ranks any items by the title returned from `title`.
+pub fn rank_by<T, I, F>(query: &str, items: I, title: F, limit: usize) -> Vec<T>
+where
+    I: IntoIterator<Item = T>,
+    F: Fn(&T) -> &str,
+{
+    let query = normalize(query);
+
+    let mut scored = items
+        .into_iter()
+        .filter_map(|item| score(&query, title(&item)).map(|x| (x, item)))
+        .collect::<Vec<(u32, T)>>();
+
+    scored.sort_by(|(score_a, a), (score_b, b)| {
+        score_b
+            .cmp(score_a)
+            .then_with(|| title(a).to_lowercase().cmp(&title(b).to_lowercase()))
+    });
+    scored.truncate(limit);
+
+    scored.into_iter().map(|(_, x)| x).collect()
+}
+
+/// Checks whether two titles are equal ignoring case and punctuation.
+pub fn is_same_title(a: &str, b: &str) -> bool {
+    normalize(a) == normalize(b)
+}
+
+/// An index of the words of many titles, used to find match candidates without scoring every title.
+#[derive(Default)]
+pub struct SearchIndex {
+    tokens: BTreeMap<String, Vec<u32>>,
+}
+
+impl SearchIndex {
+    /// Maximum number of candidates which are scored by a search.
+    const MAX_CANDIDATES: usize = 5000;
+    /// Length of the word prefix used to find candidates if a word does not match exactly, e.g. because of a typo.
+    const FALLBACK_PREFIX_LEN: usize = 3;
+
+    /// Builds the index. Titles are referenced by their position.
+    pub fn new<'a, I>(titles: I) -> Self
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut tokens: BTreeMap<String, Vec<u32>> = BTreeMap::new();
+
+        for (index, title) in titles.into_iter().enumerate() {
+            for token in normalize(title).split(' ').filter(|x| !x.is_empty()) {
+                let ids = tokens.entry(token.to_owned()).or_default();
+                if ids.last() != Some(&(index as u32)) {
+                    ids.push(index as u32);
+                }
+            }
+        }
+
+        Self { tokens }
+    }
+
+    /// Finds the positions of titles which have a word starting like a word of the query.
+    /// The word with the fewest candidates is used to keep the result small.
+    pub fn candidates(&self, query: &str) -> Vec<usize> {
+        let query = normalize(query);
+
+        let mut best: Option<Vec<usize>> = None;
+        for token in query.split(' ').filter(|x| !x.is_empty()) {
+            let mut ids = self.with_prefix(token);
+            if ids.is_empty() {
+                let prefix = token
+                    .chars()
+                    .take(Self::FALLBACK_PREFIX_LEN)
+                    .collect::<String>();
+                ids = self.with_prefix(&prefix);
+            }
+
+            if !ids.is_empty() && best.as_ref().is_none_or(|x| ids.len() < x.len()) {
+                best = Some(ids);
+            }
+        }
+
+        let mut ids = best.unwrap_or_default();
+        ids.truncate(Self::MAX_CANDIDATES);
+        ids
+    }
+
+    fn with_prefix(&self, prefix: &str) -> Vec<usize> {
+        let mut ids = self
+            .tokens
+            .range(prefix.to_owned()..)
+            .take_while(|(token, _)| token.starts_with(prefix))
+            .flat_map(|(_, ids)| ids.iter().map(|x| *x as usize))
+            .collect::<Vec<usize>>();
+
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+}
+
+/// Scores how well a candidate matches an already normalized query. Higher is better.
+fn score(query: &str, candidate: &str) -> Option<u32> {
+    if query.is_empty() {
+        return Some(0);
+    }
+
+    let candidate = normalize(candidate);
+    let tokens = candidate.split(' ').collect::<Vec<&str>>();
+    let query_tokens = query.split(' ').collect::<Vec<&str>>();
+
+    if candidate == query {
+        return Some(1000);
+    }
+    if candidate.starts_with(query) {
+        return Some(900);
+    }
+    if tokens.iter().any(|x| x.starts_with(query)) {
+        return Some(800);
+    }
+    if candidate.contains(query) {
+        return Some(700);
+    }
+    if query_tokens
+        .iter()
+        .all(|q| tokens.iter().any(|x| x.starts_with(q)))
+    {
+        return Some(600);
+    }
+
+    // Every word of the query has to be similar to some word of the candidate.
+    let mut similarity = 0.0;
+    for query_token in &query_tokens {
+        let best = tokens
+            .iter()
+            .map(|x| token_similarity(query_token, x))
+            .fold(0.0, f64::max);
+
+        if best < MIN_SIMILARITY {
+            return None;
+        }
+        similarity += best;
+    }
+
+    Some((similarity / query_tokens.len() as f64 * 500.0) as u32)
+}
+
+/// Similarity of two words between 0 and 1. A word is also compared to the beginning of the
+/// other word, so incomplete words still match.
+fn token_similarity(query: &str, token: &str) -> f64 {
+    let query = query.chars().collect::<Vec<char>>();
+    let token = token.chars().collect::<Vec<char>>();
+
+    let full = similarity(&query, &token);
+    let prefix = if token.len() > query.len() {
+        similarity(&query, &token[..query.len()])
+    } else {
+        0.0
+    };
+
+    full.max(prefix)
+}
+
+fn similarity(a: &[char], b: &[char]) -> f64 {
+    let max_len = a.len().max(b.len());
+    if max_len == 0 {
+        return 1.0;
+    }
+
+    1.0 - levenshtein(a, b) as f64 / max_len as f64
+}
+
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut previous = (0..=b.len()).collect::<Vec<usize>>();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, char_a) in a.iter().enumerate() {
+        current[0] = i + 1;
+
+        for (j, char_b) in b.iter().enumerate() {
+            let cost = usize::from(char_a != char_b);
+            current[j + 1] = (previous[j] + cost)
+                .min(previous[j + 1] + 1)
+                .min(current[j] + 1);
+        }
+
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+/// Lowercases a title and replaces punctuation with single spaces.
+fn normalize(value: &str) -> String {
+    value
+        .to_lowercase()
+        .split(|x: char| !x.is_alphanumeric())
+        .filter(|x| !x.is_empty())
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TITLES: [&str; 6] = [
+        "Portal 2",
+        "The Witcher 3: Wild Hunt",
+        "The Witcher 2",
+        "Portal",
+        "Half-Life 2: Episode One",
+        "Witchery",
+    ];
+
+    #[test]
+    fn rank_by_match_kind() {
+        assert_eq!(rank("portal", TITLES, 10), vec!["Portal", "Portal 2"]);
+        // A prefix of the title ranks before a prefix of a word.
+        assert_eq!(
+            rank("witcher", TITLES, 10),
+            vec!["Witchery", "The Witcher 2", "The Witcher 3: Wild Hunt"]
+        );
+    }
+
+    #[test]
+    fn witcher_finds_the_witcher_3() {
+        assert_eq!(
+            rank("witcher", ["Twitch", "Witch It", "The Witcher 3"], 10),
+            vec!["The Witcher 3", "Witch It"]
+        );
+        assert_eq!(
+            rank("witcher 3", TITLES, 1),
+            vec!["The Witcher 3: Wild Hunt"]
+        );
+    }
+
+    #[test]
+    fn rank_typos_and_incomplete_words() {
+        assert_eq!(
+            rank("wticher", TITLES, 10)[..2],
+            ["The Witcher 2", "The Witcher 3: Wild Hunt"]
+        );
+        assert_eq!(
+            rank("half epi", TITLES, 10),
+            vec!["Half-Life 2: Episode One"]
+        );
+        assert!(rank("minecraft", TITLES, 10).is_empty());
+    }
+
+    #[test]
+    fn empty_query_sorts_alphabetically() {
+        assert_eq!(
+            rank("", TITLES, 3),
+            vec!["Half-Life 2: Episode One", "Portal", "Portal 2"]
+        );
+    }
+
+    #[test]
+    fn same_title_ignores_case_and_punctuation() {
+        assert!(is_same_title(
+            "The Witcher 3: Wild Hunt",
+            "the witcher 3 - wild hunt"
+        ));
+        assert!(!is_same_title("Portal", "Portal 2"));
+    }
+
+    #[test]
+    fn index_finds_candidates_by_word_prefix() {
+        let index = SearchIndex::new(TITLES);
+
+        assert_eq!(index.candidates("witch"), vec![1, 2, 5]);
+        assert_eq!(index.candidates("the wild"), vec![1]);
+        assert_eq!(index.candidates("portl"), vec![0, 3]);
+        assert!(index.candidates("minecraft").is_empty());
+    }
+}
@@ -0,0 +1,245 @@
+use chrono::{DateTime, Utc};
+use entity::{game, game_key, sea_orm_active_enums::KeyState};
+use gemuki_service::{
+    crypto::KeyCipher,
+    mutation::GameKeyMutation,
+    policy::ClaimOutcome,
+    pool::PoolMember,
+    query::{GameKeyQuery, GameQuery},
+};
+use log::error;
+use migration::sea_orm::DbConn;
+use poise::serenity_prelude::{
+    self as serenity, ButtonStyle, Color, ComponentInteraction, CreateActionRow, CreateButton,
+    CreateEmbed, CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage, User,
+    UserId,
+};
+use tokio::sync::RwLock;
+
+use crate::{commands::gamekey::denial_message, Data, PoiseError};
+
+pub static ACCEPT_BUTTON_PREFIX: &str = "gift-accept-";
+pub static DECLINE_BUTTON_PREFIX: &str = "gift-decline-";
+
+/// Creates the message offering a gifted key to its recipient.
+pub fn create_gift_message(
+    game: &game::Model,
+    game_key: &game_key::Model,
+    owner_id: u64,
+) -> CreateMessage {
+    let embed = CreateEmbed::new()
+        .colour(Color::DARK_GREEN)
+        .title(format!("A key for {} has been gifted to you", game.title))
+        .description(format!(
+            "<@{owner_id}> wants to gift you a key. Accept the gift to reveal the key."
+        ));
+    let embed = match &game.image_link {
+        Some(link) => embed.image(link),
+        None => embed,
+    };
+
+    let components = CreateActionRow::Buttons(vec![
+        CreateButton::new(format!("{ACCEPT_BUTTON_PREFIX}{}", game_key.id))
+            .label("Accept")
+            .style(ButtonStyle::Success),
+        CreateButton::new(format!("{DECLINE_BUTTON_PREFIX}{}", game_key.id))
+            .label("Decline")
+            .style(ButtonStyle::Danger),
+    ]);
+
+    CreateMessage::new()
+        .embed(embed)
+        .components(vec![components])
+}
+
+/// Offers an unused key of its owner to another user by DM. The key is reserved for the user
+/// until the gift is accepted or declined. Returns the message for the owner.
+pub async fn offer_key(
+    http: &serenity::Http,
+    db: &DbConn,
+    key_cipher: &RwLock<KeyCipher>,
+    owner_id: u64,
+    gamekey_id: i32,
+    user: &User,
+) -> Result<String, PoiseError> {
+    if user.bot || user.id == owner_id {
+        return Ok("You can not gift a key to yourself or a bot.".to_owned());
+    }
+
+    let cipher = key_cipher.read().await;
+
+    let game_key = match GameKeyQuery::get_one(db, &cipher, gamekey_id, owner_id).await? {
+        Some(g) => g,
+        None => return Ok(format!("The key `{}` does not exist.", gamekey_id)),
+    };
+
+    if game_key.keystate != KeyState::Unused {
+        return Ok(format!("The key `{}` is already used.", gamekey_id));
+    }
+
+    if game_key
+        .expiration_date
+        .is_some_and(|x| x < Utc::now().naive_utc())
+    {
+        return Ok("The key is already expired.".to_owned());
+    }
+
+    if let Some(recipient) = game_key.recipient_user_id {
+        return Ok(format!(
+            "The key `{}` has already been gifted to <@{}>.",
+            gamekey_id, recipient
+        ));
+    }
+
+    let game = match GameQuery::get_one(db, game_key.game_id).await? {
+        Some(g) => g,
+        None => return Ok(format!("The game `{}` does not exist.", game_key.game_id)),
+    };
+
+    let game_key =
+        match GameKeyMutation::offer(db, &cipher, game_key.id, owner_id, user.id.get()).await? {
+            Some(k) => k,
+            None => return Ok(format!("The key `{}` is no longer available.", gamekey_id)),
+        };
+
+    drop(cipher);
+
+    let message = create_gift_message(&game, &game_key, owner_id);
+
+    let content = match user.id.direct_message(http, message).await {
+        Ok(_) => format!("Offered the key for `{}` to <@{}>.", game.title, user.id),
+        Err(why) => {
+            error!("Could not send gift to {} because of '{}'.", user.id, why);
+
+            GameKeyMutation::decline_gift(db, game_key.id, user.id.get()).await?;
+
+            format!("Could not send a DM to <@{}>.", user.id)
+        }
+    };
+
+    Ok(content)
+}
+
+/// Handles the accept or decline button of a gift offer.
+pub async fn handle_response(
+    ctx: &serenity::Context,
+    data: &Data,
+    interaction: &ComponentInteraction,
+    gamekey_id: &str,
+    accepted: bool,
+) -> Result<(), PoiseError> {
+    let db = &data.conn;
+    let recipient_id = interaction.user.id.get();
+
+    let game_key = match gamekey_id.parse() {
+        Ok(id) => {
+            let cipher = data.key_cipher.read().await;
+            GameKeyQuery::get_one_by_recipient(db, &cipher, id, recipient_id).await?
+        }
+        Err(_) => None,
+    }
+    .filter(|x| x.keystate == KeyState::Reserved);
+
+    let game_key = match game_key {
+        Some(k) => k,
+        None => {
+            respond(ctx, interaction, "This gift is no longer available.").await?;
+            return Ok(());
+        }
+    };
+    let owner_id = game_key.create_user_id as u64;
+    let title = GameQuery::get_one(db, game_key.game_id)
+        .await?
+        .map(|x| x.title)
+        .unwrap_or("Unknown game".to_owned());
+
+    if !accepted {
+        if !GameKeyMutation::decline_gift(db, game_key.id, recipient_id).await? {
+            respond(ctx, interaction, "This gift is no longer available.").await?;
+            return Ok(());
+        }
+
+        respond(ctx, interaction, "You declined the gift.").await?;
+        notify_owner(
+            ctx,
+            owner_id,
+            format!(
+                "<@{recipient_id}> declined your gift of `{title}`. The key is available again."
+            ),
+        )
+        .await;
+
+        return Ok(());
+    }
+
+    let mut member = PoolMember::new(recipient_id);
+    member.account_date =
+        DateTime::from_timestamp(interaction.user.id.created_at().unix_timestamp(), 0);
+
+    let outcome = {
+        let cipher = data.key_cipher.read().await;
+        GameKeyMutation::accept_gift(db, &cipher, game_key.id, &member, &data.claim_policy).await?
+    };
+    match outcome {
+        ClaimOutcome::Claimed(game_key) => {
+            respond(
+                ctx,
+                interaction,
+                &format!("Your key for `{title}`: `{}`", game_key.value),
+            )
+            .await?;
+            notify_owner(
+                ctx,
+                owner_id,
+                format!("<@{recipient_id}> accepted your gift of `{title}`."),
+            )
+            .await;
+        }
+        // The offer stays open, so the gift can be accepted once the policy allows it.
+        ClaimOutcome::Denied(denial) => {
+            interaction
+                .create_response(
+                    ctx,
+                    CreateInteractionResponse::Message(
+                        CreateInteractionResponseMessage::new()
+                            .content(denial_message(&denial))
+                            .ephemeral(true),
+                    ),
+                )
+                .await?;
+        }
+        ClaimOutcome::Unavailable => {
+            respond(ctx, interaction, "This gift is no longer available.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn respond(
+    ctx: &serenity::Context,
+    interaction: &ComponentInteraction,
+    content: &str,
+) -> Result<(), PoiseError> {
+    interaction
+        .create_response(
+            ctx,
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .components(Vec::new()),
+            ),
+        )
+        .await?;
+
+    Ok(())
+}
+
+async fn notify_owner(ctx: &serenity::Context, owner_id: u64, content: String) {
+    if let Err(why) = UserId::new(owner_id)
+        .direct_message(ctx, CreateMessage::new().content(content))
+        .await
+    {
+        error!("Could not notify gift owner {owner_id} because of '{why}'.");
+    }
+}
@@ -0,0 +1,292 @@
+use chrono::Utc;
+use entity::{
+    game, giveaway,
+    sea_orm_active_enums::{GiveawayState, KeyState},
+};
+use gemuki_service::{
+    crypto::KeyCipher,
+    mutation::{GameKeyMutation, GiveawayMutation},
+    policy::ClaimOutcome,
+    query::{GameKeyQuery, GameQuery, GiveawayQuery},
+};
+use log::{error, info};
+use migration::sea_orm::DbConn;
+use poise::serenity_prelude::{
+    self as serenity, ButtonStyle, ChannelId, Color, ComponentInteraction, CreateActionRow,
+    CreateButton, CreateEmbed, CreateInteractionResponse, CreateInteractionResponseMessage,
+    CreateMessage, EditMessage, MessageId, UserId,
+};
+use rand::Rng;
+use tokio::sync::RwLock;
+
+use crate::{Data, PoiseError};
+
+pub static ENTER_BUTTON_PREFIX: &str = "giveaway-enter-";
+
+/// Creates the embed announcing a giveaway.
+pub fn create_giveaway_embed(
+    game: Option<&game::Model>,
+    giveaway: &giveaway::Model,
+) -> CreateEmbed {
+    let embed = CreateEmbed::new()
+        .colour(Color::GOLD)
+        .title(format!(
+            "Giveaway: {}",
+            game.map(|x| x.title.as_str()).unwrap_or("Unknown game")
+        ))
+        .description("Press **Enter** to take part in the giveaway.")
+        .field("Hosted by", format!("<@{}>", giveaway.host_user_id), true)
+        .field(
+            "Ends",
+            format!("<t:{}:R>", giveaway.end_date.timestamp()),
+            true,
+        );
+
+    let embed = match giveaway.winner_user_id {
+        Some(winner) => embed.description("The giveaway has ended.").field(
+            "Winner",
+            format!("<@{winner}>"),
+            true,
+        ),
+        None if giveaway.state != GiveawayState::Running => {
+            embed.description("The giveaway has ended without a winner.")
+        }
+        None => embed,
+    };
+
+    match game.and_then(|x| x.image_link.clone()) {
+        Some(link) => embed.image(link),
+        None => embed,
+    }
+}
+
+/// Creates the button used to enter a giveaway.
+pub fn create_enter_button(giveaway_id: i32) -> CreateActionRow {
+    CreateActionRow::Buttons(vec![CreateButton::new(format!(
+        "{ENTER_BUTTON_PREFIX}{giveaway_id}"
+    ))
+    .label("Enter")
+    .emoji('🎉')
+    .style(ButtonStyle::Primary)])
+}
+
+/// Handles a press of the enter button of a giveaway.
+pub async fn handle_enter(
+    ctx: &serenity::Context,
+    data: &Data,
+    interaction: &ComponentInteraction,
+    giveaway_id: &str,
+) -> Result<(), PoiseError> {
+    let db = &data.conn;
+
+    let giveaway = match giveaway_id.parse() {
+        Ok(id) => GiveawayQuery::get_one(db, id).await?,
+        Err(_) => None,
+    };
+
+    let message = match giveaway {
+        Some(g) if g.state != GiveawayState::Running || g.end_date <= Utc::now() => {
+            "This giveaway has already ended."
+        }
+        Some(g) if g.host_user_id == interaction.user.id.get() as i64 => {
+            "You can not enter your own giveaway."
+        }
+        Some(g) => {
+            if GiveawayMutation::enter(db, g.id, interaction.user.id.get()).await? {
+                "You entered the giveaway. Good luck!"
+            } else {
+                "You already entered this giveaway."
+            }
+        }
+        None => "This giveaway does not exist.",
+    };
+
+    interaction
+        .create_response(
+            ctx,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(message)
+                    .ephemeral(true),
+            ),
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Draws winners for all giveaways which have ended.
+pub async fn draw_due_giveaways(
+    http: &serenity::Http,
+    db: &DbConn,
+    key_cipher: &RwLock<KeyCipher>,
+) -> Result<(), PoiseError> {
+    for giveaway in GiveawayQuery::get_all_due(db, Utc::now()).await? {
+        if let Err(why) = draw(http, db, key_cipher, &giveaway).await {
+            error!(
+                "Could not draw giveaway {} because of '{why}'.",
+                giveaway.id
+            );
+        }
+    }
+
+    Ok(())
+}
+
+async fn draw(
+    http: &serenity::Http,
+    db: &DbConn,
+    key_cipher: &RwLock<KeyCipher>,
+    giveaway: &giveaway::Model,
+) -> Result<(), PoiseError> {
+    let game_key = GameKeyQuery::get_one(
+        db,
+        &*key_cipher.read().await,
+        giveaway.game_key_id,
+        giveaway.host_user_id as u64,
+    )
+    .await?;
+    let game = match &game_key {
+        Some(k) => GameQuery::get_one(db, k.game_id).await?,
+        None => None,
+    };
+
+    if !game_key.is_some_and(|x| x.keystate == KeyState::Reserved) {
+        return fail(http, db, giveaway, game.as_ref()).await;
+    }
+
+    let entrants = GiveawayQuery::get_entrants(db, giveaway.id).await?;
+    if entrants.is_empty() {
+        if !GameKeyMutation::release_from_giveaway(db, giveaway.game_key_id).await? {
+            return fail(http, db, giveaway, game.as_ref()).await;
+        }
+
+        if let Some(giveaway) =
+            GiveawayMutation::finish(db, giveaway.id, GiveawayState::Finished, None).await?
+        {
+            announce(
+                http,
+                &giveaway,
+                game.as_ref(),
+                "Nobody entered the giveaway.",
+            )
+            .await?;
+        }
+        return Ok(());
+    }
+
+    let winner = {
+        let mut rng = rand::rng();
+        entrants[rng.random_range(0..entrants.len())] as u64
+    };
+
+    let outcome = {
+        let cipher = key_cipher.read().await;
+        GameKeyMutation::hand_out(db, &cipher, giveaway.game_key_id, winner).await?
+    };
+    let game_key = match outcome {
+        ClaimOutcome::Claimed(k) => k,
+        ClaimOutcome::Denied(_) | ClaimOutcome::Unavailable => {
+            return fail(http, db, giveaway, game.as_ref()).await;
+        }
+    };
+
+    let giveaway =
+        match GiveawayMutation::finish(db, giveaway.id, GiveawayState::Finished, Some(winner))
+            .await?
+        {
+            Some(g) => g,
+            None => return Ok(()),
+        };
+
+    let title = game
+        .as_ref()
+        .map(|x| x.title.as_str())
+        .unwrap_or("Unknown game");
+    let dm = UserId::new(winner)
+        .direct_message(
+            http,
+            CreateMessage::new().content(format!(
+                "You won the giveaway for `{title}`! Your key: `{}`",
+                game_key.value
+            )),
+        )
+        .await;
+
+    if let Err(why) = dm {
+        error!("Could not send giveaway key to winner {winner} because of '{why}'.");
+        announce(
+            http,
+            &giveaway,
+            game.as_ref(),
+            &format!("Congratulations <@{winner}>! I could not DM you, please contact <@{}> for your key.", giveaway.host_user_id),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    announce(
+        http,
+        &giveaway,
+        game.as_ref(),
+        &format!("Congratulations <@{winner}>! Your key has been sent to you via DM."),
+    )
+    .await?;
+
+    info!("Giveaway {} has been won by {winner}.", giveaway.id);
+
+    Ok(())
+}
+
+/// Ends a giveaway whose key is no longer available.
+async fn fail(
+    http: &serenity::Http,
+    db: &DbConn,
+    giveaway: &giveaway::Model,
+    game: Option<&game::Model>,
+) -> Result<(), PoiseError> {
+    if let Some(giveaway) =
+        GiveawayMutation::finish(db, giveaway.id, GiveawayState::Failed, None).await?
+    {
+        announce(
+            http,
+            &giveaway,
+            game,
+            "The key of this giveaway is no longer available.",
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+async fn announce(
+    http: &serenity::Http,
+    giveaway: &giveaway::Model,
+    game: Option<&game::Model>,
+    content: &str,
+) -> Result<(), PoiseError> {
+    let channel = ChannelId::new(giveaway.channel_id as u64);
+
+    if let Some(message_id) = giveaway.message_id {
+        channel
+            .edit_message(
+                http,
+                MessageId::new(message_id as u64),
+                EditMessage::new()
+                    .embed(create_giveaway_embed(game, giveaway))
+                    .components(Vec::new()),
+            )
+            .await?;
+    }
+
+    let message = CreateMessage::new().content(content);
+    let message = match giveaway.message_id {
+        Some(message_id) => message.reference_message((channel, MessageId::new(message_id as u64))),
+        None => message,
+    };
+
+    channel.send_message(http, message).await?;
+
+    Ok(())
+}
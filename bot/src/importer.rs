@@ -0,0 +1,307 @@
+//! Importers for order exports of bundle stores.
+//!
+//! Supported are the order json of Humble Bundle (`/api/v1/order/<id>?all_tpkds=true`) and the
+//! order export of Fanatical. Every revealed key of an order becomes a [`KeyImport`] with the
+//! name of the bundle as its source.
+
+use std::collections::HashMap;
+
+use chrono::{Duration, Utc};
+use entity::game;
+use gemuki_service::{
+    import::{KeyImport, KeyImportOutcome, KeyImportResult},
+    query::GameQuery,
+};
+use log::error;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{steam, Data, PoiseError};
+
+#[derive(Error, Debug)]
+pub enum ImportError {
+    #[error("The file is not valid json: {0}")]
+    Serde(#[from] serde_json::Error),
+    #[error("The file is neither a Humble Bundle nor a Fanatical order.")]
+    UnknownFormat,
+}
+
+/// Rows of an order which are ready to be imported.
+#[derive(Clone, Debug, Default)]
+pub struct PreparedImport {
+    pub rows: Vec<KeyImport>,
+    pub failed: Vec<KeyImportResult>,
+    /// Games which do not exist yet. They are created by the import together with their keys.
+    pub new_games: Vec<game::Model>,
+}
+
+/// A key of a bundle order before it has been mapped to a game.
+#[derive(Clone, Debug)]
+pub struct OrderKey {
+    pub product_name: String,
+    pub platform: Option<String>,
+    pub value: Option<String>,
+    pub steam_appid: Option<u32>,
+    pub expires_in_days: Option<i64>,
+    pub source: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct HumbleOrder {
+    product: HumbleProduct,
+    tpkd_dict: HumbleTpkdDict,
+}
+
+#[derive(Deserialize, Debug)]
+struct HumbleProduct {
+    human_name: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct HumbleTpkdDict {
+    #[serde(default)]
+    all_tpks: Vec<HumbleTpk>,
+}
+
+#[derive(Deserialize, Debug)]
+struct HumbleTpk {
+    human_name: String,
+    key_type: String,
+    redeemed_key_val: Option<String>,
+    steam_app_id: Option<u32>,
+    num_days_until_expired: Option<i64>,
+}
+
+#[derive(Deserialize, Debug)]
+struct FanaticalOrder {
+    items: Vec<FanaticalItem>,
+}
+
+#[derive(Deserialize, Debug)]
+struct FanaticalItem {
+    name: String,
+    #[serde(default)]
+    keys: Vec<FanaticalKey>,
+}
+
+#[derive(Deserialize, Debug)]
+struct FanaticalKey {
+    name: String,
+    #[serde(default)]
+    drm: Vec<String>,
+    key: Option<String>,
+    steam_id: Option<u32>,
+}
+
+/// Parses a saved Humble Bundle order or Fanatical order export. The store is detected by the structure of the json.
+pub fn parse_order(data: &[u8]) -> Result<Vec<OrderKey>, ImportError> {
+    let value: serde_json::Value = serde_json::from_slice(data)?;
+    let orders = match value {
+        serde_json::Value::Array(orders) => orders,
+        order => vec![order],
+    };
+
+    let mut keys = Vec::new();
+    for order in orders {
+        if order.get("tpkd_dict").is_some() {
+            keys.extend(parse_humble(serde_json::from_value(order)?));
+        } else if order.get("items").is_some() {
+            keys.extend(parse_fanatical(serde_json::from_value(order)?));
+        } else {
+            return Err(ImportError::UnknownFormat);
+        }
+    }
+
+    Ok(keys)
+}
+
+fn parse_humble(order: HumbleOrder) -> Vec<OrderKey> {
+    order
+        .tpkd_dict
+        .all_tpks
+        .into_iter()
+        .map(|x| OrderKey {
+            product_name: x.human_name,
+            platform: map_platform(&x.key_type),
+            value: x.redeemed_key_val,
+            steam_appid: x.steam_app_id,
+            expires_in_days: x.num_days_until_expired.filter(|x| *x > 0),
+            source: order.product.human_name.clone(),
+        })
+        .collect()
+}
+
+fn parse_fanatical(order: FanaticalOrder) -> Vec<OrderKey> {
+    order
+        .items
+        .into_iter()
+        .flat_map(|item| {
+            item.keys.into_iter().map(move |x| OrderKey {
+                product_name: x.name,
+                platform: x.drm.iter().find_map(|x| map_platform(x)),
+                value: x.key,
+                steam_appid: x.steam_id,
+                expires_in_days: None,
+                source: item.name.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Maps the key type of a store to the name of a platform.
+fn map_platform(key_type: &str) -> Option<String> {
+    let platform = match key_type.to_lowercase().as_str() {
+        "steam" => "Steam",
+        "epic" | "epic games" | "epic_games" => "Epic Games",
+        "uplay" | "ubisoft" | "ubisoft connect" => "Ubisoft Connect",
+        "origin" | "ea" | "ea app" | "ea play" => "EA Play",
+        _ => return None,
+    };
+
+    Some(platform.to_owned())
+}
+
+/// Maps the keys of an order to games and turns them into rows for the key import.
+///
+/// Games which do not exist yet are prepared from their steam details when possible, otherwise
+/// with their product name only. Keys without a value or platform are returned as failures.
+pub async fn prepare_import(
+    data: &Data,
+    user_id: u64,
+    keys: Vec<OrderKey>,
+) -> Result<PreparedImport, PoiseError> {
+    let mut prepared = PreparedImport::default();
+    let mut titles: HashMap<String, String> = HashMap::new();
+
+    for (index, key) in keys.into_iter().enumerate() {
+        let line = index as u64 + 1;
+
+        let value = match key.value {
+            Some(v) if !v.trim().is_empty() => v.trim().to_owned(),
+            _ => {
+                prepared.failed.push(KeyImportResult {
+                    line,
+                    game_title: key.product_name,
+                    outcome: KeyImportOutcome::Failed(
+                        "The key has not been revealed in the store yet.".to_owned(),
+                    ),
+                });
+                continue;
+            }
+        };
+        let platform = match key.platform {
+            Some(p) => p,
+            None => {
+                prepared.failed.push(KeyImportResult {
+                    line,
+                    game_title: key.product_name,
+                    outcome: KeyImportOutcome::Failed(
+                        "The platform of the key is not supported.".to_owned(),
+                    ),
+                });
+                continue;
+            }
+        };
+
+        let title = match titles.get(&key.product_name) {
+            Some(t) => t.clone(),
+            None => {
+                let (title, new_game) =
+                    resolve_game(data, user_id, &key.product_name, key.steam_appid).await?;
+                titles.insert(key.product_name.clone(), title.clone());
+                if !prepared.new_games.iter().any(|x| x.title == title) {
+                    prepared.new_games.extend(new_game);
+                }
+                title
+            }
+        };
+
+        prepared.rows.push(KeyImport {
+            line,
+            game_title: title,
+            platform,
+            value,
+            page_link: None,
+            notes: None,
+            expiration_date: key
+                .expires_in_days
+                .map(|x| (Utc::now() + Duration::days(x)).naive_utc()),
+            source: Some(key.source),
+        });
+    }
+
+    Ok(prepared)
+}
+
+/// Finds the title of the game for a product. Returns the game to create if it does not exist.
+async fn resolve_game(
+    data: &Data,
+    user_id: u64,
+    product_name: &str,
+    steam_appid: Option<u32>,
+) -> Result<(String, Option<game::Model>), PoiseError> {
+    let db = &data.conn;
+
+    if GameQuery::get_by_title(db, product_name).await?.is_some()
+        || GameQuery::get_trashed_by_title(db, product_name)
+            .await?
+            .is_some()
+    {
+        return Ok((product_name.to_owned(), None));
+    }
+
+    let app_details = match steam_appid {
+        Some(appid) => {
+            data.steam_client
+                .get_app_details(appid, &data.steam_country)
+                .await
+        }
+        None => {
+            steam::find_app_details(
+                data.steam_client.as_ref(),
+                &data.steam_app_cache,
+                product_name,
+                &data.steam_country,
+            )
+            .await
+        }
+    };
+    let model = match app_details {
+        Ok(Some(details)) => {
+            if GameQuery::get_by_title(db, &details.name).await?.is_some()
+                || GameQuery::get_trashed_by_title(db, &details.name)
+                    .await?
+                    .is_some()
+            {
+                return Ok((details.name, None));
+            }
+            details.to_game(user_id)
+        }
+        Ok(None) => bare_game(product_name, user_id),
+        Err(why) => {
+            error!("Could not retrieve game data of '{product_name}' from steam, {why:?}");
+            bare_game(product_name, user_id)
+        }
+    };
+
+    Ok((model.title.clone(), Some(model)))
+}
+
+fn bare_game(title: &str, user_id: u64) -> game::Model {
+    game::Model {
+        id: 0,
+        title: title.to_owned(),
+        description: None,
+        image_link: None,
+        create_date: Utc::now(),
+        create_user_id: user_id as i64,
+        modify_date: None,
+        modify_user_id: None,
+        deleted_date: None,
+        deleted_user_id: None,
+        metadata: None,
+        steam_appid: None,
+        gog_id: None,
+        epic_slug: None,
+    }
+}
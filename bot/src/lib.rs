@@ -1,23 +1,58 @@
 mod cache;
 mod commands;
+mod events;
+mod exporter;
+mod fuzzy;
+mod gift;
+mod giveaway;
+mod importer;
 mod paginate;
+mod refresh;
+mod reminder;
 mod steam;
+mod store;
+mod tasks;
+mod wishlist;
+
+use std::sync::Arc;
 
 use async_mutex::Mutex;
-use cache::{GameTitleCache, SteamAppCache};
+use cache::{GameTitleCache, PriceCache, SteamAppCache};
 use chrono::Duration;
 use commands::statistic::statistics;
-use commands::{game::game, gamekey::gamekey, version::version};
+use commands::{
+    admin::admin, audit::audit, game::game, gamekey::gamekey, giveaway::giveaway, pool::pool,
+    settings::settings, trash::trash, version::version, wishlist::wishlist,
+};
+use gemuki_service::{crypto::KeyCipher, policy::ClaimPolicy};
 use migration::sea_orm::DatabaseConnection;
 use migration::{sea_orm::Database, Migrator, MigratorTrait};
 use poise::serenity_prelude::{self as serenity};
+use refresh::RefreshReport;
+use steam::{FixtureSteamClient, ReqwestSteamClient, SteamClient};
+use tokio::sync::RwLock;
 
 pub type PoiseError = Box<dyn std::error::Error + Send + Sync>;
 
 pub struct Data {
     conn: DatabaseConnection,
     game_title_cache: Mutex<GameTitleCache>,
-    steam_app_cache: Mutex<SteamAppCache>,
+    steam_app_cache: Arc<Mutex<SteamAppCache>>,
+    steam_client: Arc<dyn SteamClient>,
+    refresh_report: Arc<Mutex<RefreshReport>>,
+    /// Country code of the steam store region used for users without their own setting.
+    steam_country: String,
+    price_cache: Mutex<PriceCache>,
+    /// Read while keys are encrypted or decrypted, written while the master key is rotated.
+    key_cipher: Arc<RwLock<KeyCipher>>,
+    trash_retention: Duration,
+    /// Limits for claiming keys of other users.
+    claim_policy: ClaimPolicy,
+}
+
+/// Reads a number from an environment variable. Returns `None` if it is missing or invalid.
+fn env_number<T: std::str::FromStr>(name: &str) -> Option<T> {
+    std::env::var(name).ok().and_then(|x| x.parse().ok())
 }
 
 #[tokio::main]
@@ -26,28 +61,109 @@ async fn run() -> Result<(), PoiseError> {
 
     dotenvy::dotenv().ok();
     let token = std::env::var("GEMUKI_TOKEN").expect("Missing GEMUKI_TOKEN.");
+    let master_key = std::env::var("GEMUKI_MASTER_KEY").expect("Missing GEMUKI_MASTER_KEY.");
     let db_url =
         std::env::var("GEMUKI_DATABASE_URL").expect("GEMUKI_DATABASE_URL is not set in .env file");
+    let trash_retention = env_number("GEMUKI_TRASH_RETENTION_DAYS")
+        .map(Duration::days)
+        .unwrap_or(Duration::days(30));
+    let metadata_refresh_interval = env_number("GEMUKI_METADATA_REFRESH_HOURS")
+        .map(Duration::hours)
+        .unwrap_or(Duration::hours(24));
+    let steam_country = std::env::var("GEMUKI_STEAM_COUNTRY")
+        .ok()
+        .and_then(|x| steam::parse_country_code(&x))
+        .unwrap_or("de".to_owned());
+    let claim_policy = ClaimPolicy {
+        max_claims: env_number("GEMUKI_CLAIM_LIMIT"),
+        window: env_number("GEMUKI_CLAIM_WINDOW_HOURS")
+            .map(Duration::hours)
+            .unwrap_or(Duration::hours(24)),
+        max_per_game: env_number("GEMUKI_CLAIM_LIMIT_PER_GAME"),
+        min_account_age: env_number("GEMUKI_CLAIM_MIN_ACCOUNT_DAYS").map(Duration::days),
+        min_membership_age: env_number("GEMUKI_CLAIM_MIN_MEMBER_DAYS").map(Duration::days),
+    };
+    let expiry_reminder_days = std::env::var("GEMUKI_EXPIRY_REMINDER_DAYS")
+        .map(|x| reminder::parse_windows(&x))
+        .unwrap_or(vec![30, 7, 1]);
+    let steam_client: Arc<dyn SteamClient> = match std::env::var("GEMUKI_STEAM_FIXTURES") {
+        Ok(dir) => Arc::new(FixtureSteamClient::new(dir)),
+        Err(_) => Arc::new(ReqwestSteamClient::default()),
+    };
     let intents = serenity::GatewayIntents::non_privileged();
 
     let conn = Database::connect(&db_url).await?;
     Migrator::up(&conn, None).await?;
 
     let title_cache = GameTitleCache::init(&conn, Duration::seconds(3600)).await;
-    let app_cache = SteamAppCache::init(Duration::seconds(3600)).await;
+    let app_cache = Arc::new(Mutex::new(SteamAppCache::load(&conn).await));
+    let key_cipher = Arc::new(RwLock::new(KeyCipher::new(&master_key)));
+
+    tokio::spawn(tasks::purge_trash(conn.clone(), trash_retention));
+    tokio::spawn(tasks::expire_keys(conn.clone()));
+    let refresh_report = Arc::new(Mutex::new(RefreshReport::default()));
+    tokio::spawn(tasks::refresh_game_metadata(
+        conn.clone(),
+        steam_client.clone(),
+        refresh_report.clone(),
+        metadata_refresh_interval,
+        steam_country.clone(),
+    ));
+    tokio::spawn(tasks::refresh_steam_apps(
+        conn.clone(),
+        steam_client.clone(),
+        app_cache.clone(),
+    ));
 
     let framework = poise::Framework::builder()
         .options(poise::FrameworkOptions {
-            commands: vec![version(), game(), gamekey(), statistics()],
+            commands: vec![
+                version(),
+                game(),
+                gamekey(),
+                statistics(),
+                admin(),
+                audit(),
+                trash(),
+                giveaway(),
+                settings(),
+                wishlist(),
+                pool(),
+            ],
+            event_handler: |ctx, event, framework, data| {
+                Box::pin(events::event_handler(ctx, event, framework, data))
+            },
             ..Default::default()
         })
-        .setup(|ctx, _ready, framework| {
+        .setup(move |ctx, _ready, framework| {
             Box::pin(async move {
                 poise::builtins::register_globally(ctx, &framework.options().commands).await?;
+
+                tokio::spawn(tasks::draw_giveaways(
+                    ctx.http.clone(),
+                    conn.clone(),
+                    key_cipher.clone(),
+                ));
+                tokio::spawn(tasks::release_reservations(ctx.http.clone(), conn.clone()));
+                if !expiry_reminder_days.is_empty() {
+                    tokio::spawn(tasks::remind_expiring_keys(
+                        ctx.http.clone(),
+                        conn.clone(),
+                        expiry_reminder_days,
+                    ));
+                }
+
                 Ok(Data {
                     conn,
                     game_title_cache: Mutex::new(title_cache),
-                    steam_app_cache: Mutex::new(app_cache),
+                    steam_app_cache: app_cache,
+                    steam_client,
+                    refresh_report,
+                    steam_country,
+                    price_cache: Mutex::new(PriceCache::new(Duration::seconds(3600))),
+                    key_cipher,
+                    trash_retention,
+                    claim_policy,
                 })
             })
         })
@@ -1,7 +1,7 @@
-use crate::{Data, PoiseError};
-use entity::game;
-use gemuki_service::query::{GameKeyModel, GameKeyQuery};
-use poise::serenity_prelude::{self as serenity, Color, CreateEmbed};
+use crate::{commands::find_price, store, Data, PoiseError};
+use entity::game::{self, GameMetadata};
+use gemuki_service::query::{AuditFilter, AuditQuery, GameKeyModel, GameKeyQuery};
+use poise::serenity_prelude::{self as serenity, Color, CreateEmbed, CreateEmbedFooter};
 
 type Context<'a> = poise::Context<'a, Data, PoiseError>;
 
@@ -69,10 +69,15 @@ async fn create_gamedetail_embed(
 
     let embed = CreateEmbed::new()
         .colour(Color::DARK_BLUE)
-        .title(game.title)
-        .description(game.description.unwrap_or("None".to_owned()))
+        .title(&game.title)
+        .description(game.description.as_deref().unwrap_or("None"))
         .field("Id", format!("{}", game.id), true)
         .field("Keys", key_count.to_string(), true);
+    let embed = store::add_store_fields(embed, &game);
+    let embed = match game.metadata {
+        Some(metadata) => add_metadata_fields(embed, &metadata),
+        None => embed,
+    };
     let embed = match game.image_link {
         Some(link) => embed.image(link),
         None => embed,
@@ -81,6 +86,63 @@ async fn create_gamedetail_embed(
     Ok(embed)
 }
 
+/// Number of screenshots linked in a game embed.
+const MAX_SCREENSHOT_LINKS: usize = 3;
+/// Maximum length of an embed field value allowed by discord.
+const MAX_FIELD_LENGTH: usize = 1024;
+
+/// Adds the store details of a game to its embed. Empty details are left out.
+pub fn add_metadata_fields(embed: CreateEmbed, metadata: &GameMetadata) -> CreateEmbed {
+    let release_date = metadata
+        .release_date
+        .clone()
+        .map(|x| match metadata.coming_soon {
+            true => format!("{x} (coming soon)"),
+            false => x,
+        });
+    let platforms = [
+        (metadata.windows, "Windows"),
+        (metadata.mac, "Mac"),
+        (metadata.linux, "Linux"),
+    ]
+    .into_iter()
+    .filter(|(supported, _)| *supported)
+    .map(|(_, name)| name)
+    .collect::<Vec<&str>>();
+    let screenshots = metadata
+        .screenshots
+        .iter()
+        .take(MAX_SCREENSHOT_LINKS)
+        .enumerate()
+        .map(|(i, link)| format!("[{}]({})", i + 1, link))
+        .collect::<Vec<String>>();
+
+    let fields = [
+        ("Genres", metadata.genres.join(", ")),
+        ("Developers", metadata.developers.join(", ")),
+        ("Publishers", metadata.publishers.join(", ")),
+        ("Release date", release_date.unwrap_or_default()),
+        (
+            "Metacritic",
+            metadata
+                .metacritic_score
+                .map(|x| x.to_string())
+                .unwrap_or_default(),
+        ),
+        ("Platforms", platforms.join(", ")),
+        ("Categories", metadata.categories.join(", ")),
+        ("Screenshots", screenshots.join(" ")),
+    ];
+
+    fields
+        .into_iter()
+        .filter(|(_, value)| !value.is_empty())
+        .fold(embed, |embed, (name, value)| {
+            let value = value.chars().take(MAX_FIELD_LENGTH).collect::<String>();
+            embed.field(name, value, name != "Categories")
+        })
+}
+
 pub async fn paginate_game_keys(
     ctx: Context<'_>,
     pages: &[GameKeyModel],
@@ -95,7 +157,7 @@ pub async fn paginate_game_keys(
             serenity::CreateButton::new(&next_button_id).emoji('▶'),
         ]);
 
-        let embed = create_gamekey_detail_embed(pages, 0).await?;
+        let embed = create_gamekey_detail_embed(ctx, pages, 0).await?;
 
         poise::CreateReply::default()
             .embed(embed)
@@ -121,7 +183,7 @@ pub async fn paginate_game_keys(
             continue;
         }
 
-        let embed = create_gamekey_detail_embed(pages, current_page).await?;
+        let embed = create_gamekey_detail_embed(ctx, pages, current_page).await?;
 
         press
             .create_response(
@@ -137,6 +199,7 @@ pub async fn paginate_game_keys(
 }
 
 async fn create_gamekey_detail_embed(
+    ctx: Context<'_>,
     pages: &[GameKeyModel],
     current_page: usize,
 ) -> Result<CreateEmbed, PoiseError> {
@@ -147,9 +210,14 @@ async fn create_gamekey_detail_embed(
 
     let embed = CreateEmbed::new()
         .title(format!("{} Key-Id {}", game.title, game_key.id))
-        .description(game.description.unwrap_or("None".to_owned()))
-        .field("Platform", platform.name, true)
-        .field("State", game_key.keystate, true)
+        .description(game.description.as_deref().unwrap_or("None"))
+        .field("Platform", &platform.name, true)
+        .field("State", game_key.keystate.to_string(), true);
+    let embed = match find_price(ctx, &game).await {
+        Some(price) => embed.field("Price", price, true),
+        None => embed,
+    };
+    let embed = embed
         .field(
             "Expiration date",
             game_key
@@ -173,11 +241,16 @@ async fn create_gamekey_detail_embed(
             game_key.modify_user_id.unwrap_or(0).to_string(),
             false,
         );
-
-    let embed = match game_key.page_link {
-        Some(link) => embed.url(link),
+    let embed = match game_key.recipient_user_id {
+        Some(recipient) => embed.field("Gifted to", format!("<@{recipient}>"), false),
+        None => embed,
+    };
+    let embed = match game_key.source {
+        Some(source) => embed.field("Source", source, false),
         None => embed,
     };
+    let embed = store::add_key_store_fields(embed, &game, &platform.name, game_key.page_link);
+
     let embed = match game.image_link {
         Some(link) => embed.image(link),
         None => embed,
@@ -185,3 +258,110 @@ async fn create_gamekey_detail_embed(
 
     Ok(embed)
 }
+
+const AUDIT_PAGE_SIZE: u64 = 10;
+
+pub async fn paginate_audit_events(
+    ctx: Context<'_>,
+    filter: AuditFilter,
+) -> Result<(), PoiseError> {
+    let ctx_id = ctx.id();
+    let prev_button_id = format!("{}prev", ctx_id);
+    let next_button_id = format!("{}next", ctx_id);
+
+    let db = &ctx.data().conn;
+
+    let (embed, num_pages) = create_audit_embed(db, &filter, 0).await?;
+
+    if num_pages == 0 {
+        ctx.reply("No audit events found.").await?;
+        return Ok(());
+    }
+
+    let reply = {
+        let components = serenity::CreateActionRow::Buttons(vec![
+            serenity::CreateButton::new(&prev_button_id).emoji('◀'),
+            serenity::CreateButton::new(&next_button_id).emoji('▶'),
+        ]);
+
+        poise::CreateReply::default()
+            .embed(embed)
+            .components(vec![components])
+    };
+
+    ctx.send(reply).await?;
+
+    let mut current_page = 0;
+    while let Some(press) = serenity::collector::ComponentInteractionCollector::new(ctx)
+        .filter(move |press| press.data.custom_id.starts_with(&ctx_id.to_string()))
+        .timeout(std::time::Duration::from_secs(3600 * 24))
+        .await
+    {
+        if press.data.custom_id == next_button_id {
+            current_page += 1;
+            if current_page >= num_pages {
+                current_page = 0;
+            }
+        } else if press.data.custom_id == prev_button_id {
+            current_page = current_page.checked_sub(1).unwrap_or(num_pages - 1);
+        } else {
+            continue;
+        }
+
+        let (embed, _) = create_audit_embed(db, &filter, current_page).await?;
+
+        press
+            .create_response(
+                ctx.serenity_context(),
+                serenity::CreateInteractionResponse::UpdateMessage(
+                    serenity::CreateInteractionResponseMessage::new().embed(embed),
+                ),
+            )
+            .await?;
+    }
+
+    Ok(())
+}
+
+async fn create_audit_embed(
+    db: &migration::sea_orm::DatabaseConnection,
+    filter: &AuditFilter,
+    current_page: u64,
+) -> Result<(CreateEmbed, u64), PoiseError> {
+    let (events, num_pages) =
+        AuditQuery::get_page(db, filter, current_page, AUDIT_PAGE_SIZE).await?;
+
+    let mut embed = CreateEmbed::new()
+        .colour(Color::DARK_GREY)
+        .title("Audit log")
+        .footer(CreateEmbedFooter::new(format!(
+            "Page {}/{}",
+            current_page + 1,
+            num_pages.max(1)
+        )));
+
+    for event in events {
+        let mut value = format!(
+            "<t:{}:f> by <@{}>\nGame: `{}` Key: `{}`",
+            event.create_date.timestamp(),
+            event.user_id,
+            event
+                .game_id
+                .map(|x| x.to_string())
+                .unwrap_or("None".to_owned()),
+            event
+                .game_key_id
+                .map(|x| x.to_string())
+                .unwrap_or("None".to_owned()),
+        );
+
+        if let Some(previous_state) = event.previous_state {
+            let previous_state: String = previous_state.chars().take(800).collect();
+            value.push_str(&format!("\nBefore: `{previous_state}`"));
+        }
+
+        embed = embed.field(format!("#{} {}", event.id, event.action), value, false);
+    }
+
+    Ok((embed, num_pages))
+}
@@ -0,0 +1,151 @@
+//! Refreshes the steam details of games, because descriptions and header images captured by
+//! `/game quicksetup` go stale over time. Prices are recorded on the way, so the value of keys
+//! can be estimated later on.
+
+use chrono::{DateTime, Utc};
+use entity::{game, sea_orm_active_enums::AuditAction};
+use gemuki_service::{
+    mutation::{AuditMutation, GameMutation, PriceSnapshotMutation, SYSTEM_USER_ID},
+    query::{GameQuery, PriceSnapshotQuery},
+};
+use log::info;
+use migration::sea_orm::{DbConn, DbErr};
+
+use crate::{
+    steam::{AppDetails, SteamClient},
+    PoiseError,
+};
+
+/// Pause between two requests, so steam does not rate limit the bot.
+static REQUEST_DELAY: std::time::Duration = std::time::Duration::from_millis(1500);
+
+/// Result of the last metadata refresh.
+#[derive(Clone, Debug, Default)]
+pub struct RefreshReport {
+    pub finish_date: Option<DateTime<Utc>>,
+    pub checked: usize,
+    /// Titles of the games whose details changed.
+    pub changed: Vec<String>,
+    /// Titles of the games which could not be refreshed and the reason.
+    pub failed: Vec<(String, String)>,
+}
+
+/// Re-fetches the steam details of all games with a steam app id and updates changed games.
+pub async fn refresh_games(
+    db: &DbConn,
+    client: &dyn SteamClient,
+    country_code: &str,
+) -> Result<RefreshReport, PoiseError> {
+    let mut report = RefreshReport::default();
+
+    for game in GameQuery::get_all_with_steam_appid(db).await? {
+        report.checked += 1;
+
+        match refresh_game(db, client, &game, country_code).await {
+            Ok(true) => report.changed.push(game.title.clone()),
+            Ok(false) => {}
+            Err(why) => report.failed.push((game.title.clone(), why.to_string())),
+        }
+
+        tokio::time::sleep(REQUEST_DELAY).await;
+    }
+
+    report.finish_date = Some(Utc::now());
+    info!(
+        "Refreshed {} games, {} changed and {} failed.",
+        report.checked,
+        report.changed.len(),
+        report.failed.len()
+    );
+
+    Ok(report)
+}
+
+/// Updates the description, image and metadata of a game. Returns whether the game changed.
+/// Details steam does not provide anymore are kept.
+async fn refresh_game(
+    db: &DbConn,
+    client: &dyn SteamClient,
+    game: &game::Model,
+    country_code: &str,
+) -> Result<bool, PoiseError> {
+    let appid = match game.steam_appid.and_then(|x| u32::try_from(x).ok()) {
+        Some(a) => a,
+        None => return Err("The steam app id is invalid.".into()),
+    };
+    let details = match client.get_app_details(appid, country_code).await? {
+        Some(d) => d,
+        None => return Err(format!("Steam has no details for app {appid}.").into()),
+    };
+    record_price(db, appid, &details, country_code).await?;
+    let refreshed = details.to_game(SYSTEM_USER_ID);
+
+    // The game may have been edited while steam was asked, so compare with the stored game.
+    let current = match GameQuery::get_one(db, game.id).await? {
+        Some(g) => g,
+        None => return Err("The game does not exist anymore.".into()),
+    };
+    let unchanged = refreshed
+        .description
+        .as_ref()
+        .is_none_or(|x| current.description.as_ref() == Some(x))
+        && refreshed
+            .image_link
+            .as_ref()
+            .is_none_or(|x| current.image_link.as_ref() == Some(x))
+        && refreshed.metadata == current.metadata;
+    if unchanged {
+        return Ok(false);
+    }
+
+    match GameMutation::update_details(
+        db,
+        current.id,
+        refreshed.description,
+        refreshed.image_link,
+        refreshed.metadata,
+        SYSTEM_USER_ID,
+    )
+    .await?
+    {
+        Some(updated) => {
+            AuditMutation::log_game(
+                db,
+                AuditAction::Update,
+                SYSTEM_USER_ID,
+                &updated,
+                Some(&current),
+            )
+            .await?;
+            Ok(true)
+        }
+        None => Err("The game does not exist anymore.".into()),
+    }
+}
+
+/// Stores the current price of a steam app, if it is sold at all and the price changed since
+/// the last snapshot.
+pub async fn record_price(
+    db: &DbConn,
+    appid: u32,
+    details: &AppDetails,
+    country_code: &str,
+) -> Result<(), DbErr> {
+    let snapshot = match &details.price_overview {
+        Some(price) => price.to_snapshot(appid, country_code),
+        None => return Ok(()),
+    };
+
+    let latest = PriceSnapshotQuery::get_latest(db, snapshot.steam_appid, country_code).await?;
+    let unchanged = latest.is_some_and(|x| {
+        x.currency == snapshot.currency
+            && x.initial_price == snapshot.initial_price
+            && x.final_price == snapshot.final_price
+            && x.discount_percent == snapshot.discount_percent
+    });
+    if !unchanged {
+        PriceSnapshotMutation::create(db, snapshot).await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,246 @@
+//! Reminds owners by DM about their unused keys before they expire.
+
+use std::collections::BTreeMap;
+
+use chrono::Utc;
+use entity::{game, game_key};
+use gemuki_service::{
+    mutation::{ExpiryReminderMutation, GameKeyMutation},
+    policy::ClaimOutcome,
+    pool::PoolMember,
+    query::{ExpiryReminderQuery, GameKeyQuery},
+};
+use log::{error, info};
+use migration::sea_orm::DbConn;
+use poise::serenity_prelude::{
+    self as serenity, ButtonStyle, Color, ComponentInteraction, ComponentInteractionDataKind,
+    CreateActionRow, CreateButton, CreateEmbed, CreateInteractionResponse,
+    CreateInteractionResponseMessage, CreateMessage, CreateSelectMenu, CreateSelectMenuKind,
+    UserId,
+};
+
+use crate::{gift, Data, PoiseError};
+
+pub static CLAIM_BUTTON_PREFIX: &str = "expiry-claim-";
+pub static GIVE_AWAY_BUTTON_PREFIX: &str = "expiry-give-away-";
+pub static RECIPIENT_SELECT_PREFIX: &str = "expiry-recipient-";
+
+/// Maximum number of keys listed in a reminder.
+const MAX_LISTED_KEYS: usize = 20;
+/// Maximum number of keys with buttons, one action row per key.
+const MAX_KEY_BUTTONS: usize = 5;
+
+/// Parses a comma separated list of reminder windows in days, e.g. `30,7,1`. Invalid and
+/// non-positive entries are ignored.
+pub fn parse_windows(value: &str) -> Vec<i32> {
+    let mut windows = value
+        .split(',')
+        .filter_map(|x| x.trim().parse().ok())
+        .filter(|x| *x > 0)
+        .collect::<Vec<i32>>();
+    windows.sort_unstable_by(|a, b| b.cmp(a));
+    windows.dedup();
+    windows
+}
+
+/// Sends every owner one summary of their keys which entered a reminder window. The reminders
+/// are recorded once the DM has been sent, so every key is reminded about once per window.
+pub async fn send_due_reminders(
+    http: &serenity::Http,
+    db: &DbConn,
+    windows: &[i32],
+) -> Result<(), PoiseError> {
+    let mut owners: BTreeMap<u64, Vec<(game_key::Model, game::Model, i32)>> = BTreeMap::new();
+    for pending in ExpiryReminderQuery::get_pending(db, Utc::now(), windows).await? {
+        owners
+            .entry(pending.0.create_user_id as u64)
+            .or_default()
+            .push(pending);
+    }
+
+    for (owner_id, keys) in owners {
+        match UserId::new(owner_id)
+            .direct_message(http, create_reminder_message(&keys))
+            .await
+        {
+            Ok(_) => info!(
+                "Reminded user {owner_id} about {} expiring keys.",
+                keys.len()
+            ),
+            Err(why) => {
+                error!("Could not remind user {owner_id} about expiring keys because of '{why}'.");
+                continue;
+            }
+        }
+
+        for (game_key, _, window) in &keys {
+            ExpiryReminderMutation::record(db, game_key.id, *window).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Creates the summary of expiring keys with buttons to claim or give away the first keys.
+fn create_reminder_message(keys: &[(game_key::Model, game::Model, i32)]) -> CreateMessage {
+    let mut lines = keys
+        .iter()
+        .take(MAX_LISTED_KEYS)
+        .map(|(game_key, game, _)| {
+            format!(
+                "`{}` {} expires <t:{}:R>",
+                game_key.id,
+                game.title,
+                game_key
+                    .expiration_date
+                    .map(|x| x.and_utc().timestamp())
+                    .unwrap_or(0)
+            )
+        })
+        .collect::<Vec<String>>();
+    if keys.len() > MAX_LISTED_KEYS {
+        lines.push(format!("... and {} more", keys.len() - MAX_LISTED_KEYS));
+    }
+
+    let embed = CreateEmbed::new()
+        .colour(Color::ORANGE)
+        .title("Your keys expire soon")
+        .description(lines.join("\n"));
+
+    let components = keys
+        .iter()
+        .take(MAX_KEY_BUTTONS)
+        .map(|(game_key, _, _)| {
+            CreateActionRow::Buttons(vec![
+                CreateButton::new(format!("{CLAIM_BUTTON_PREFIX}{}", game_key.id))
+                    .label(format!("Claim key {}", game_key.id))
+                    .style(ButtonStyle::Success),
+                CreateButton::new(format!("{GIVE_AWAY_BUTTON_PREFIX}{}", game_key.id))
+                    .label(format!("Give away key {}", game_key.id))
+                    .style(ButtonStyle::Primary),
+            ])
+        })
+        .collect();
+
+    CreateMessage::new().embed(embed).components(components)
+}
+
+/// Handles the claim button of a reminder. Owners claim their own key without any limits.
+pub async fn handle_claim(
+    ctx: &serenity::Context,
+    data: &Data,
+    interaction: &ComponentInteraction,
+    gamekey_id: &str,
+) -> Result<(), PoiseError> {
+    let db = &data.conn;
+    let user_id = interaction.user.id.get();
+
+    let outcome = {
+        let cipher = data.key_cipher.read().await;
+        let owned = match gamekey_id.parse() {
+            Ok(id) => GameKeyQuery::get_one(db, &cipher, id, user_id).await?,
+            Err(_) => None,
+        };
+        match owned {
+            Some(k) => {
+                let member = PoolMember::new(user_id);
+                GameKeyMutation::claim(db, &cipher, k.id, &member, &data.claim_policy).await?
+            }
+            None => ClaimOutcome::Unavailable,
+        }
+    };
+
+    let content = match outcome {
+        ClaimOutcome::Claimed(game_key) => format!("Your key: `{}`", game_key.value),
+        ClaimOutcome::Denied(_) | ClaimOutcome::Unavailable => {
+            "This key is no longer available.".to_owned()
+        }
+    };
+    respond(
+        ctx,
+        interaction,
+        CreateInteractionResponseMessage::new().content(content),
+    )
+    .await
+}
+
+/// Handles the give away button of a reminder by asking for the user to gift the key to.
+pub async fn handle_give_away(
+    ctx: &serenity::Context,
+    interaction: &ComponentInteraction,
+    gamekey_id: &str,
+) -> Result<(), PoiseError> {
+    let select = CreateSelectMenu::new(
+        format!("{RECIPIENT_SELECT_PREFIX}{gamekey_id}"),
+        CreateSelectMenuKind::User {
+            default_users: None,
+        },
+    )
+    .placeholder("User you want to gift the key to");
+
+    respond(
+        ctx,
+        interaction,
+        CreateInteractionResponseMessage::new()
+            .content(format!("Who should get the key `{gamekey_id}`?"))
+            .components(vec![CreateActionRow::SelectMenu(select)]),
+    )
+    .await
+}
+
+/// Handles the chosen recipient of a key to give away by offering it as a gift.
+pub async fn handle_recipient(
+    ctx: &serenity::Context,
+    data: &Data,
+    interaction: &ComponentInteraction,
+    gamekey_id: &str,
+) -> Result<(), PoiseError> {
+    let recipient = match &interaction.data.kind {
+        ComponentInteractionDataKind::UserSelect { values } => values.first().copied(),
+        _ => None,
+    };
+
+    let content = match (gamekey_id.parse(), recipient) {
+        (Ok(id), Some(recipient)) => {
+            let user = recipient.to_user(ctx).await?;
+            gift::offer_key(
+                &ctx.http,
+                &data.conn,
+                &data.key_cipher,
+                interaction.user.id.get(),
+                id,
+                &user,
+            )
+            .await?
+        }
+        _ => "This key is no longer available.".to_owned(),
+    };
+
+    interaction
+        .create_response(
+            ctx,
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .components(Vec::new()),
+            ),
+        )
+        .await?;
+
+    Ok(())
+}
+
+async fn respond(
+    ctx: &serenity::Context,
+    interaction: &ComponentInteraction,
+    message: CreateInteractionResponseMessage,
+) -> Result<(), PoiseError> {
+    interaction
+        .create_response(
+            ctx,
+            CreateInteractionResponse::Message(message.ephemeral(true)),
+        )
+        .await?;
+
+    Ok(())
+}
@@ -1,7 +1,19 @@
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use async_mutex::Mutex;
+use async_trait::async_trait;
+use chrono::Utc;
+use entity::{
+    game::{self, GameMetadata},
+    price_snapshot, steam_app,
+};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::{collections::HashMap, path::PathBuf};
 use thiserror::Error;
 
+use crate::cache::{PriceCache, SteamAppCache};
+
+static GET_ALL_APPS_URL: &str =
+    "https://api.steampowered.com/ISteamApps/GetAppList/v0002/?format=json";
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ApplistResponse {
     applist: Applist,
@@ -38,65 +50,661 @@ impl App {
     pub fn name(&self) -> &str {
         &self.name
     }
+
+    pub fn to_model(&self) -> steam_app::Model {
+        steam_app::Model {
+            appid: i64::from(self.appid),
+            name: self.name.clone(),
+        }
+    }
+}
+
+impl From<steam_app::Model> for App {
+    fn from(value: steam_app::Model) -> Self {
+        Self {
+            appid: value.appid as u32,
+            name: value.name,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct GameData {
     pub success: bool,
-    pub data: AppDetails,
+    /// Missing if the request has not been successful.
+    pub data: Option<AppDetails>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// Store details of an app. Steam leaves out or nulls many fields depending on the kind of app,
+/// e.g. free games have no price and unreleased games or DLCs often lack a website or description,
+/// so every field falls back to its default.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
 pub struct AppDetails {
     #[serde(rename(serialize = "type"))]
     #[serde(rename(deserialize = "type"))]
+    #[serde(deserialize_with = "null_as_default")]
     pub app_type: String,
+    #[serde(deserialize_with = "null_as_default")]
     pub name: String,
     pub steam_appid: u32,
+    #[serde(deserialize_with = "null_as_default")]
     pub is_free: bool,
+    #[serde(deserialize_with = "null_as_default")]
     pub detailed_description: String,
+    #[serde(deserialize_with = "null_as_default")]
     pub about_the_game: String,
+    #[serde(deserialize_with = "null_as_default")]
     pub short_description: String,
+    #[serde(deserialize_with = "null_as_default")]
     pub header_image: String,
-    pub website: String,
-    pub price_overview: PriceOverview,
+    pub website: Option<String>,
+    /// Missing for free and unreleased games.
+    pub price_overview: Option<PriceOverview>,
+    #[serde(deserialize_with = "null_as_default")]
+    pub genres: Vec<Description>,
+    #[serde(deserialize_with = "null_as_default")]
+    pub categories: Vec<Description>,
+    pub release_date: Option<ReleaseDate>,
+    #[serde(deserialize_with = "null_as_default")]
+    pub developers: Vec<String>,
+    #[serde(deserialize_with = "null_as_default")]
+    pub publishers: Vec<String>,
+    pub metacritic: Option<Metacritic>,
+    pub platforms: Option<Platforms>,
+    #[serde(deserialize_with = "null_as_default")]
+    pub screenshots: Vec<Screenshot>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+impl AppDetails {
+    /// Creates a new, not yet stored game from the steam details.
+    pub fn to_game(&self, user_id: u64) -> game::Model {
+        game::Model {
+            id: 0,
+            title: self.name.clone(),
+            description: Some(self.short_description.clone()).filter(|x| !x.trim().is_empty()),
+            image_link: Some(self.header_image.clone()).filter(|x| !x.trim().is_empty()),
+            create_date: Utc::now(),
+            create_user_id: user_id as i64,
+            modify_date: None,
+            modify_user_id: None,
+            deleted_date: None,
+            deleted_user_id: None,
+            metadata: Some(self.to_metadata()),
+            steam_appid: Some(i64::from(self.steam_appid)).filter(|x| *x != 0),
+            gog_id: None,
+            epic_slug: None,
+        }
+    }
+
+    /// Collects the details which are stored alongside a game.
+    pub fn to_metadata(&self) -> GameMetadata {
+        let platforms = self.platforms.clone().unwrap_or_default();
+
+        GameMetadata {
+            genres: self.genres.iter().map(|x| x.description.clone()).collect(),
+            categories: self
+                .categories
+                .iter()
+                .map(|x| x.description.clone())
+                .collect(),
+            release_date: self
+                .release_date
+                .as_ref()
+                .map(|x| x.date.clone())
+                .filter(|x| !x.trim().is_empty()),
+            coming_soon: self.release_date.as_ref().is_some_and(|x| x.coming_soon),
+            developers: self.developers.clone(),
+            publishers: self.publishers.clone(),
+            metacritic_score: self.metacritic.as_ref().map(|x| x.score),
+            windows: platforms.windows,
+            mac: platforms.mac,
+            linux: platforms.linux,
+            screenshots: self
+                .screenshots
+                .iter()
+                .map(|x| x.path_full.clone())
+                .filter(|x| !x.is_empty())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
 pub struct PriceOverview {
+    #[serde(deserialize_with = "null_as_default")]
     pub currency: String,
     pub initial: u32,
     #[serde(rename(serialize = "final"))]
     #[serde(rename(deserialize = "final"))]
     pub final_value: u32,
     pub discount_percent: u8,
+    #[serde(deserialize_with = "null_as_default")]
     pub initial_formatted: String,
+    #[serde(deserialize_with = "null_as_default")]
     pub final_formatted: String,
 }
 
+impl PriceOverview {
+    /// Creates a new, not yet stored price snapshot of an app in a store region.
+    pub fn to_snapshot(&self, steam_appid: u32, country_code: &str) -> price_snapshot::Model {
+        price_snapshot::Model {
+            id: 0,
+            steam_appid: i64::from(steam_appid),
+            country_code: country_code.to_string(),
+            currency: self.currency.clone(),
+            initial_price: i32::try_from(self.initial).unwrap_or(i32::MAX),
+            final_price: i32::try_from(self.final_value).unwrap_or(i32::MAX),
+            discount_percent: i32::from(self.discount_percent),
+            create_date: Utc::now(),
+        }
+    }
+}
+
+/// A genre or category. Its id is left out because steam sends it as number or string.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Description {
+    #[serde(deserialize_with = "null_as_default")]
+    pub description: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ReleaseDate {
+    pub coming_soon: bool,
+    /// Localized date, e.g. `18. Mai 2015` or `Coming soon`.
+    #[serde(deserialize_with = "null_as_default")]
+    pub date: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Metacritic {
+    pub score: u32,
+    pub url: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Platforms {
+    pub windows: bool,
+    pub mac: bool,
+    pub linux: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Screenshot {
+    #[serde(deserialize_with = "null_as_default")]
+    pub path_thumbnail: String,
+    #[serde(deserialize_with = "null_as_default")]
+    pub path_full: String,
+}
+
+/// Deserializes `null` as the default value of a field.
+fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// Price of an app in a store region.
+#[derive(Clone, Debug)]
+pub enum Price {
+    Free,
+    Paid(PriceOverview),
+    /// The app cannot be bought in the region, e.g. because it has not been released yet.
+    Unavailable,
+}
+
+impl Price {
+    pub fn from_details(details: Option<&AppDetails>) -> Self {
+        match details {
+            Some(d) if d.is_free => Price::Free,
+            Some(AppDetails {
+                price_overview: Some(p),
+                ..
+            }) => Price::Paid(p.clone()),
+            _ => Price::Unavailable,
+        }
+    }
+}
+
+impl std::fmt::Display for Price {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Price::Free => write!(f, "Free"),
+            Price::Paid(p) if p.discount_percent > 0 => write!(
+                f,
+                "~~{}~~ **{}** (-{}%)",
+                p.initial_formatted, p.final_formatted, p.discount_percent
+            ),
+            Price::Paid(p) => write!(f, "{}", p.final_formatted),
+            Price::Unavailable => write!(f, "Not available"),
+        }
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum SteamError {
     #[error("Failed to request steam game information: {0}")]
     Reqwest(#[from] reqwest::Error),
     #[error("Failed to to parse steam json: {0}")]
     Serde(#[from] serde_json::Error),
+    #[error("Failed to read steam fixture: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Access to the steam web api. The bot uses [`ReqwestSteamClient`], tests and offline setups use
+/// [`FixtureSteamClient`].
+#[async_trait]
+pub trait SteamClient: Send + Sync {
+    /// Gets the list of all steam apps. Apps without a name are left out.
+    async fn get_app_list(&self) -> Result<Vec<App>, SteamError>;
+
+    /// Gets the store details of an app with the prices of the store region of the given country code.
+    /// Returns `None` if steam has no details for the app.
+    async fn get_app_details(
+        &self,
+        appid: u32,
+        country_code: &str,
+    ) -> Result<Option<AppDetails>, SteamError>;
+}
+
+/// Requests the steam web api over http.
+#[derive(Default)]
+pub struct ReqwestSteamClient {
+    client: reqwest::Client,
+}
+
+impl ReqwestSteamClient {
+    async fn get(&self, url: &str) -> Result<String, SteamError> {
+        Ok(self.client.get(url).send().await?.text().await?)
+    }
+}
+
+#[async_trait]
+impl SteamClient for ReqwestSteamClient {
+    async fn get_app_list(&self) -> Result<Vec<App>, SteamError> {
+        parse_app_list(&self.get(GET_ALL_APPS_URL).await?)
+    }
+
+    async fn get_app_details(
+        &self,
+        appid: u32,
+        country_code: &str,
+    ) -> Result<Option<AppDetails>, SteamError> {
+        let body = self
+            .get(&format!(
+                "http://store.steampowered.com/api/appdetails?appids={}&cc={}",
+                appid, country_code
+            ))
+            .await?;
+
+        parse_app_details(appid, &body)
+    }
+}
+
+/// Reads recorded steam responses from a directory instead of requesting steam.
+///
+/// The app list is read from `applist.json` and the details of an app from `appdetails/<appid>.<country code>.json`,
+/// falling back to `appdetails/<appid>.json`. Apps without a file have no details.
+pub struct FixtureSteamClient {
+    dir: PathBuf,
 }
 
-pub async fn get_app_details(appid: u32) -> Result<Option<AppDetails>, SteamError> {
-    let body = reqwest::get(format!(
-        "http://store.steampowered.com/api/appdetails?appids={}&cc=de",
-        appid
-    ))
-    .await?
-    .text()
-    .await?
-    .replace("\n", "");
+impl FixtureSteamClient {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+#[async_trait]
+impl SteamClient for FixtureSteamClient {
+    async fn get_app_list(&self) -> Result<Vec<App>, SteamError> {
+        parse_app_list(&tokio::fs::read_to_string(self.dir.join("applist.json")).await?)
+    }
+
+    async fn get_app_details(
+        &self,
+        appid: u32,
+        country_code: &str,
+    ) -> Result<Option<AppDetails>, SteamError> {
+        let dir = self.dir.join("appdetails");
+
+        for path in [
+            dir.join(format!("{appid}.{country_code}.json")),
+            dir.join(format!("{appid}.json")),
+        ] {
+            match tokio::fs::read_to_string(path).await {
+                Ok(body) => return parse_app_details(appid, &body),
+                Err(why) if why.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(why) => return Err(why.into()),
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Parses a `GetAppList` response. Apps without a name are left out.
+pub fn parse_app_list(body: &str) -> Result<Vec<App>, SteamError> {
+    let response: ApplistResponse = serde_json::de::from_str(body)?;
+
+    Ok(response
+        .applist()
+        .apps()
+        .iter()
+        .filter(|x| !x.name().trim().is_empty())
+        .cloned()
+        .collect())
+}
+
+/// Parses an `appdetails` response. Returns `None` if the response has no successful entry for the app.
+pub fn parse_app_details(appid: u32, body: &str) -> Result<Option<AppDetails>, SteamError> {
+    // Steam answers with `null` for some invalid app ids.
+    let appdata: Option<HashMap<String, GameData>> = serde_json::de::from_str(body)?;
+
+    Ok(appdata
+        .unwrap_or_default()
+        .remove(&appid.to_string())
+        .filter(|x| x.success)
+        .and_then(|x| x.data))
+}
+
+/// Searches the steam app list for a title and retrieves the details of the found app.
+pub async fn find_app_details(
+    client: &dyn SteamClient,
+    cache: &Mutex<SteamAppCache>,
+    title: &str,
+    country_code: &str,
+) -> Result<Option<AppDetails>, SteamError> {
+    let appid = {
+        let cache = cache.lock().await;
+
+        match cache.find_by_name(title) {
+            Some(app) => app.appid(),
+            None => return Ok(None),
+        }
+    };
+
+    client.get_app_details(appid, country_code).await
+}
+
+/// Gets the price of an app in the store region of the given country code. Prices are cached
+/// per region, the cache is not locked while requesting steam.
+pub async fn get_price(
+    client: &dyn SteamClient,
+    cache: &Mutex<PriceCache>,
+    appid: u32,
+    country_code: &str,
+) -> Result<Price, SteamError> {
+    if let Some(price) = cache.lock().await.get(appid, country_code) {
+        return Ok(price);
+    }
+
+    let details = client.get_app_details(appid, country_code).await?;
+    let price = Price::from_details(details.as_ref());
+    cache
+        .lock()
+        .await
+        .insert(appid, country_code, price.clone());
+
+    Ok(price)
+}
+
+/// Checks that a country code consists of two letters and returns it in lowercase, as used by steam.
+pub fn parse_country_code(value: &str) -> Option<String> {
+    let value = value.trim();
+
+    match value.len() == 2 && value.chars().all(|x| x.is_ascii_alphabetic()) {
+        true => Some(value.to_ascii_lowercase()),
+        false => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::AppMatch;
+
+    fn client() -> FixtureSteamClient {
+        FixtureSteamClient::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/steam"))
+    }
+
+    async fn app_cache() -> SteamAppCache {
+        SteamAppCache::new(client().get_app_list().await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn app_list_skips_apps_without_name() {
+        let apps = client().get_app_list().await.unwrap();
+
+        assert_eq!(apps.len(), 6);
+        assert!(apps.iter().all(|x| !x.name().trim().is_empty()));
+    }
+
+    #[tokio::test]
+    async fn app_details_with_price() {
+        let details = client()
+            .get_app_details(292030, "de")
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(details.app_type, "game");
+        assert_eq!(details.name, "The Witcher 3: Wild Hunt");
+        assert!(!details.is_free);
+
+        let price = details.price_overview.unwrap();
+        assert_eq!(price.currency, "EUR");
+        assert_eq!(price.final_value, 749);
+        assert_eq!(price.discount_percent, 75);
+    }
+
+    #[tokio::test]
+    async fn app_details_of_free_game_without_price() {
+        let details = client().get_app_details(570, "de").await.unwrap().unwrap();
+
+        assert!(details.is_free);
+        assert!(details.price_overview.is_none());
+    }
+
+    #[tokio::test]
+    async fn app_details_of_dlc_without_website() {
+        let details = client()
+            .get_app_details(378648, "de")
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(details.app_type, "dlc");
+        assert!(details.website.is_none());
+    }
+
+    #[tokio::test]
+    async fn app_details_metadata() {
+        let metadata = client()
+            .get_app_details(292030, "de")
+            .await
+            .unwrap()
+            .unwrap()
+            .to_metadata();
+
+        assert_eq!(metadata.genres, vec!["RPG"]);
+        assert_eq!(metadata.categories, vec!["Single-player"]);
+        assert_eq!(metadata.developers, vec!["CD PROJEKT RED"]);
+        assert_eq!(metadata.release_date.as_deref(), Some("18. Mai 2015"));
+        assert!(!metadata.coming_soon);
+        assert_eq!(metadata.metacritic_score, Some(93));
+        assert!(metadata.windows && !metadata.mac && !metadata.linux);
+        assert!(metadata.screenshots.is_empty());
+    }
+
+    #[tokio::test]
+    async fn app_details_of_unreleased_game_with_null_fields() {
+        let details = client()
+            .get_app_details(2050650, "de")
+            .await
+            .unwrap()
+            .unwrap();
+        let game = details.to_game(42);
+        let metadata = game.metadata.unwrap();
+
+        assert!(!details.is_free);
+        assert!(details.price_overview.is_none());
+        assert!(game.description.is_none());
+        assert!(metadata.genres.is_empty());
+        assert!(metadata.developers.is_empty());
+        assert!(metadata.coming_soon);
+        assert_eq!(metadata.metacritic_score, None);
+        assert!(!metadata.windows);
+        assert_eq!(
+            metadata.screenshots,
+            vec!["https://example.com/ss_1.1920x1080.jpg"]
+        );
+    }
+
+    #[tokio::test]
+    async fn app_details_of_null_response() {
+        assert!(client()
+            .get_app_details(20900, "de")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn app_details_of_unsuccessful_request() {
+        assert!(client()
+            .get_app_details(1086940, "de")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn app_details_of_unknown_app() {
+        assert!(client().get_app_details(1, "de").await.unwrap().is_none());
+    }
+
+    #[test]
+    fn app_details_of_other_app() {
+        let body = r#"{"570": {"success": false}}"#;
+
+        assert!(parse_app_details(292030, body).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn app_details_of_store_region() {
+        let details = client()
+            .get_app_details(292030, "us")
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(details.price_overview.unwrap().currency, "USD");
+    }
+
+    #[tokio::test]
+    async fn prices_are_cached_per_region() {
+        let cache = Mutex::new(PriceCache::new(chrono::Duration::seconds(3600)));
+
+        let de = get_price(&client(), &cache, 292030, "de").await.unwrap();
+        let us = get_price(&client(), &cache, 292030, "us").await.unwrap();
+        let free = get_price(&client(), &cache, 570, "de").await.unwrap();
+
+        assert_eq!(de.to_string(), "~~29,99€~~ **7,49€** (-75%)");
+        assert_eq!(us.to_string(), "$39.99");
+        assert_eq!(free.to_string(), "Free");
+        assert!(cache.lock().await.get(292030, "us").is_some());
+        assert!(cache.lock().await.get(292030, "gb").is_none());
+    }
+
+    #[tokio::test]
+    async fn price_of_unreleased_game() {
+        let details = client().get_app_details(2050650, "de").await.unwrap();
+
+        assert!(matches!(
+            Price::from_details(details.as_ref()),
+            Price::Unavailable
+        ));
+    }
+
+    #[test]
+    fn country_codes() {
+        assert_eq!(parse_country_code(" US ").as_deref(), Some("us"));
+        assert_eq!(parse_country_code("de").as_deref(), Some("de"));
+        assert!(parse_country_code("usa").is_none());
+        assert!(parse_country_code("1a").is_none());
+    }
+
+    #[test]
+    fn app_details_malformed() {
+        assert!(parse_app_details(570, "<html>Too many requests</html>").is_err());
+    }
+
+    #[tokio::test]
+    async fn quicksetup_creates_game_from_details() {
+        let cache = app_cache().await;
+
+        let appid = match cache.match_title("the witcher 3 wild hunt", 10) {
+            AppMatch::Found(appid) => appid,
+            other => panic!("Expected a single app, got {other:?}"),
+        };
+        assert_eq!(appid, 292030);
+
+        let game = client()
+            .get_app_details(appid, "de")
+            .await
+            .unwrap()
+            .unwrap()
+            .to_game(42);
+
+        assert_eq!(game.title, "The Witcher 3: Wild Hunt");
+        assert_eq!(game.create_user_id, 42);
+        assert_eq!(game.steam_appid, Some(292030));
+        assert!(game
+            .description
+            .unwrap()
+            .starts_with("You are Geralt of Rivia"));
+        assert!(game.image_link.unwrap().ends_with("/292030/header.jpg"));
+    }
+
+    #[tokio::test]
+    async fn quicksetup_matches_typo() {
+        let cache = app_cache().await;
+
+        assert!(matches!(
+            cache.match_title("dota2", 10),
+            AppMatch::Found(570)
+        ));
+    }
+
+    #[tokio::test]
+    async fn quicksetup_offers_candidates_for_ambiguous_title() {
+        let cache = app_cache().await;
+
+        match cache.match_title("witcher", 10) {
+            AppMatch::Ambiguous(apps) => {
+                assert_eq!(apps.len(), 4);
+                assert!(apps.iter().all(|x| x.name().contains("Witcher")));
+            }
+            other => panic!("Expected several apps, got {other:?}"),
+        }
+    }
 
-    let appdata: HashMap<String, GameData> = serde_json::de::from_str(&body)?;
-    let entry = appdata.get(&appid.to_string());
+    #[tokio::test]
+    async fn quicksetup_unknown_title() {
+        let cache = app_cache().await;
 
-    match entry {
-        Some(e) => Ok(Some(e.data.clone())),
-        None => Ok(None),
+        assert!(matches!(
+            cache.match_title("Stardew Valley", 10),
+            AppMatch::NotFound
+        ));
     }
 }
@@ -0,0 +1,75 @@
+//! Links to the store pages of games, built from their external ids.
+
+use entity::game;
+use poise::serenity_prelude::CreateEmbed;
+
+/// Opens the product activation of the steam client.
+pub static STEAM_REDEEM_LINK: &str = "steam://open/activateproduct";
+
+/// Store pages of a game on all platforms it has an external id for.
+pub fn store_links(game: &game::Model) -> Vec<(&'static str, String)> {
+    let steam = game
+        .steam_appid
+        .map(|x| ("Steam", format!("https://store.steampowered.com/app/{x}/")));
+    let gog = game
+        .gog_id
+        .as_ref()
+        .map(|x| ("GOG", format!("https://www.gog.com/game/{x}")));
+    let epic = game
+        .epic_slug
+        .as_ref()
+        .map(|x| ("Epic Games", format!("https://store.epicgames.com/p/{x}")));
+
+    [steam, gog, epic].into_iter().flatten().collect()
+}
+
+/// Store page of a game on the platform with the given name.
+pub fn store_link(game: &game::Model, platform: &str) -> Option<String> {
+    store_links(game)
+        .into_iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(platform))
+        .map(|(_, link)| link)
+}
+
+/// Links the store pages of a game in its embed. The first store page becomes the embed url.
+pub fn add_store_fields(embed: CreateEmbed, game: &game::Model) -> CreateEmbed {
+    let links = store_links(game);
+
+    let embed = match links.first() {
+        Some((_, link)) => embed.url(link),
+        None => return embed,
+    };
+    let embed = embed.field(
+        "Store",
+        links
+            .iter()
+            .map(|(name, link)| format!("[{name}]({link})"))
+            .collect::<Vec<String>>()
+            .join(" · "),
+        false,
+    );
+
+    match game.steam_appid {
+        Some(appid) => embed.field("Open in Steam", format!("steam://store/{appid}"), false),
+        None => embed,
+    }
+}
+
+/// Links the store page of a key's game and, for steam keys, the product activation in the key embed.
+/// A page link stored on the key takes precedence over the store page.
+pub fn add_key_store_fields(
+    embed: CreateEmbed,
+    game: &game::Model,
+    platform: &str,
+    page_link: Option<String>,
+) -> CreateEmbed {
+    let embed = match page_link.or_else(|| store_link(game, platform)) {
+        Some(link) => embed.url(link),
+        None => embed,
+    };
+
+    match platform.eq_ignore_ascii_case("Steam") {
+        true => embed.field("Redeem", STEAM_REDEEM_LINK, false),
+        false => embed,
+    }
+}
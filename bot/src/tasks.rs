@@ -0,0 +1,207 @@
+use std::sync::Arc;
+
+use async_mutex::Mutex;
+use chrono::{Duration, Utc};
+use gemuki_service::{
+    crypto::KeyCipher,
+    mutation::{GameKeyMutation, GameMutation, SteamAppMutation},
+    query::GameQuery,
+};
+use log::{error, info};
+use migration::sea_orm::DatabaseConnection;
+use poise::serenity_prelude::{self as serenity, CreateMessage, UserId};
+use tokio::sync::RwLock;
+
+use crate::{
+    cache::SteamAppCache,
+    giveaway,
+    refresh::{self, RefreshReport},
+    reminder,
+    steam::{App, SteamClient},
+};
+
+static PURGE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3600);
+
+/// Periodically deletes games and keys which have been in the trash longer than the retention period.
+pub async fn purge_trash(db: DatabaseConnection, retention: Duration) {
+    let mut interval = tokio::time::interval(PURGE_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        match GameMutation::purge_trashed_before(&db, Utc::now() - retention).await {
+            Ok((0, 0)) => {}
+            Ok((keys, games)) => info!("Purged {keys} keys and {games} games from the trash."),
+            Err(why) => error!("Could not purge the trash because of '{why}'."),
+        }
+    }
+}
+
+static EXPIRE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3600);
+
+/// Periodically marks unused keys as expired once their expiration date has passed.
+pub async fn expire_keys(db: DatabaseConnection) {
+    let mut interval = tokio::time::interval(EXPIRE_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        match GameKeyMutation::expire_before(&db, Utc::now().naive_utc()).await {
+            Ok(result) if result.rows_affected > 0 => {
+                info!("Marked {} keys as expired.", result.rows_affected)
+            }
+            Ok(_) => {}
+            Err(why) => error!("Could not expire keys because of '{why}'."),
+        }
+    }
+}
+
+static STEAM_APP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3600);
+
+/// Periodically downloads the steam app list and stores it in the database. The cache is only
+/// locked to swap in the new snapshot, so the last good snapshot is served while downloading
+/// and whenever steam cannot be reached.
+pub async fn refresh_steam_apps(
+    db: DatabaseConnection,
+    client: Arc<dyn SteamClient>,
+    cache: Arc<Mutex<SteamAppCache>>,
+) {
+    let mut interval = tokio::time::interval(STEAM_APP_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let apps = match client.get_app_list().await {
+            Ok(a) if !a.is_empty() => a,
+            Ok(_) => {
+                error!("Steam returned an empty app list, keeping the last snapshot.");
+                continue;
+            }
+            Err(why) => {
+                error!(
+                    "Could not refresh steam apps because of '{why}', keeping the last snapshot."
+                );
+                continue;
+            }
+        };
+
+        match SteamAppMutation::sync(&db, apps.iter().map(App::to_model).collect()).await {
+            Ok(sync) => info!(
+                "Stored steam apps, {} created, {} updated and {} deleted.",
+                sync.created, sync.updated, sync.deleted
+            ),
+            Err(why) => error!("Could not store steam apps because of '{why}'."),
+        }
+
+        let refreshed = SteamAppCache::new(apps);
+        *cache.lock().await = refreshed;
+
+        info!("Steam app cache has been updated.");
+    }
+}
+
+/// Periodically re-fetches the steam details of games. The report of the last run is kept for `/admin refresh-report`.
+pub async fn refresh_game_metadata(
+    db: DatabaseConnection,
+    client: Arc<dyn SteamClient>,
+    report: Arc<Mutex<RefreshReport>>,
+    refresh_interval: Duration,
+    country_code: String,
+) {
+    let period = refresh_interval
+        .to_std()
+        .unwrap_or(std::time::Duration::from_secs(3600 * 24));
+    let mut interval = tokio::time::interval(period);
+
+    loop {
+        interval.tick().await;
+
+        match refresh::refresh_games(&db, client.as_ref(), &country_code).await {
+            Ok(r) => *report.lock().await = r,
+            Err(why) => error!("Could not refresh game metadata because of '{why}'."),
+        }
+    }
+}
+
+static GIVEAWAY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Periodically draws the winners of ended giveaways. Giveaways which ended while the bot was offline are drawn on startup.
+pub async fn draw_giveaways(
+    http: Arc<serenity::Http>,
+    db: DatabaseConnection,
+    key_cipher: Arc<RwLock<KeyCipher>>,
+) {
+    let mut interval = tokio::time::interval(GIVEAWAY_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if let Err(why) = giveaway::draw_due_giveaways(&http, &db, &key_cipher).await {
+            error!("Could not draw giveaways because of '{why}'.");
+        }
+    }
+}
+
+static RESERVATION_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Periodically releases reserved keys once their reservation ended and tells the owners about it.
+pub async fn release_reservations(http: Arc<serenity::Http>, db: DatabaseConnection) {
+    let mut interval = tokio::time::interval(RESERVATION_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let released = match GameKeyMutation::release_reservations_before(&db, Utc::now()).await {
+            Ok(r) => r,
+            Err(why) => {
+                error!("Could not release reservations because of '{why}'.");
+                continue;
+            }
+        };
+        if !released.is_empty() {
+            info!("Released {} reserved keys.", released.len());
+        }
+
+        for game_key in released {
+            let title = match GameQuery::get_one(&db, game_key.game_id).await {
+                Ok(Some(g)) => g.title,
+                _ => "Unknown game".to_owned(),
+            };
+            let holder = game_key
+                .reserved_user_id
+                .map(|x| format!(" for <@{x}>"))
+                .unwrap_or_default();
+            let content = format!(
+                "The reservation{holder} of your key `{}` for `{title}` ended, the key is unused again.",
+                game_key.id
+            );
+
+            let owner_id = game_key.create_user_id as u64;
+            if let Err(why) = UserId::new(owner_id)
+                .direct_message(&http, CreateMessage::new().content(content))
+                .await
+            {
+                error!("Could not notify key owner {owner_id} because of '{why}'.");
+            }
+        }
+    }
+}
+
+static REMINDER_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3600);
+
+/// Periodically reminds owners about their unused keys expiring within one of the windows in days.
+pub async fn remind_expiring_keys(
+    http: Arc<serenity::Http>,
+    db: DatabaseConnection,
+    windows: Vec<i32>,
+) {
+    let mut interval = tokio::time::interval(REMINDER_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if let Err(why) = reminder::send_due_reminders(&http, &db, &windows).await {
+            error!("Could not send expiry reminders because of '{why}'.");
+        }
+    }
+}
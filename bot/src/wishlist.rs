@@ -0,0 +1,118 @@
+//! Notifies users by DM when keys for a game on their wishlist are added or moved into a pool.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use entity::{game_key, key_pool, sea_orm_active_enums::KeyState};
+use gemuki_service::{
+    pool::PoolMember,
+    query::{GameQuery, KeyPoolQuery, PlatformQuery, UserSettingQuery, WishlistQuery},
+};
+use log::{error, info};
+use migration::sea_orm::DbConn;
+use poise::serenity_prelude::{self as serenity, CreateMessage, GuildId, UserId};
+
+use crate::PoiseError;
+
+/// Notifies the users wishing for the games of newly added keys. Personal keys are only
+/// announced if the owner shares them, keys of a pool only to the users who can access the pool.
+/// Every user gets at most one message per game and pool.
+pub async fn notify_wishing_users(
+    http: &serenity::Http,
+    db: &DbConn,
+    owner_id: u64,
+    game_keys: &[game_key::Model],
+) -> Result<(), PoiseError> {
+    let shares_keys = UserSettingQuery::shares_keys(db, owner_id).await?;
+    let platforms = PlatformQuery::get_all(db).await?;
+    let pools = KeyPoolQuery::get_all(db).await?;
+
+    let mut games: BTreeMap<(Option<i32>, i32), Vec<String>> = BTreeMap::new();
+    for game_key in game_keys
+        .iter()
+        .filter(|x| x.keystate == KeyState::Unused)
+        .filter(|x| x.pool_id.is_some() || shares_keys)
+    {
+        let platform = platforms
+            .iter()
+            .find(|x| x.id == game_key.platform_id)
+            .map(|x| x.name.clone())
+            .unwrap_or("Unknown platform".to_owned());
+        let names = games
+            .entry((game_key.pool_id, game_key.game_id))
+            .or_default();
+        if !names.contains(&platform) {
+            names.push(platform);
+        }
+    }
+
+    for ((pool_id, game_id), platform_names) in games {
+        let game = match GameQuery::get_one(db, game_id).await? {
+            Some(g) => g,
+            None => continue,
+        };
+        let pool = match pool_id {
+            Some(id) => match pools.iter().find(|x| x.id == id) {
+                Some(p) => Some(p),
+                None => continue,
+            },
+            None => None,
+        };
+
+        let content = match pool {
+            Some(pool) => format!(
+                "A key for `{}` on your wishlist is now available in the pool `{}` ({}). Use `/gamekey quickclaim` to get it.",
+                game.title,
+                pool.name,
+                platform_names.join(", ")
+            ),
+            None => format!(
+                "A key for `{}` on your wishlist has been added by <@{owner_id}> ({}). Ask them if you can have it.",
+                game.title,
+                platform_names.join(", ")
+            ),
+        };
+        let user_ids = WishlistQuery::get_all_of_game(db, &game)
+            .await?
+            .into_iter()
+            .map(|x| x.user_id as u64)
+            .filter(|x| *x != owner_id)
+            .collect::<BTreeSet<u64>>();
+        for user_id in user_ids {
+            if let Some(pool) = pool {
+                if !can_access(http, pool, user_id).await {
+                    continue;
+                }
+            }
+
+            match UserId::new(user_id)
+                .direct_message(http, CreateMessage::new().content(content.clone()))
+                .await
+            {
+                Ok(_) => info!("Notified user {user_id} about a key for '{}'.", game.title),
+                Err(why) => {
+                    error!("Could not notify user {user_id} about a wished key because of '{why}'.")
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks whether a user can access a pool, looking up their roles in the guild of the pool.
+async fn can_access(http: &serenity::Http, pool: &key_pool::Model, user_id: u64) -> bool {
+    let mut member = PoolMember::new(user_id);
+    if let Some(guild_id) = pool.guild_id {
+        let guild_member = match http
+            .get_member(GuildId::new(guild_id as u64), UserId::new(user_id))
+            .await
+        {
+            Ok(m) => m,
+            Err(_) => return false,
+        };
+        member.guild_id = Some(guild_id as u64);
+        member.role_ids = guild_member.roles.iter().map(|x| x.get()).collect();
+    }
+
+    member.can_access(pool)
+}
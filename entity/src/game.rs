@@ -1,6 +1,6 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.0
 
-use sea_orm::entity::prelude::*;
+use sea_orm::{entity::prelude::*, FromJsonQueryResult};
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
@@ -15,12 +15,39 @@ pub struct Model {
     pub modify_date: Option<DateTimeUtc>,
     pub modify_user_id: Option<i64>,
     pub image_link: Option<String>,
+    pub deleted_date: Option<DateTimeUtc>,
+    pub deleted_user_id: Option<i64>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub metadata: Option<GameMetadata>,
+    pub steam_appid: Option<i64>,
+    pub gog_id: Option<String>,
+    pub epic_slug: Option<String>,
+}
+
+/// Store details of a game, e.g. from steam.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, FromJsonQueryResult)]
+#[serde(default)]
+pub struct GameMetadata {
+    pub genres: Vec<String>,
+    pub categories: Vec<String>,
+    pub release_date: Option<String>,
+    pub coming_soon: bool,
+    pub developers: Vec<String>,
+    pub publishers: Vec<String>,
+    pub metacritic_score: Option<u32>,
+    pub windows: bool,
+    pub mac: bool,
+    pub linux: bool,
+    /// Links to the full size screenshots.
+    pub screenshots: Vec<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(has_many = "super::game_key::Entity")]
     GameKey,
+    #[sea_orm(has_many = "super::wishlist::Entity")]
+    Wishlist,
 }
 
 impl Related<super::game_key::Entity> for Entity {
@@ -29,4 +56,10 @@ impl Related<super::game_key::Entity> for Entity {
     }
 }
 
+impl Related<super::wishlist::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Wishlist.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
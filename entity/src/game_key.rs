@@ -1,5 +1,6 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.0
 
+use super::sea_orm_active_enums::KeyState;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -11,8 +12,7 @@ pub struct Model {
     pub game_id: i32,
     pub platform_id: i32,
     pub value: String,
-    #[sea_orm(column_type = "custom(\"enum_text\")")]
-    pub keystate: String,
+    pub keystate: KeyState,
     pub page_link: Option<String>,
     pub create_date: DateTimeUtc,
     pub create_user_id: i64,
@@ -20,10 +20,19 @@ pub struct Model {
     pub modify_user_id: Option<i64>,
     pub notes: Option<String>,
     pub expiration_date: Option<DateTime>,
+    pub deleted_date: Option<DateTimeUtc>,
+    pub deleted_user_id: Option<i64>,
+    pub recipient_user_id: Option<i64>,
+    pub source: Option<String>,
+    pub pool_id: Option<i32>,
+    pub reserved_user_id: Option<i64>,
+    pub reserved_until: Option<DateTimeUtc>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::expiry_reminder::Entity")]
+    ExpiryReminder,
     #[sea_orm(
         belongs_to = "super::game::Entity",
         from = "Column::GameId",
@@ -32,6 +41,16 @@ pub enum Relation {
         on_delete = "NoAction"
     )]
     Game,
+    #[sea_orm(has_many = "super::giveaway::Entity")]
+    Giveaway,
+    #[sea_orm(
+        belongs_to = "super::key_pool::Entity",
+        from = "Column::PoolId",
+        to = "super::key_pool::Column::Id",
+        on_update = "NoAction",
+        on_delete = "NoAction"
+    )]
+    KeyPool,
     #[sea_orm(
         belongs_to = "super::platform::Entity",
         from = "Column::PlatformId",
@@ -42,12 +61,30 @@ pub enum Relation {
     Platform,
 }
 
+impl Related<super::expiry_reminder::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ExpiryReminder.def()
+    }
+}
+
 impl Related<super::game::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Game.def()
     }
 }
 
+impl Related<super::giveaway::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Giveaway.def()
+    }
+}
+
+impl Related<super::key_pool::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::KeyPool.def()
+    }
+}
+
 impl Related<super::platform::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Platform.def()
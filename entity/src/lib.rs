@@ -2,6 +2,16 @@
 
 pub mod prelude;
 
+pub mod audit_event;
+pub mod expiry_reminder;
 pub mod game;
 pub mod game_key;
+pub mod giveaway;
+pub mod giveaway_entry;
+pub mod key_pool;
 pub mod platform;
+pub mod price_snapshot;
+pub mod sea_orm_active_enums;
+pub mod steam_app;
+pub mod user_setting;
+pub mod wishlist;
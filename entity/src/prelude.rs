@@ -1,5 +1,14 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.0
 
+pub use super::audit_event::Entity as AuditEvent;
+pub use super::expiry_reminder::Entity as ExpiryReminder;
 pub use super::game::Entity as Game;
 pub use super::game_key::Entity as GameKey;
+pub use super::giveaway::Entity as Giveaway;
+pub use super::giveaway_entry::Entity as GiveawayEntry;
+pub use super::key_pool::Entity as KeyPool;
 pub use super::platform::Entity as Platform;
+pub use super::price_snapshot::Entity as PriceSnapshot;
+pub use super::steam_app::Entity as SteamApp;
+pub use super::user_setting::Entity as UserSetting;
+pub use super::wishlist::Entity as Wishlist;
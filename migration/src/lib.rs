@@ -5,6 +5,23 @@ mod m20240722_083119_seeding_data;
 mod m20240723_102800_game_image_link;
 mod m20240725_122713_game_key_notes;
 mod m20250603_114102_key_expiry_date;
+mod m20261018_090000_encrypt_game_key_values;
+mod m20261018_100000_audit_event;
+mod m20261018_110000_soft_delete;
+mod m20261018_120000_giveaway;
+mod m20261018_130000_game_key_recipient;
+mod m20261018_140000_key_state_lifecycle;
+mod m20261018_150000_game_key_source;
+mod m20261018_160000_steam_app;
+mod m20261018_170000_game_metadata;
+mod m20261018_180000_game_external_ids;
+mod m20261018_190000_user_setting;
+mod m20261018_200000_price_snapshot;
+mod m20261018_210000_wishlist;
+mod m20261018_220000_key_pool;
+mod m20261018_230000_claim_override;
+mod m20261018_240000_key_reservation;
+mod m20261018_250000_expiry_reminder;
 
 pub struct Migrator;
 
@@ -17,6 +34,23 @@ impl MigratorTrait for Migrator {
             Box::new(m20240723_102800_game_image_link::Migration),
             Box::new(m20240725_122713_game_key_notes::Migration),
             Box::new(m20250603_114102_key_expiry_date::Migration),
+            Box::new(m20261018_090000_encrypt_game_key_values::Migration),
+            Box::new(m20261018_100000_audit_event::Migration),
+            Box::new(m20261018_110000_soft_delete::Migration),
+            Box::new(m20261018_120000_giveaway::Migration),
+            Box::new(m20261018_130000_game_key_recipient::Migration),
+            Box::new(m20261018_140000_key_state_lifecycle::Migration),
+            Box::new(m20261018_150000_game_key_source::Migration),
+            Box::new(m20261018_160000_steam_app::Migration),
+            Box::new(m20261018_170000_game_metadata::Migration),
+            Box::new(m20261018_180000_game_external_ids::Migration),
+            Box::new(m20261018_190000_user_setting::Migration),
+            Box::new(m20261018_200000_price_snapshot::Migration),
+            Box::new(m20261018_210000_wishlist::Migration),
+            Box::new(m20261018_220000_key_pool::Migration),
+            Box::new(m20261018_230000_claim_override::Migration),
+            Box::new(m20261018_240000_key_reservation::Migration),
+            Box::new(m20261018_250000_expiry_reminder::Migration),
         ]
     }
 }
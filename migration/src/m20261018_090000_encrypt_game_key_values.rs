@@ -0,0 +1,76 @@
+use gemuki_service::crypto::KeyCipher;
+use sea_orm::ConnectionTrait;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let cipher = master_cipher()?;
+
+        for (id, value) in select_values(manager).await? {
+            if KeyCipher::is_encrypted(&value) {
+                continue;
+            }
+
+            update_value(manager, id, cipher.encrypt(&value)).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let cipher = master_cipher()?;
+
+        for (id, value) in select_values(manager).await? {
+            if !KeyCipher::is_encrypted(&value) {
+                continue;
+            }
+
+            update_value(manager, id, cipher.decrypt(&value)?).await?;
+        }
+
+        Ok(())
+    }
+}
+
+fn master_cipher() -> Result<KeyCipher, DbErr> {
+    let master_key = std::env::var("GEMUKI_MASTER_KEY").map_err(|_| {
+        DbErr::Migration("GEMUKI_MASTER_KEY is required to encrypt game keys.".to_owned())
+    })?;
+
+    Ok(KeyCipher::new(&master_key))
+}
+
+async fn select_values(manager: &SchemaManager<'_>) -> Result<Vec<(i32, String)>, DbErr> {
+    let db = manager.get_connection();
+    let select = Query::select()
+        .columns([GameKey::Id, GameKey::Value])
+        .from(GameKey::Table)
+        .to_owned();
+
+    db.query_all(db.get_database_backend().build(&select))
+        .await?
+        .iter()
+        .map(|row| Ok((row.try_get("", "id")?, row.try_get("", "value")?)))
+        .collect()
+}
+
+async fn update_value(manager: &SchemaManager<'_>, id: i32, value: String) -> Result<(), DbErr> {
+    let update = Query::update()
+        .table(GameKey::Table)
+        .value(GameKey::Value, value)
+        .and_where(Expr::col(GameKey::Id).eq(id))
+        .to_owned();
+
+    manager.exec_stmt(update).await
+}
+
+#[derive(DeriveIden)]
+enum GameKey {
+    Table,
+    Id,
+    Value,
+}
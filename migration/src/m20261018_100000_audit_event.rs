@@ -0,0 +1,55 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AuditEvent::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AuditEvent::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AuditEvent::Action).string_len(20).not_null())
+                    .col(ColumnDef::new(AuditEvent::UserId).big_integer().not_null())
+                    .col(ColumnDef::new(AuditEvent::GameId).integer().null())
+                    .col(ColumnDef::new(AuditEvent::GameKeyId).integer().null())
+                    .col(ColumnDef::new(AuditEvent::PreviousState).text().null())
+                    .col(ColumnDef::new(AuditEvent::PreviousValue).text().null())
+                    .col(
+                        ColumnDef::new(AuditEvent::CreateDate)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AuditEvent::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum AuditEvent {
+    Table,
+    Id,
+    Action,
+    UserId,
+    GameId,
+    GameKeyId,
+    PreviousState,
+    PreviousValue,
+    CreateDate,
+}
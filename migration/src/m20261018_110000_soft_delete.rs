@@ -0,0 +1,94 @@
+use sea_orm_migration::{
+    prelude::*,
+    schema::{big_integer_null, timestamp_null},
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Game::Table)
+                    .add_column_if_not_exists(timestamp_null(Game::DeletedDate))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Game::Table)
+                    .add_column_if_not_exists(big_integer_null(Game::DeletedUserId))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GameKey::Table)
+                    .add_column_if_not_exists(timestamp_null(GameKey::DeletedDate))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GameKey::Table)
+                    .add_column_if_not_exists(big_integer_null(GameKey::DeletedUserId))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GameKey::Table)
+                    .drop_column(GameKey::DeletedUserId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GameKey::Table)
+                    .drop_column(GameKey::DeletedDate)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Game::Table)
+                    .drop_column(Game::DeletedUserId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Game::Table)
+                    .drop_column(Game::DeletedDate)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Game {
+    Table,
+    DeletedDate,
+    DeletedUserId,
+}
+
+#[derive(DeriveIden)]
+enum GameKey {
+    Table,
+    DeletedDate,
+    DeletedUserId,
+}
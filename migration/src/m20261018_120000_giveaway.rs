@@ -0,0 +1,129 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Giveaway::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Giveaway::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Giveaway::GameKeyId).integer().not_null())
+                    .col(ColumnDef::new(Giveaway::GuildId).big_integer().not_null())
+                    .col(ColumnDef::new(Giveaway::ChannelId).big_integer().not_null())
+                    .col(ColumnDef::new(Giveaway::MessageId).big_integer().null())
+                    .col(
+                        ColumnDef::new(Giveaway::HostUserId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Giveaway::State).string_len(20).not_null())
+                    .col(ColumnDef::new(Giveaway::EndDate).timestamp().not_null())
+                    .col(ColumnDef::new(Giveaway::WinnerUserId).big_integer().null())
+                    .col(ColumnDef::new(Giveaway::CreateDate).timestamp().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Giveaway::Table, Giveaway::GameKeyId)
+                            .to(GameKey::Table, GameKey::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(GiveawayEntry::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(GiveawayEntry::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(GiveawayEntry::GiveawayId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(GiveawayEntry::UserId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(GiveawayEntry::CreateDate)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(GiveawayEntry::Table, GiveawayEntry::GiveawayId)
+                            .to(Giveaway::Table, Giveaway::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_giveaway_entry_unique_user")
+                    .table(GiveawayEntry::Table)
+                    .col(GiveawayEntry::GiveawayId)
+                    .col(GiveawayEntry::UserId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(GiveawayEntry::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Giveaway::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Giveaway {
+    Table,
+    Id,
+    GameKeyId,
+    GuildId,
+    ChannelId,
+    MessageId,
+    HostUserId,
+    State,
+    EndDate,
+    WinnerUserId,
+    CreateDate,
+}
+
+#[derive(DeriveIden)]
+enum GiveawayEntry {
+    Table,
+    Id,
+    GiveawayId,
+    UserId,
+    CreateDate,
+}
+
+#[derive(DeriveIden)]
+enum GameKey {
+    Table,
+    Id,
+}
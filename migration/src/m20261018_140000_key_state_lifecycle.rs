@@ -0,0 +1,117 @@
+use sea_orm::{DatabaseBackend, EnumIter, Iterable};
+use sea_orm_migration::prelude::{extension::postgres::Type, *};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        match manager.get_database_backend() {
+            DatabaseBackend::Postgres => {
+                for state in [
+                    KeyState::Reserved,
+                    KeyState::Gifted,
+                    KeyState::Invalid,
+                    KeyState::Expired,
+                ] {
+                    manager
+                        .alter_type(
+                            Type::alter()
+                                .name(Alias::new("keystate"))
+                                .add_value(state)
+                                .if_not_exists()
+                                .to_owned(),
+                        )
+                        .await?;
+                }
+            }
+            DatabaseBackend::MySql => {
+                manager
+                    .alter_table(
+                        Table::alter()
+                            .table(GameKey::Table)
+                            .modify_column(
+                                ColumnDef::new(GameKey::Keystate)
+                                    .enumeration(Alias::new("keystate"), KeyState::iter())
+                                    .not_null(),
+                            )
+                            .to_owned(),
+                    )
+                    .await?;
+            }
+            // SQLite stores the state as text, so there is nothing to alter.
+            DatabaseBackend::Sqlite => {}
+        }
+
+        let expire = Query::update()
+            .table(GameKey::Table)
+            .value(GameKey::Keystate, KeyState::Expired.to_string())
+            .and_where(Expr::col(GameKey::Keystate).eq(KeyState::Unused.to_string()))
+            .and_where(Expr::col(GameKey::ExpirationDate).lt(Expr::current_timestamp()))
+            .to_owned();
+
+        manager.exec_stmt(expire).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for (from, to) in [
+            (KeyState::Reserved, KeyState::Unused),
+            (KeyState::Expired, KeyState::Unused),
+            (KeyState::Gifted, KeyState::Used),
+            (KeyState::Invalid, KeyState::Used),
+        ] {
+            let update = Query::update()
+                .table(GameKey::Table)
+                .value(GameKey::Keystate, to.to_string())
+                .and_where(Expr::col(GameKey::Keystate).eq(from.to_string()))
+                .to_owned();
+
+            manager.exec_stmt(update).await?;
+        }
+
+        // Postgres can not drop enum values, the added values stay unused.
+        if manager.get_database_backend() == DatabaseBackend::MySql {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(GameKey::Table)
+                        .modify_column(
+                            ColumnDef::new(GameKey::Keystate)
+                                .enumeration(
+                                    Alias::new("keystate"),
+                                    [KeyState::Unused, KeyState::Used],
+                                )
+                                .not_null(),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum GameKey {
+    Table,
+    Keystate,
+    ExpirationDate,
+}
+
+#[derive(Iden, EnumIter)]
+pub enum KeyState {
+    #[iden = "Unused"]
+    Unused,
+    #[iden = "Used"]
+    Used,
+    #[iden = "Reserved"]
+    Reserved,
+    #[iden = "Gifted"]
+    Gifted,
+    #[iden = "Invalid"]
+    Invalid,
+    #[iden = "Expired"]
+    Expired,
+}
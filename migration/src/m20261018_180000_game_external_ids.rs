@@ -0,0 +1,53 @@
+use sea_orm_migration::{
+    prelude::*,
+    schema::{big_integer_null, string_len_null},
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only supports one column per alter statement.
+        for column in [
+            big_integer_null(Game::SteamAppid),
+            string_len_null(Game::GogId, 100),
+            string_len_null(Game::EpicSlug, 200),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Game::Table)
+                        .add_column_if_not_exists(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [Game::SteamAppid, Game::GogId, Game::EpicSlug] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Game::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Game {
+    Table,
+    SteamAppid,
+    GogId,
+    EpicSlug,
+}
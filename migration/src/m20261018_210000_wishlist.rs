@@ -0,0 +1,106 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Wishlist::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Wishlist::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Wishlist::UserId).big_integer().not_null())
+                    .col(ColumnDef::new(Wishlist::GameId).integer().null())
+                    .col(ColumnDef::new(Wishlist::SteamAppid).big_integer().null())
+                    .col(ColumnDef::new(Wishlist::CreateDate).timestamp().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Wishlist::Table, Wishlist::GameId)
+                            .to(Game::Table, Game::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_wishlist_unique_game")
+                    .table(Wishlist::Table)
+                    .col(Wishlist::UserId)
+                    .col(Wishlist::GameId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_wishlist_unique_steam_appid")
+                    .table(Wishlist::Table)
+                    .col(Wishlist::UserId)
+                    .col(Wishlist::SteamAppid)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserSetting::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(UserSetting::ShareKeys)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserSetting::Table)
+                    .drop_column(UserSetting::ShareKeys)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Wishlist::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Wishlist {
+    Table,
+    Id,
+    UserId,
+    GameId,
+    SteamAppid,
+    CreateDate,
+}
+
+#[derive(DeriveIden)]
+enum Game {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum UserSetting {
+    Table,
+    ShareKeys,
+}
@@ -0,0 +1,81 @@
+use sea_orm_migration::{prelude::*, schema::integer_null};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(KeyPool::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(KeyPool::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(KeyPool::Name)
+                            .string_len(100)
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(KeyPool::Kind).string_len(20).not_null())
+                    .col(
+                        ColumnDef::new(KeyPool::OwnerUserId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(KeyPool::GuildId).big_integer().null())
+                    .col(ColumnDef::new(KeyPool::RoleId).big_integer().null())
+                    .col(ColumnDef::new(KeyPool::CreateDate).timestamp().not_null())
+                    .to_owned(),
+            )
+            .await?;
+        // SQLite can not add a foreign key to an existing table, the relation lives in the entity.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GameKey::Table)
+                    .add_column_if_not_exists(integer_null(GameKey::PoolId))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GameKey::Table)
+                    .drop_column(GameKey::PoolId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table(KeyPool::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum KeyPool {
+    Table,
+    Id,
+    Name,
+    Kind,
+    OwnerUserId,
+    GuildId,
+    RoleId,
+    CreateDate,
+}
+
+#[derive(DeriveIden)]
+enum GameKey {
+    Table,
+    PoolId,
+}
@@ -0,0 +1,35 @@
+use sea_orm_migration::{prelude::*, schema::timestamp_null};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserSetting::Table)
+                    .add_column_if_not_exists(timestamp_null(UserSetting::ClaimOverrideUntil))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserSetting::Table)
+                    .drop_column(UserSetting::ClaimOverrideUntil)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserSetting {
+    Table,
+    ClaimOverrideUntil,
+}
@@ -0,0 +1,57 @@
+use sea_orm_migration::{
+    prelude::*,
+    schema::{big_integer_null, timestamp_null},
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GameKey::Table)
+                    .add_column_if_not_exists(big_integer_null(GameKey::ReservedUserId))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GameKey::Table)
+                    .add_column_if_not_exists(timestamp_null(GameKey::ReservedUntil))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GameKey::Table)
+                    .drop_column(GameKey::ReservedUntil)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GameKey::Table)
+                    .drop_column(GameKey::ReservedUserId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum GameKey {
+    Table,
+    ReservedUserId,
+    ReservedUntil,
+}
@@ -0,0 +1,78 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ExpiryReminder::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ExpiryReminder::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ExpiryReminder::GameKeyId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ExpiryReminder::WindowDays)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ExpiryReminder::CreateDate)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(ExpiryReminder::Table, ExpiryReminder::GameKeyId)
+                            .to(GameKey::Table, GameKey::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_expiry_reminder_unique_window")
+                    .table(ExpiryReminder::Table)
+                    .col(ExpiryReminder::GameKeyId)
+                    .col(ExpiryReminder::WindowDays)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ExpiryReminder::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ExpiryReminder {
+    Table,
+    Id,
+    GameKeyId,
+    WindowDays,
+    CreateDate,
+}
+
+#[derive(DeriveIden)]
+enum GameKey {
+    Table,
+    Id,
+}
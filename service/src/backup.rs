@@ -0,0 +1,177 @@
+use aes_gcm::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use entity::{game, game_key, key_pool, platform};
+use sea_orm::sqlx::types::chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::crypto::{CryptoError, KeyCipher};
+
+/// Version of the backup format. Increase it when the layout of [`Backup`] changes.
+pub const BACKUP_FORMAT_VERSION: u32 = 1;
+
+/// Prefix of backups encrypted with a passphrase. The payload starts with the salt and nonce.
+static ENCRYPTED_PREFIX: &str = "gemuki-backup2:";
+/// Number of PBKDF2-HMAC-SHA256 rounds used to derive the key from a passphrase.
+const KDF_ROUNDS: u32 = 600_000;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+#[derive(Error, Debug)]
+pub enum BackupError {
+    #[error("The backup is not valid json: {0}")]
+    Serde(#[from] serde_json::Error),
+    #[error("Could not decrypt the backup: {0}")]
+    Crypto(#[from] CryptoError),
+    #[error("The backup is encrypted, a passphrase is required.")]
+    PassphraseRequired,
+    #[error("The backup format version {0} is not supported.")]
+    UnsupportedFormat(u32),
+    #[error(
+        "The backup has been created with schema `{found}`, which is newer than the database schema `{expected}`."
+    )]
+    SchemaMismatch { expected: String, found: String },
+}
+
+/// A full backup of all games, keys, platforms and pools.
+///
+/// Key values are stored decrypted, so a backup can be restored with a different master key.
+/// Use a passphrase to encrypt the whole archive. Fields added by later migrations are optional,
+/// so backups of older schemas can still be read.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Backup {
+    pub format_version: u32,
+    /// Name of the last applied migration when the backup was created.
+    pub schema_version: String,
+    pub create_date: DateTime<Utc>,
+    pub platforms: Vec<platform::Model>,
+    /// Missing in backups created before key pools existed.
+    #[serde(default)]
+    pub pools: Vec<key_pool::Model>,
+    pub games: Vec<game::Model>,
+    pub game_keys: Vec<game_key::Model>,
+}
+
+impl Backup {
+    /// Serializes the backup to json. The json gets encrypted if a passphrase is given.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the backup cannot be serialized.
+    pub fn to_bytes(&self, passphrase: Option<&str>) -> Result<Vec<u8>, BackupError> {
+        let json = serde_json::to_string_pretty(self)?;
+
+        Ok(match passphrase {
+            Some(p) => encrypt(p, &json).into_bytes(),
+            None => json.into_bytes(),
+        })
+    }
+
+    /// Reads a backup created by [`Backup::to_bytes`] and checks its versions.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the backup is malformed, encrypted with another passphrase or
+    /// was created with a different format or a newer schema version.
+    pub fn from_bytes(
+        data: &[u8],
+        passphrase: Option<&str>,
+        schema_version: &str,
+    ) -> Result<Self, BackupError> {
+        let content = String::from_utf8_lossy(data);
+        let content = content.trim();
+
+        let json = if let Some(payload) = content.strip_prefix(ENCRYPTED_PREFIX) {
+            match passphrase {
+                Some(p) => decrypt(p, payload)?,
+                None => return Err(BackupError::PassphraseRequired),
+            }
+        } else if KeyCipher::is_encrypted(content) {
+            // Backups of earlier versions were encrypted like key values.
+            match passphrase {
+                Some(p) => KeyCipher::new(p).decrypt(content)?,
+                None => return Err(BackupError::PassphraseRequired),
+            }
+        } else {
+            content.to_owned()
+        };
+
+        let backup: Backup = serde_json::from_str(&json)?;
+
+        if backup.format_version != BACKUP_FORMAT_VERSION {
+            return Err(BackupError::UnsupportedFormat(backup.format_version));
+        }
+        // Migration names start with their date, so older schemas sort first.
+        if backup.schema_version.as_str() > schema_version {
+            return Err(BackupError::SchemaMismatch {
+                expected: schema_version.to_owned(),
+                found: backup.schema_version,
+            });
+        }
+
+        Ok(backup)
+    }
+}
+
+/// Encrypts a backup with AES-256-GCM. The key is derived from the passphrase with
+/// PBKDF2 and a random salt, which is stored together with the random nonce in front of
+/// the ciphertext.
+fn encrypt(passphrase: &str, json: &str) -> String {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+    let ciphertext = derive_cipher(passphrase, &salt)
+        .encrypt(&nonce, json.as_bytes())
+        .expect("AES-GCM encryption of in memory data does not fail");
+
+    let mut payload = Vec::with_capacity(SALT_LEN + NONCE_LEN + ciphertext.len());
+    payload.extend_from_slice(&salt);
+    payload.extend_from_slice(&nonce);
+    payload.extend_from_slice(&ciphertext);
+
+    format!("{ENCRYPTED_PREFIX}{}", STANDARD.encode(payload))
+}
+
+/// Decrypts the payload of a backup encrypted by [`encrypt`].
+fn decrypt(passphrase: &str, payload: &str) -> Result<String, CryptoError> {
+    let payload = STANDARD.decode(payload)?;
+    if payload.len() < SALT_LEN + NONCE_LEN {
+        return Err(CryptoError::Truncated);
+    }
+
+    let (salt, rest) = payload.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let plaintext = derive_cipher(passphrase, salt)
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| CryptoError::Decryption)?;
+
+    Ok(String::from_utf8(plaintext)?)
+}
+
+fn derive_cipher(passphrase: &str, salt: &[u8]) -> Aes256Gcm {
+    let key =
+        pbkdf2::pbkdf2_hmac_array::<sha2::Sha256, 32>(passphrase.as_bytes(), salt, KDF_ROUNDS);
+    Aes256Gcm::new(&key.into())
+}
+
+/// Number of rows of a table which have been restored or already existed.
+#[derive(Clone, Debug, Default)]
+pub struct RestoreCount {
+    pub created: u64,
+    pub existing: u64,
+}
+
+/// Result of restoring a backup.
+#[derive(Clone, Debug, Default)]
+pub struct RestoreReport {
+    pub platforms: RestoreCount,
+    pub pools: RestoreCount,
+    pub games: RestoreCount,
+    pub game_keys: RestoreCount,
+    /// Rows which could not be restored because they conflict with existing data.
+    pub conflicts: Vec<String>,
+}
@@ -0,0 +1,183 @@
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use hmac::{Hmac, Mac};
+use sea_orm::DbErr;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// Prefix marking a value as encrypted by [`KeyCipher`].
+static ENCRYPTED_PREFIX: &str = "enc1:";
+
+const NONCE_LEN: usize = 12;
+
+#[derive(Error, Debug)]
+pub enum CryptoError {
+    #[error("Encrypted value is not valid base64: {0}")]
+    Encoding(#[from] base64::DecodeError),
+    #[error("Encrypted value is too short.")]
+    Truncated,
+    #[error("Could not decrypt value, the master key is probably wrong.")]
+    Decryption,
+    #[error("Decrypted value is not valid utf-8.")]
+    Utf8(#[from] std::string::FromUtf8Error),
+}
+
+impl From<CryptoError> for DbErr {
+    fn from(value: CryptoError) -> Self {
+        DbErr::Custom(value.to_string())
+    }
+}
+
+/// Encrypts and decrypts game key values with a master secret.
+///
+/// Values are encrypted with AES-256-GCM. The nonce is derived from the plaintext
+/// (HMAC-SHA256 with a separate sub key), so the same key value always results in the
+/// same ciphertext. This keeps the unique constraint on `game_key.value` working.
+#[derive(Clone)]
+pub struct KeyCipher {
+    cipher: Aes256Gcm,
+    nonce_key: [u8; 32],
+}
+
+impl KeyCipher {
+    /// Creates a new cipher by deriving the encryption keys from the master secret.
+    #[must_use]
+    pub fn new(master_secret: &str) -> Self {
+        let encryption_key = derive_key("gemuki-key-encryption", master_secret);
+        let nonce_key = derive_key("gemuki-key-nonce", master_secret);
+
+        Self {
+            cipher: Aes256Gcm::new(&encryption_key.into()),
+            nonce_key,
+        }
+    }
+
+    /// Checks whether a stored value has been encrypted.
+    #[must_use]
+    pub fn is_encrypted(value: &str) -> bool {
+        value.starts_with(ENCRYPTED_PREFIX)
+    }
+
+    /// Encrypts a plaintext key value.
+    #[must_use]
+    pub fn encrypt(&self, plaintext: &str) -> String {
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&self.nonce_key)
+            .expect("HMAC accepts keys of any length");
+        mac.update(plaintext.as_bytes());
+        let tag = mac.finalize().into_bytes();
+        let nonce = Nonce::from_slice(&tag[..NONCE_LEN]);
+
+        let ciphertext = self
+            .cipher
+            .encrypt(nonce, plaintext.as_bytes())
+            .expect("AES-GCM encryption of in memory data does not fail");
+
+        let mut payload = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+        payload.extend_from_slice(nonce);
+        payload.extend_from_slice(&ciphertext);
+
+        format!("{ENCRYPTED_PREFIX}{}", STANDARD.encode(payload))
+    }
+
+    /// Decrypts a stored key value. Values without the encryption prefix are treated as plaintext.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the value is malformed or was encrypted with a different master secret.
+    pub fn decrypt(&self, value: &str) -> Result<String, CryptoError> {
+        let encoded = match value.strip_prefix(ENCRYPTED_PREFIX) {
+            Some(e) => e,
+            None => return Ok(value.to_owned()),
+        };
+
+        let payload = STANDARD.decode(encoded)?;
+        if payload.len() < NONCE_LEN {
+            return Err(CryptoError::Truncated);
+        }
+
+        let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| CryptoError::Decryption)?;
+
+        Ok(String::from_utf8(plaintext)?)
+    }
+}
+
+fn derive_key(label: &str, master_secret: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(label.as_bytes());
+    hasher.update(master_secret.as_bytes());
+    hasher.finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let cipher = KeyCipher::new("secret");
+
+        let encrypted = cipher.encrypt("AAAAA-BBBBB-CCCCC");
+
+        assert!(KeyCipher::is_encrypted(&encrypted));
+        assert_ne!(encrypted, "AAAAA-BBBBB-CCCCC");
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), "AAAAA-BBBBB-CCCCC");
+    }
+
+    #[test]
+    fn encryption_is_deterministic() {
+        let cipher = KeyCipher::new("secret");
+
+        assert_eq!(cipher.encrypt("AAAAA"), cipher.encrypt("AAAAA"));
+        assert_eq!(
+            cipher.encrypt("AAAAA"),
+            KeyCipher::new("secret").encrypt("AAAAA")
+        );
+        assert_ne!(cipher.encrypt("AAAAA"), cipher.encrypt("BBBBB"));
+    }
+
+    #[test]
+    fn legacy_plaintext_passes_through() {
+        let cipher = KeyCipher::new("secret");
+
+        assert!(!KeyCipher::is_encrypted("AAAAA-BBBBB-CCCCC"));
+        assert_eq!(
+            cipher.decrypt("AAAAA-BBBBB-CCCCC").unwrap(),
+            "AAAAA-BBBBB-CCCCC"
+        );
+    }
+
+    #[test]
+    fn wrong_key_fails() {
+        let encrypted = KeyCipher::new("secret").encrypt("AAAAA-BBBBB-CCCCC");
+
+        assert!(matches!(
+            KeyCipher::new("other").decrypt(&encrypted),
+            Err(CryptoError::Decryption)
+        ));
+        assert_ne!(
+            KeyCipher::new("other").encrypt("AAAAA"),
+            KeyCipher::new("secret").encrypt("AAAAA")
+        );
+    }
+
+    #[test]
+    fn malformed_values_fail() {
+        let cipher = KeyCipher::new("secret");
+
+        assert!(matches!(
+            cipher.decrypt("enc1:not base64!"),
+            Err(CryptoError::Encoding(_))
+        ));
+        assert!(matches!(
+            cipher.decrypt("enc1:AAAA"),
+            Err(CryptoError::Truncated)
+        ));
+    }
+}
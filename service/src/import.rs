@@ -0,0 +1,299 @@
+use entity::{game, game_key};
+use sea_orm::sqlx::types::chrono::{DateTime, NaiveDate, NaiveDateTime};
+
+/// A single key which should be imported.
+#[derive(Clone, Debug)]
+pub struct KeyImport {
+    pub line: u64,
+    pub game_title: String,
+    pub platform: String,
+    pub value: String,
+    pub page_link: Option<String>,
+    pub notes: Option<String>,
+    pub expiration_date: Option<NaiveDateTime>,
+    /// Where the key comes from, e.g. the name of a bundle.
+    pub source: Option<String>,
+}
+
+/// What happened to a single imported row.
+#[derive(Clone, Debug)]
+pub enum KeyImportOutcome {
+    /// The key has been created. Contains the decrypted key.
+    Created(Box<game_key::Model>),
+    /// A key with the same value already exists, the row has been skipped.
+    Duplicate,
+    /// The row could not be imported.
+    Failed(String),
+}
+
+/// Result of a single imported row.
+#[derive(Clone, Debug)]
+pub struct KeyImportResult {
+    pub line: u64,
+    pub game_title: String,
+    pub outcome: KeyImportOutcome,
+}
+
+/// Result of an import. Results are ordered by their line in the imported file.
+#[derive(Clone, Debug, Default)]
+pub struct KeyImportReport {
+    pub results: Vec<KeyImportResult>,
+    pub created_games: Vec<game::Model>,
+}
+
+impl KeyImportReport {
+    #[must_use]
+    pub fn count_created(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|x| matches!(x.outcome, KeyImportOutcome::Created(_)))
+            .count()
+    }
+
+    #[must_use]
+    pub fn count_duplicates(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|x| matches!(x.outcome, KeyImportOutcome::Duplicate))
+            .count()
+    }
+
+    #[must_use]
+    pub fn count_failed(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|x| matches!(x.outcome, KeyImportOutcome::Failed(_)))
+            .count()
+    }
+}
+
+/// Parses a csv file with the columns `game_title, platform, key, page_link, notes, expiration_date`.
+///
+/// Both `,` and `;` are accepted as delimiter and an optional header row is skipped. Returns the
+/// valid rows and a failed result for every row which could not be parsed.
+#[must_use]
+pub fn parse_csv(data: &[u8]) -> (Vec<KeyImport>, Vec<KeyImportResult>) {
+    let first_line = data.split(|x| *x == b'\n').next().unwrap_or_default();
+    let delimiter = if first_line.contains(&b';') && !first_line.contains(&b',') {
+        b';'
+    } else {
+        b','
+    };
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(data);
+
+    let mut rows = Vec::new();
+    let mut failed = Vec::new();
+
+    for (index, record) in reader.records().enumerate() {
+        let record = match record {
+            Ok(r) => r,
+            Err(why) => {
+                failed.push(KeyImportResult {
+                    line: why
+                        .position()
+                        .map(|x| record_line(data, x))
+                        .unwrap_or_default(),
+                    game_title: String::new(),
+                    outcome: KeyImportOutcome::Failed(why.to_string()),
+                });
+                continue;
+            }
+        };
+        let line = record
+            .position()
+            .map(|x| record_line(data, x))
+            .unwrap_or(index as u64 + 1);
+
+        if index == 0 && is_header(&record) {
+            continue;
+        }
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+
+        match parse_record(line, &record) {
+            Ok(row) => rows.push(row),
+            Err(why) => failed.push(KeyImportResult {
+                line,
+                game_title: record.get(0).unwrap_or_default().to_owned(),
+                outcome: KeyImportOutcome::Failed(why),
+            }),
+        }
+    }
+
+    (rows, failed)
+}
+
+/// Gets the line a record starts in. The position of a record points to the blank lines the
+/// reader skipped before it, so these are counted as well.
+fn record_line(data: &[u8], position: &csv::Position) -> u64 {
+    let skipped = data
+        .get(position.byte() as usize..)
+        .unwrap_or_default()
+        .iter()
+        .take_while(|x| matches!(x, b'\n' | b'\r'))
+        .filter(|x| **x == b'\n')
+        .count();
+
+    position.line() + skipped as u64
+}
+
+fn is_header(record: &csv::StringRecord) -> bool {
+    record
+        .get(0)
+        .is_some_and(|x| ["game_title", "game", "title"].contains(&x.to_lowercase().as_str()))
+}
+
+fn parse_record(line: u64, record: &csv::StringRecord) -> Result<KeyImport, String> {
+    let field = |index: usize| {
+        record
+            .get(index)
+            .filter(|x| !x.is_empty())
+            .map(str::to_owned)
+    };
+
+    let game_title = field(0).ok_or("The game title is missing.")?;
+    let platform = field(1).ok_or("The platform is missing.")?;
+    let value = field(2).ok_or("The key is missing.")?;
+    let expiration_date = match field(5) {
+        Some(date) => {
+            Some(parse_date(&date).ok_or(format!("The expiration date `{date}` is not valid."))?)
+        }
+        None => None,
+    };
+
+    Ok(KeyImport {
+        line,
+        game_title,
+        platform,
+        value,
+        page_link: field(3),
+        notes: field(4),
+        expiration_date,
+        source: None,
+    })
+}
+
+/// Parses a date in RFC 3339, `YYYY-MM-DD HH:MM:SS` or `YYYY-MM-DD` format.
+fn parse_date(value: &str) -> Option<NaiveDateTime> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date.naive_utc());
+    }
+    if let Ok(date) = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S") {
+        return Some(date);
+    }
+
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|x| x.and_hms_opt(0, 0, 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn parse_comma_separated() {
+        let (rows, failed) = parse_csv(
+            b"Portal,Steam,AAAAA-BBBBB,https://example.com,From a bundle,2026-12-31\nPortal 2,GOG,CCCCC\n",
+        );
+
+        assert!(failed.is_empty());
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].line, 1);
+        assert_eq!(rows[0].game_title, "Portal");
+        assert_eq!(rows[0].platform, "Steam");
+        assert_eq!(rows[0].value, "AAAAA-BBBBB");
+        assert_eq!(rows[0].page_link.as_deref(), Some("https://example.com"));
+        assert_eq!(rows[0].notes.as_deref(), Some("From a bundle"));
+        assert_eq!(rows[0].expiration_date, Some(date("2026-12-31 00:00:00")));
+        assert_eq!(rows[1].line, 2);
+        assert_eq!(rows[1].page_link, None);
+        assert_eq!(rows[1].expiration_date, None);
+    }
+
+    #[test]
+    fn detect_semicolon_delimiter() {
+        let (rows, failed) = parse_csv(b"Portal; Steam; AAAAA-BBBBB\nPortal, 2; GOG; CCCCC\n");
+
+        assert!(failed.is_empty());
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].platform, "Steam");
+        assert_eq!(rows[1].game_title, "Portal, 2");
+    }
+
+    #[test]
+    fn skip_header_and_blank_rows() {
+        let (rows, failed) = parse_csv(
+            b"Game_Title,platform,key\n\nPortal,Steam,AAAAA\n,,,,,\r\n\r\n\r\nPortal 2,GOG,BBBBB\n",
+        );
+
+        assert!(failed.is_empty());
+        assert_eq!(
+            rows.iter().map(|x| x.line).collect::<Vec<u64>>(),
+            vec![3, 7]
+        );
+    }
+
+    #[test]
+    fn header_is_only_skipped_in_the_first_row() {
+        let (rows, _) = parse_csv(b"Portal,Steam,AAAAA\ngame,Steam,BBBBB\n");
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1].game_title, "game");
+    }
+
+    #[test]
+    fn report_line_of_failed_rows() {
+        let (rows, failed) = parse_csv(
+            b"game_title,platform,key\nPortal,Steam\nPortal 2,GOG,AAAAA\n,Steam,BBBBB\nHalf-Life,Steam,CCCCC,,,tomorrow\n",
+        );
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].line, 3);
+        let failed = failed
+            .iter()
+            .map(|x| match &x.outcome {
+                KeyImportOutcome::Failed(why) => (x.line, x.game_title.as_str(), why.as_str()),
+                other => panic!("Expected a failed row, got {other:?}"),
+            })
+            .collect::<Vec<(u64, &str, &str)>>();
+        assert_eq!(
+            failed,
+            vec![
+                (2, "Portal", "The key is missing."),
+                (4, "", "The game title is missing."),
+                (
+                    5,
+                    "Half-Life",
+                    "The expiration date `tomorrow` is not valid."
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_date_formats() {
+        assert_eq!(
+            parse_date("2026-12-31T12:30:00+02:00"),
+            Some(date("2026-12-31 10:30:00"))
+        );
+        assert_eq!(
+            parse_date("2026-12-31 12:30:00"),
+            Some(date("2026-12-31 12:30:00"))
+        );
+        assert_eq!(parse_date("2026-12-31"), Some(date("2026-12-31 00:00:00")));
+        assert_eq!(parse_date("31.12.2026"), None);
+        assert_eq!(parse_date("2026-02-30"), None);
+    }
+}
@@ -1,8 +1,17 @@
 use entity::{game_key, prelude::GameKey};
 use sea_orm::{DbConn, DbErr, EntityTrait, PaginatorTrait, QuerySelect};
 
+pub mod backup;
+pub mod crypto;
+pub mod import;
 pub mod mutation;
+pub mod policy;
+pub mod pool;
 pub mod query;
+pub mod value;
+
+#[cfg(test)]
+mod testing;
 
 pub async fn count_users(db: &DbConn) -> Result<u64, DbErr> {
     GameKey::find()
@@ -1,12 +1,42 @@
+use std::collections::{HashMap, HashSet};
+
 use ::entity::{
-    game::{self, Entity as Game},
+    audit_event::{self, Entity as AuditEvent},
+    expiry_reminder,
+    game::{self, Entity as Game, GameMetadata},
     game_key::{self, Entity as GameKey},
+    giveaway::{self, Entity as Giveaway},
+    giveaway_entry,
+    key_pool::{self, Entity as KeyPool},
+    platform::{self, Entity as Platform},
+    price_snapshot,
+    sea_orm_active_enums::{AuditAction, GiveawayState, KeyState},
+    steam_app::{self, Entity as SteamApp},
+    user_setting::{self, Entity as UserSetting},
+    wishlist::{self, Entity as Wishlist},
 };
 
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DbConn, DbErr, DeleteResult, EntityTrait, QueryFilter, Set,
+    sea_query::Expr,
+    sqlx::types::chrono::{DateTime, NaiveDateTime, Utc},
+    ActiveModelTrait,
+    ActiveValue::NotSet,
+    ColumnTrait, Condition, ConnectionTrait, DatabaseTransaction, DbConn, DbErr, DeleteResult,
+    EntityTrait, QueryFilter, QuerySelect, QueryTrait, Set, TransactionTrait, UpdateResult,
+};
+
+use crate::{
+    backup::{Backup, RestoreReport},
+    crypto::KeyCipher,
+    import::{KeyImport, KeyImportOutcome, KeyImportReport, KeyImportResult},
+    policy::{ClaimOutcome, ClaimPolicy},
+    pool::PoolMember,
+    query::{GameKeyQuery, GameQuery, KeyPoolQuery, PlatformQuery},
 };
 
+/// User id recorded for changes made by the bot itself, e.g. by scheduled jobs.
+pub const SYSTEM_USER_ID: u64 = 0;
+
 pub struct GameMutation;
 
 impl GameMutation {
@@ -15,13 +45,20 @@ impl GameMutation {
     /// # Errors
     ///
     /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
-    pub async fn create(db: &DbConn, game: game::Model) -> Result<game::Model, DbErr> {
+    pub async fn create<C: ConnectionTrait>(
+        db: &C,
+        game: game::Model,
+    ) -> Result<game::Model, DbErr> {
         game::ActiveModel {
             title: Set(game.title),
             description: Set(game.description),
             image_link: Set(game.image_link),
-            create_date: Set(game.create_date.clone()),
+            create_date: Set(game.create_date),
             create_user_id: Set(game.create_user_id),
+            metadata: Set(game.metadata),
+            steam_appid: Set(game.steam_appid),
+            gog_id: Set(game.gog_id),
+            epic_slug: Set(game.epic_slug),
             ..Default::default()
         }
         .insert(db)
@@ -51,6 +88,12 @@ impl GameMutation {
             create_user_id: game.create_user_id,
             modify_date: Set(update_game.modify_date),
             modify_user_id: Set(update_game.modify_user_id),
+            deleted_date: game.deleted_date,
+            deleted_user_id: game.deleted_user_id,
+            metadata: Set(update_game.metadata),
+            steam_appid: Set(update_game.steam_appid),
+            gog_id: Set(update_game.gog_id),
+            epic_slug: Set(update_game.epic_slug),
         }
         .update(db)
         .await?;
@@ -58,6 +101,40 @@ impl GameMutation {
         Ok(Some(updated))
     }
 
+    /// Updates only the store details of a game, so concurrent edits of other fields are kept.
+    /// A missing description or image keeps the stored one. Returns `None` if the game does not
+    /// exist.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn update_details(
+        db: &DbConn,
+        id: i32,
+        description: Option<String>,
+        image_link: Option<String>,
+        metadata: Option<GameMetadata>,
+        user_id: u64,
+    ) -> Result<Option<game::Model>, DbErr> {
+        let mut update = Game::update_many()
+            .col_expr(game::Column::Metadata, Expr::value(metadata))
+            .col_expr(game::Column::ModifyDate, Expr::value(Utc::now()))
+            .col_expr(game::Column::ModifyUserId, Expr::value(user_id as i64));
+        if let Some(description) = description {
+            update = update.col_expr(game::Column::Description, Expr::value(description));
+        }
+        if let Some(image_link) = image_link {
+            update = update.col_expr(game::Column::ImageLink, Expr::value(image_link));
+        }
+
+        let result = update.filter(game::Column::Id.eq(id)).exec(db).await?;
+        if result.rows_affected == 0 {
+            return Ok(None);
+        }
+
+        Game::find_by_id(id).one(db).await
+    }
+
     /// Deletes a gamekey by its id.
     ///
     /// # Errors
@@ -66,39 +143,344 @@ impl GameMutation {
     pub async fn delete(db: &DbConn, id: i32) -> Result<DeleteResult, DbErr> {
         Game::delete_by_id(id).exec(db).await
     }
+
+    /// Moves a game and all of its gamekeys into the trash.
+    /// Returns the number of trashed keys and games.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn trash(db: &DbConn, id: i32, user_id: u64) -> Result<(u64, u64), DbErr> {
+        let txn = db.begin().await?;
+        let now = Utc::now();
+
+        let trashed_keys = GameKey::update_many()
+            .col_expr(game_key::Column::DeletedDate, Expr::value(now))
+            .col_expr(game_key::Column::DeletedUserId, Expr::value(user_id as i64))
+            .filter(game_key::Column::GameId.eq(id))
+            .filter(game_key::Column::DeletedDate.is_null())
+            .exec(&txn)
+            .await?;
+        let trashed_games = Game::update_many()
+            .col_expr(game::Column::DeletedDate, Expr::value(now))
+            .col_expr(game::Column::DeletedUserId, Expr::value(user_id as i64))
+            .filter(game::Column::Id.eq(id))
+            .filter(game::Column::DeletedDate.is_null())
+            .exec(&txn)
+            .await?;
+
+        txn.commit().await?;
+
+        Ok((trashed_keys.rows_affected, trashed_games.rows_affected))
+    }
+
+    /// Restores a game from the trash together with the gamekeys which were trashed with it.
+    /// Returns the number of restored keys or `None` if the game is not in the trash.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn restore(db: &DbConn, id: i32) -> Result<Option<u64>, DbErr> {
+        let txn = db.begin().await?;
+
+        let game = match Game::find_by_id(id)
+            .filter(game::Column::DeletedDate.is_not_null())
+            .one(&txn)
+            .await?
+        {
+            Some(g) => g,
+            None => return Ok(None),
+        };
+
+        let restored_keys = GameKey::update_many()
+            .col_expr(
+                game_key::Column::DeletedDate,
+                Expr::value(None::<DateTime<Utc>>),
+            )
+            .col_expr(game_key::Column::DeletedUserId, Expr::value(None::<i64>))
+            .filter(game_key::Column::GameId.eq(id))
+            .filter(game_key::Column::DeletedDate.eq(game.deleted_date))
+            .exec(&txn)
+            .await?;
+        Game::update_many()
+            .col_expr(
+                game::Column::DeletedDate,
+                Expr::value(None::<DateTime<Utc>>),
+            )
+            .col_expr(game::Column::DeletedUserId, Expr::value(None::<i64>))
+            .filter(game::Column::Id.eq(id))
+            .exec(&txn)
+            .await?;
+
+        txn.commit().await?;
+
+        Ok(Some(restored_keys.rows_affected))
+    }
+
+    /// Permanently deletes a trashed game and all of its gamekeys.
+    /// Returns the number of deleted keys and games.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn purge(db: &DbConn, id: i32) -> Result<(u64, u64), DbErr> {
+        Self::purge_many(db, vec![id]).await
+    }
+
+    /// Permanently deletes all games and gamekeys which have been trashed before the given date.
+    /// Returns the number of deleted keys and games.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn purge_trashed_before(
+        db: &DbConn,
+        before: DateTime<Utc>,
+    ) -> Result<(u64, u64), DbErr> {
+        let game_ids = Game::find()
+            .filter(game::Column::DeletedDate.lt(before))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|x| x.id)
+            .collect();
+
+        let (purged_game_keys, purged_games) = Self::purge_many(db, game_ids).await?;
+
+        let txn = db.begin().await?;
+        let purged_keys = GameKeyMutation::delete_with_giveaways(
+            &txn,
+            Condition::all().add(game_key::Column::DeletedDate.lt(before)),
+        )
+        .await?;
+        txn.commit().await?;
+
+        Ok((purged_game_keys + purged_keys.rows_affected, purged_games))
+    }
+
+    async fn purge_many(db: &DbConn, ids: Vec<i32>) -> Result<(u64, u64), DbErr> {
+        let txn = db.begin().await?;
+
+        let trashed_ids: Vec<i32> = Game::find()
+            .filter(game::Column::Id.is_in(ids))
+            .filter(game::Column::DeletedDate.is_not_null())
+            .all(&txn)
+            .await?
+            .into_iter()
+            .map(|x| x.id)
+            .collect();
+
+        let purged_keys = GameKeyMutation::delete_with_giveaways(
+            &txn,
+            Condition::all().add(game_key::Column::GameId.is_in(trashed_ids.clone())),
+        )
+        .await?;
+        let purged_games = Game::delete_many()
+            .filter(game::Column::Id.is_in(trashed_ids))
+            .exec(&txn)
+            .await?;
+
+        txn.commit().await?;
+
+        Ok((purged_keys.rows_affected, purged_games.rows_affected))
+    }
 }
 
 pub struct GameKeyMutation;
 
 impl GameKeyMutation {
-    /// Creates a new gamekey.
+    /// Creates a new gamekey. The key value gets encrypted before it is stored.
     ///
     /// # Errors
     ///
     /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
-    pub async fn create(db: &DbConn, gamekey: game_key::Model) -> Result<game_key::Model, DbErr> {
-        game_key::ActiveModel {
+    pub async fn create(
+        db: &DbConn,
+        cipher: &KeyCipher,
+        gamekey: game_key::Model,
+    ) -> Result<game_key::Model, DbErr> {
+        let mut created = game_key::ActiveModel {
             game_id: Set(gamekey.game_id),
             platform_id: Set(gamekey.platform_id),
-            value: Set(gamekey.value),
+            value: Set(cipher.encrypt(&gamekey.value)),
             keystate: Set(gamekey.keystate),
             page_link: Set(gamekey.page_link),
+            notes: Set(gamekey.notes),
+            source: Set(gamekey.source),
             create_date: Set(gamekey.create_date),
             create_user_id: Set(gamekey.create_user_id),
             expiration_date: Set(gamekey.expiration_date),
             ..Default::default()
         }
         .insert(db)
-        .await
+        .await?;
+
+        created.value = cipher.decrypt(&created.value)?;
+
+        Ok(created)
+    }
+
+    /// Imports many gamekeys of a user at once.
+    ///
+    /// Every row is validated against the existing games and platforms first. Missing games
+    /// are created from `new_games` if it contains them, otherwise with their title only if
+    /// `create_missing` is set. Keys whose value already exists are skipped. All games and keys
+    /// are inserted inside a single transaction, games only together with their first key.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn import(
+        db: &DbConn,
+        cipher: &KeyCipher,
+        user_id: u64,
+        rows: Vec<KeyImport>,
+        create_missing: bool,
+        new_games: Vec<game::Model>,
+    ) -> Result<KeyImportReport, DbErr> {
+        let mut report = KeyImportReport::default();
+        let mut new_games = new_games
+            .into_iter()
+            .map(|x| (x.title.clone(), x))
+            .collect::<HashMap<String, game::Model>>();
+        let mut games: HashMap<String, Option<game::Model>> = HashMap::new();
+        let mut platforms: HashMap<String, Option<platform::Model>> = HashMap::new();
+        let mut valid = Vec::new();
+
+        for row in rows {
+            if !platforms.contains_key(&row.platform) {
+                let platform = PlatformQuery::get_by_name(db, &row.platform).await?;
+                platforms.insert(row.platform.clone(), platform);
+            }
+            let platform = match &platforms[&row.platform] {
+                Some(p) => p.id,
+                None => {
+                    report.results.push(KeyImportResult {
+                        line: row.line,
+                        outcome: KeyImportOutcome::Failed(format!(
+                            "The platform `{}` does not exist.",
+                            row.platform
+                        )),
+                        game_title: row.game_title,
+                    });
+                    continue;
+                }
+            };
+
+            if !games.contains_key(&row.game_title) {
+                let game = GameQuery::get_by_title(db, &row.game_title).await?;
+                games.insert(row.game_title.clone(), game);
+            }
+            if games[&row.game_title].is_none() {
+                let reason = if GameQuery::get_trashed_by_title(db, &row.game_title)
+                    .await?
+                    .is_some()
+                {
+                    Some("The game is in the trash.")
+                } else if !create_missing && !new_games.contains_key(&row.game_title) {
+                    Some("The game does not exist.")
+                } else {
+                    None
+                };
+
+                if let Some(reason) = reason {
+                    report.results.push(KeyImportResult {
+                        line: row.line,
+                        game_title: row.game_title,
+                        outcome: KeyImportOutcome::Failed(reason.to_owned()),
+                    });
+                    continue;
+                }
+            }
+
+            valid.push((row, platform));
+        }
+
+        let now = Utc::now();
+        let txn = db.begin().await?;
+        let mut values = HashSet::new();
+
+        for (row, platform_id) in valid {
+            let value = cipher.encrypt(&row.value);
+            let exists = GameKey::find()
+                .filter(game_key::Column::Value.eq(&value))
+                .one(&txn)
+                .await?
+                .is_some();
+
+            if exists || !values.insert(value.clone()) {
+                report.results.push(KeyImportResult {
+                    line: row.line,
+                    game_title: row.game_title,
+                    outcome: KeyImportOutcome::Duplicate,
+                });
+                continue;
+            }
+
+            let game_id = match &games[&row.game_title] {
+                Some(g) => g.id,
+                None => {
+                    let game = match new_games.remove(&row.game_title) {
+                        Some(model) => GameMutation::create(&txn, model).await?,
+                        None => {
+                            game::ActiveModel {
+                                title: Set(row.game_title.clone()),
+                                create_date: Set(now),
+                                create_user_id: Set(user_id as i64),
+                                ..Default::default()
+                            }
+                            .insert(&txn)
+                            .await?
+                        }
+                    };
+
+                    let game_id = game.id;
+                    games.insert(row.game_title.clone(), Some(game.clone()));
+                    report.created_games.push(game);
+
+                    game_id
+                }
+            };
+
+            let mut created = game_key::ActiveModel {
+                game_id: Set(game_id),
+                platform_id: Set(platform_id),
+                value: Set(value),
+                keystate: Set(KeyState::Unused),
+                page_link: Set(row.page_link),
+                notes: Set(row.notes),
+                source: Set(row.source),
+                create_date: Set(now),
+                create_user_id: Set(user_id as i64),
+                expiration_date: Set(row.expiration_date),
+                ..Default::default()
+            }
+            .insert(&txn)
+            .await?;
+            created.value = row.value;
+
+            report.results.push(KeyImportResult {
+                line: row.line,
+                game_title: row.game_title,
+                outcome: KeyImportOutcome::Created(Box::new(created)),
+            });
+        }
+
+        txn.commit().await?;
+
+        report.results.sort_by_key(|x| x.line);
+
+        Ok(report)
     }
 
-    /// Updates the details of a gamekey.
+    /// Updates the details of a gamekey. The key value gets encrypted before it is stored.
     ///
     /// # Errors
     ///
     /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
     pub async fn update(
         db: &DbConn,
+        cipher: &KeyCipher,
         update_gamekey: game_key::Model,
     ) -> Result<Option<game_key::Model>, DbErr> {
         let gamekey: game_key::ActiveModel =
@@ -107,11 +489,11 @@ impl GameKeyMutation {
                 None => return Ok(None),
             };
 
-        let updated = game_key::ActiveModel {
+        let mut updated = game_key::ActiveModel {
             id: gamekey.id,
             game_id: Set(update_gamekey.game_id),
             platform_id: Set(update_gamekey.platform_id),
-            value: Set(update_gamekey.value),
+            value: Set(cipher.encrypt(&update_gamekey.value)),
             keystate: Set(update_gamekey.keystate),
             page_link: Set(update_gamekey.page_link),
             notes: Set(update_gamekey.notes),
@@ -120,13 +502,482 @@ impl GameKeyMutation {
             modify_date: Set(update_gamekey.modify_date),
             modify_user_id: Set(update_gamekey.modify_user_id),
             expiration_date: Set(update_gamekey.expiration_date),
+            deleted_date: gamekey.deleted_date,
+            deleted_user_id: gamekey.deleted_user_id,
+            recipient_user_id: Set(update_gamekey.recipient_user_id),
+            source: Set(update_gamekey.source),
+            pool_id: gamekey.pool_id,
+            // A reservation ends as soon as the key leaves the reserved state.
+            reserved_user_id: match update_gamekey.keystate {
+                KeyState::Reserved => gamekey.reserved_user_id,
+                _ => Set(None),
+            },
+            reserved_until: match update_gamekey.keystate {
+                KeyState::Reserved => gamekey.reserved_until,
+                _ => Set(None),
+            },
         }
         .update(db)
         .await?;
 
+        updated.value = cipher.decrypt(&updated.value)?;
+
         Ok(Some(updated))
     }
 
+    /// Claims an unused, unexpired gamekey the member may access by marking it as used. Keys
+    /// reserved for the member can be claimed until the reservation ends.
+    ///
+    /// The state is changed with a conditional update inside a transaction, so only one of
+    /// several concurrent claims of the same key wins. The claim policy is checked and the
+    /// reveal is logged inside the same transaction, see [`Self::finish_claim`].
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn claim(
+        db: &DbConn,
+        cipher: &KeyCipher,
+        id: i32,
+        member: &PoolMember,
+        policy: &ClaimPolicy,
+    ) -> Result<ClaimOutcome, DbErr> {
+        let access = member.key_condition(&KeyPoolQuery::get_all(db).await?);
+        let now = Utc::now();
+        let txn = db.begin().await?;
+
+        let result = GameKey::update_many()
+            .col_expr(game_key::Column::Keystate, Expr::value(KeyState::Used))
+            .col_expr(game_key::Column::ModifyDate, Expr::value(now))
+            .col_expr(
+                game_key::Column::ModifyUserId,
+                Expr::value(member.user_id as i64),
+            )
+            .filter(game_key::Column::Id.eq(id))
+            .filter(
+                Condition::any()
+                    .add(
+                        Condition::all()
+                            .add(access)
+                            .add(game_key::Column::Keystate.eq(KeyState::Unused)),
+                    )
+                    .add(Self::reserved_for(member.user_id, now)),
+            )
+            .filter(game_key::Column::DeletedDate.is_null())
+            .filter(
+                Condition::any()
+                    .add(game_key::Column::ExpirationDate.is_null())
+                    .add(game_key::Column::ExpirationDate.gt(now.naive_utc())),
+            )
+            .exec(&txn)
+            .await?;
+
+        if result.rows_affected == 0 {
+            txn.rollback().await?;
+            return Ok(ClaimOutcome::Unavailable);
+        }
+
+        Self::finish_claim(txn, cipher, id, member, policy).await
+    }
+
+    /// Accepts a gift offered to the member by marking the key as gifted. Works like
+    /// [`Self::claim`], so the claim policy applies to gifts as well.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn accept_gift(
+        db: &DbConn,
+        cipher: &KeyCipher,
+        id: i32,
+        member: &PoolMember,
+        policy: &ClaimPolicy,
+    ) -> Result<ClaimOutcome, DbErr> {
+        let txn = db.begin().await?;
+
+        let result = GameKey::update_many()
+            .col_expr(game_key::Column::Keystate, Expr::value(KeyState::Gifted))
+            .col_expr(game_key::Column::ModifyDate, Expr::value(Utc::now()))
+            .col_expr(
+                game_key::Column::ModifyUserId,
+                Expr::value(member.user_id as i64),
+            )
+            .filter(game_key::Column::Id.eq(id))
+            .filter(game_key::Column::Keystate.eq(KeyState::Reserved))
+            .filter(game_key::Column::RecipientUserId.eq(member.user_id))
+            .filter(game_key::Column::DeletedDate.is_null())
+            .exec(&txn)
+            .await?;
+
+        if result.rows_affected == 0 {
+            txn.rollback().await?;
+            return Ok(ClaimOutcome::Unavailable);
+        }
+
+        Self::finish_claim(txn, cipher, id, member, policy).await
+    }
+
+    /// Declines a gift offered to the recipient, so the key is unused again. Returns `false` if
+    /// the key is no longer offered to the recipient.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn decline_gift(db: &DbConn, id: i32, recipient_user_id: u64) -> Result<bool, DbErr> {
+        let result = GameKey::update_many()
+            .col_expr(game_key::Column::Keystate, Expr::value(KeyState::Unused))
+            .col_expr(
+                game_key::Column::RecipientUserId,
+                Expr::value(Option::<i64>::None),
+            )
+            .col_expr(game_key::Column::ModifyDate, Expr::value(Utc::now()))
+            .col_expr(
+                game_key::Column::ModifyUserId,
+                Expr::value(recipient_user_id as i64),
+            )
+            .filter(game_key::Column::Id.eq(id))
+            .filter(game_key::Column::Keystate.eq(KeyState::Reserved))
+            .filter(game_key::Column::RecipientUserId.eq(recipient_user_id))
+            .filter(game_key::Column::DeletedDate.is_null())
+            .exec(db)
+            .await?;
+
+        Ok(result.rows_affected > 0)
+    }
+
+    /// Checks the claim policy for a key whose state has just been changed in the transaction and
+    /// logs the reveal. The update holds the write lock of the database until the transaction
+    /// ends, so concurrent claims of a user are counted one after another. The transaction is
+    /// rolled back if the policy denies the claim.
+    async fn finish_claim(
+        txn: DatabaseTransaction,
+        cipher: &KeyCipher,
+        id: i32,
+        member: &PoolMember,
+        policy: &ClaimPolicy,
+    ) -> Result<ClaimOutcome, DbErr> {
+        let mut claimed = match GameKey::find_by_id(id).one(&txn).await? {
+            Some(k) => k,
+            None => {
+                txn.rollback().await?;
+                return Ok(ClaimOutcome::Unavailable);
+            }
+        };
+
+        if let Some(denial) = policy.check(&txn, member, &claimed).await? {
+            txn.rollback().await?;
+            return Ok(ClaimOutcome::Denied(denial));
+        }
+
+        GameKey::update_many()
+            .col_expr(
+                game_key::Column::ReservedUserId,
+                Expr::value(Option::<i64>::None),
+            )
+            .col_expr(
+                game_key::Column::ReservedUntil,
+                Expr::value(Option::<DateTime<Utc>>::None),
+            )
+            .filter(game_key::Column::Id.eq(id))
+            .exec(&txn)
+            .await?;
+        claimed.reserved_user_id = None;
+        claimed.reserved_until = None;
+
+        claimed.value = cipher.decrypt(&claimed.value)?;
+        AuditMutation::log_game_key(
+            &txn,
+            cipher,
+            AuditAction::Reveal,
+            member.user_id,
+            &claimed,
+            None,
+        )
+        .await?;
+
+        txn.commit().await?;
+
+        Ok(ClaimOutcome::Claimed(Box::new(claimed)))
+    }
+
+    /// Reserves an unused gamekey of a user for a holder until the given date. Reserved keys can
+    /// only be claimed by the holder. Returns `None` if the key is not an unused key of the user.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn reserve(
+        db: &DbConn,
+        cipher: &KeyCipher,
+        id: i32,
+        user_id: u64,
+        holder_user_id: u64,
+        until: DateTime<Utc>,
+    ) -> Result<Option<game_key::Model>, DbErr> {
+        let result = GameKey::update_many()
+            .col_expr(game_key::Column::Keystate, Expr::value(KeyState::Reserved))
+            .col_expr(
+                game_key::Column::ReservedUserId,
+                Expr::value(holder_user_id as i64),
+            )
+            .col_expr(game_key::Column::ReservedUntil, Expr::value(until))
+            .col_expr(game_key::Column::ModifyDate, Expr::value(Utc::now()))
+            .col_expr(game_key::Column::ModifyUserId, Expr::value(user_id as i64))
+            .filter(game_key::Column::Id.eq(id))
+            .filter(game_key::Column::CreateUserId.eq(user_id))
+            .filter(game_key::Column::Keystate.eq(KeyState::Unused))
+            .filter(game_key::Column::RecipientUserId.is_null())
+            .filter(game_key::Column::DeletedDate.is_null())
+            .exec(db)
+            .await?;
+
+        if result.rows_affected == 0 {
+            return Ok(None);
+        }
+
+        GameKeyQuery::get_one(db, cipher, id, user_id).await
+    }
+
+    /// Reserves an unused gamekey of a user as a gift for the recipient. Returns `None` if the
+    /// key is not an unused key of the user, e.g. because it has been claimed or gifted meanwhile.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn offer(
+        db: &DbConn,
+        cipher: &KeyCipher,
+        id: i32,
+        user_id: u64,
+        recipient_user_id: u64,
+    ) -> Result<Option<game_key::Model>, DbErr> {
+        let result = GameKey::update_many()
+            .col_expr(game_key::Column::Keystate, Expr::value(KeyState::Reserved))
+            .col_expr(
+                game_key::Column::RecipientUserId,
+                Expr::value(recipient_user_id as i64),
+            )
+            .col_expr(game_key::Column::ModifyDate, Expr::value(Utc::now()))
+            .col_expr(game_key::Column::ModifyUserId, Expr::value(user_id as i64))
+            .filter(game_key::Column::Id.eq(id))
+            .filter(game_key::Column::CreateUserId.eq(user_id))
+            .filter(game_key::Column::Keystate.eq(KeyState::Unused))
+            .filter(game_key::Column::RecipientUserId.is_null())
+            .filter(game_key::Column::DeletedDate.is_null())
+            .exec(db)
+            .await?;
+
+        if result.rows_affected == 0 {
+            return Ok(None);
+        }
+
+        GameKeyQuery::get_one(db, cipher, id, user_id).await
+    }
+
+    /// Reserves an unused gamekey of a user for a giveaway. Returns `None` if the key is not an
+    /// unused key of the user, e.g. because it has been claimed or gifted meanwhile.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn reserve_for_giveaway(
+        db: &DbConn,
+        cipher: &KeyCipher,
+        id: i32,
+        user_id: u64,
+    ) -> Result<Option<game_key::Model>, DbErr> {
+        let result = GameKey::update_many()
+            .col_expr(game_key::Column::Keystate, Expr::value(KeyState::Reserved))
+            .col_expr(game_key::Column::ModifyDate, Expr::value(Utc::now()))
+            .col_expr(game_key::Column::ModifyUserId, Expr::value(user_id as i64))
+            .filter(game_key::Column::Id.eq(id))
+            .filter(game_key::Column::CreateUserId.eq(user_id))
+            .filter(game_key::Column::Keystate.eq(KeyState::Unused))
+            .filter(game_key::Column::RecipientUserId.is_null())
+            .filter(game_key::Column::DeletedDate.is_null())
+            .exec(db)
+            .await?;
+
+        if result.rows_affected == 0 {
+            return Ok(None);
+        }
+
+        GameKeyQuery::get_one(db, cipher, id, user_id).await
+    }
+
+    /// Hands out the gamekey of a giveaway to its winner by marking it as used. Works like
+    /// [`Self::claim`], but the winner has been drawn already, so no claim limits apply.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn hand_out(
+        db: &DbConn,
+        cipher: &KeyCipher,
+        id: i32,
+        winner_user_id: u64,
+    ) -> Result<ClaimOutcome, DbErr> {
+        let txn = db.begin().await?;
+
+        let result = GameKey::update_many()
+            .col_expr(game_key::Column::Keystate, Expr::value(KeyState::Used))
+            .col_expr(game_key::Column::ModifyDate, Expr::value(Utc::now()))
+            .col_expr(
+                game_key::Column::ModifyUserId,
+                Expr::value(winner_user_id as i64),
+            )
+            .filter(Self::reserved_for_giveaway(id))
+            .exec(&txn)
+            .await?;
+
+        if result.rows_affected == 0 {
+            txn.rollback().await?;
+            return Ok(ClaimOutcome::Unavailable);
+        }
+
+        Self::finish_claim(
+            txn,
+            cipher,
+            id,
+            &PoolMember::new(winner_user_id),
+            &ClaimPolicy::default(),
+        )
+        .await
+    }
+
+    /// Releases the gamekey of a giveaway nobody entered, so it is unused again.
+    /// Returns `false` if the key is no longer reserved for the giveaway.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn release_from_giveaway(db: &DbConn, id: i32) -> Result<bool, DbErr> {
+        let result = GameKey::update_many()
+            .col_expr(game_key::Column::Keystate, Expr::value(KeyState::Unused))
+            .col_expr(game_key::Column::ModifyDate, Expr::value(Utc::now()))
+            .filter(Self::reserved_for_giveaway(id))
+            .exec(db)
+            .await?;
+
+        Ok(result.rows_affected > 0)
+    }
+
+    /// Releases all reservations which ended before the given date, so the keys are unused
+    /// again. Gifts are reserved without an end and are never released. Returns the released
+    /// keys as they were before, with encrypted values.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn release_reservations_before(
+        db: &DbConn,
+        before: DateTime<Utc>,
+    ) -> Result<Vec<game_key::Model>, DbErr> {
+        let txn = db.begin().await?;
+
+        let expired = GameKey::find()
+            .filter(game_key::Column::Keystate.eq(KeyState::Reserved))
+            .filter(game_key::Column::ReservedUntil.lt(before))
+            .filter(game_key::Column::RecipientUserId.is_null())
+            .all(&txn)
+            .await?;
+
+        let mut released = Vec::new();
+        for gamekey in expired {
+            let result = GameKey::update_many()
+                .col_expr(game_key::Column::Keystate, Expr::value(KeyState::Unused))
+                .col_expr(
+                    game_key::Column::ReservedUserId,
+                    Expr::value(Option::<i64>::None),
+                )
+                .col_expr(
+                    game_key::Column::ReservedUntil,
+                    Expr::value(Option::<DateTime<Utc>>::None),
+                )
+                .filter(game_key::Column::Id.eq(gamekey.id))
+                .filter(game_key::Column::Keystate.eq(KeyState::Reserved))
+                .filter(game_key::Column::ReservedUntil.lt(before))
+                .filter(game_key::Column::RecipientUserId.is_null())
+                .exec(&txn)
+                .await?;
+            if result.rows_affected == 1 {
+                released.push(gamekey);
+            }
+        }
+
+        txn.commit().await?;
+
+        Ok(released)
+    }
+
+    /// Condition matching the keys reserved for a user whose reservation has not ended yet.
+    fn reserved_for(user_id: u64, now: DateTime<Utc>) -> Condition {
+        Condition::all()
+            .add(game_key::Column::Keystate.eq(KeyState::Reserved))
+            .add(game_key::Column::ReservedUserId.eq(user_id))
+            .add(game_key::Column::ReservedUntil.gt(now))
+    }
+
+    /// Condition matching the key with the given id while it is reserved for a giveaway.
+    fn reserved_for_giveaway(id: i32) -> Condition {
+        Condition::all()
+            .add(game_key::Column::Id.eq(id))
+            .add(game_key::Column::Keystate.eq(KeyState::Reserved))
+            .add(game_key::Column::RecipientUserId.is_null())
+            .add(game_key::Column::ReservedUserId.is_null())
+            .add(game_key::Column::DeletedDate.is_null())
+    }
+
+    /// Re-encrypts all gamekey values and audited previous values with a new master key
+    /// inside a single transaction. Returns the number of re-encrypted keys.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail or a value cannot be decrypted with the current key. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn rotate_master_key(
+        db: &DbConn,
+        current: &KeyCipher,
+        new: &KeyCipher,
+    ) -> Result<u64, DbErr> {
+        let txn = db.begin().await?;
+        let mut rotated = 0;
+
+        for gamekey in GameKey::find().all(&txn).await? {
+            let plaintext = current.decrypt(&gamekey.value)?;
+
+            game_key::ActiveModel {
+                id: Set(gamekey.id),
+                value: Set(new.encrypt(&plaintext)),
+                ..Default::default()
+            }
+            .update(&txn)
+            .await?;
+
+            rotated += 1;
+        }
+
+        let audit_events = AuditEvent::find()
+            .filter(audit_event::Column::PreviousValue.is_not_null())
+            .all(&txn)
+            .await?;
+
+        for event in audit_events {
+            let plaintext = current.decrypt(&event.previous_value.unwrap_or_default())?;
+
+            audit_event::ActiveModel {
+                id: Set(event.id),
+                previous_value: Set(Some(new.encrypt(&plaintext))),
+                ..Default::default()
+            }
+            .update(&txn)
+            .await?;
+        }
+
+        txn.commit().await?;
+
+        Ok(rotated)
+    }
+
     /// Deletes a gamekey by its id.
     ///
     /// # Errors
@@ -139,15 +990,892 @@ impl GameKeyMutation {
             .await
     }
 
-    /// Deletes all game keys by game id.
+    /// Moves a gamekey of a user into the trash.
     ///
     /// # Errors
     ///
     /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
-    pub async fn delete_by_game(db: &DbConn, game_id: i32) -> Result<DeleteResult, DbErr> {
-        GameKey::delete_many()
-            .filter(game_key::Column::GameId.eq(game_id))
+    pub async fn trash(db: &DbConn, id: i32, user_id: u64) -> Result<UpdateResult, DbErr> {
+        GameKey::update_many()
+            .col_expr(game_key::Column::DeletedDate, Expr::value(Utc::now()))
+            .col_expr(game_key::Column::DeletedUserId, Expr::value(user_id as i64))
+            .filter(game_key::Column::Id.eq(id))
+            .filter(game_key::Column::CreateUserId.eq(user_id))
+            .filter(game_key::Column::DeletedDate.is_null())
+            .exec(db)
+            .await
+    }
+
+    /// Restores a gamekey of a user from the trash.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn restore(db: &DbConn, id: i32, user_id: u64) -> Result<UpdateResult, DbErr> {
+        GameKey::update_many()
+            .col_expr(
+                game_key::Column::DeletedDate,
+                Expr::value(None::<DateTime<Utc>>),
+            )
+            .col_expr(game_key::Column::DeletedUserId, Expr::value(None::<i64>))
+            .filter(game_key::Column::Id.eq(id))
+            .filter(game_key::Column::CreateUserId.eq(user_id))
+            .filter(game_key::Column::DeletedDate.is_not_null())
+            .exec(db)
+            .await
+    }
+
+    /// Permanently deletes a trashed gamekey of a user.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn purge(db: &DbConn, id: i32, user_id: u64) -> Result<DeleteResult, DbErr> {
+        let txn = db.begin().await?;
+        let purged = Self::delete_with_giveaways(
+            &txn,
+            Condition::all()
+                .add(game_key::Column::Id.eq(id))
+                .add(game_key::Column::CreateUserId.eq(user_id))
+                .add(game_key::Column::DeletedDate.is_not_null()),
+        )
+        .await?;
+        txn.commit().await?;
+
+        Ok(purged)
+    }
+
+    /// Deletes all gamekeys matching the condition together with their giveaways,
+    /// which would otherwise keep the keys referenced.
+    async fn delete_with_giveaways<C: ConnectionTrait>(
+        db: &C,
+        condition: Condition,
+    ) -> Result<DeleteResult, DbErr> {
+        Giveaway::delete_many()
+            .filter(
+                giveaway::Column::GameKeyId.in_subquery(
+                    GameKey::find()
+                        .select_only()
+                        .column(game_key::Column::Id)
+                        .filter(condition.clone())
+                        .into_query(),
+                ),
+            )
             .exec(db)
+            .await?;
+        GameKey::delete_many().filter(condition).exec(db).await
+    }
+
+    /// Marks all unused keys which expired before the given date as expired.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn expire_before(db: &DbConn, before: NaiveDateTime) -> Result<UpdateResult, DbErr> {
+        GameKey::update_many()
+            .col_expr(game_key::Column::Keystate, Expr::value(KeyState::Expired))
+            .filter(game_key::Column::Keystate.eq(KeyState::Unused))
+            .filter(game_key::Column::ExpirationDate.lt(before))
+            .filter(game_key::Column::DeletedDate.is_null())
+            .exec(db)
+            .await
+    }
+
+    /// Deletes all game keys by game id.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn delete_by_game(db: &DbConn, game_id: i32) -> Result<DeleteResult, DbErr> {
+        GameKey::delete_many()
+            .filter(game_key::Column::GameId.eq(game_id))
+            .exec(db)
+            .await
+    }
+}
+
+pub struct AuditMutation;
+
+impl AuditMutation {
+    /// Records an event on a game. The previous state of the game gets stored as json.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn log_game(
+        db: &DbConn,
+        action: AuditAction,
+        user_id: u64,
+        game: &game::Model,
+        previous: Option<&game::Model>,
+    ) -> Result<audit_event::Model, DbErr> {
+        let previous_state = previous
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|why| DbErr::Custom(format!("Could not serialize game snapshot: {why}")))?;
+
+        audit_event::ActiveModel {
+            action: Set(action),
+            user_id: Set(user_id as i64),
+            game_id: Set(Some(game.id)),
+            game_key_id: Set(None),
+            previous_state: Set(previous_state),
+            previous_value: Set(None),
+            create_date: Set(Utc::now()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+    }
+
+    /// Records an event on a gamekey. The previous state of the gamekey gets stored as json,
+    /// its previous value gets stored encrypted and separately from the snapshot.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn log_game_key<C: ConnectionTrait>(
+        db: &C,
+        cipher: &KeyCipher,
+        action: AuditAction,
+        user_id: u64,
+        game_key: &game_key::Model,
+        previous: Option<&game_key::Model>,
+    ) -> Result<audit_event::Model, DbErr> {
+        let previous_value = previous.map(|x| cipher.encrypt(&x.value));
+        let previous_state = previous
+            .map(|x| {
+                serde_json::to_string(&game_key::Model {
+                    value: String::new(),
+                    ..x.clone()
+                })
+            })
+            .transpose()
+            .map_err(|why| DbErr::Custom(format!("Could not serialize gamekey snapshot: {why}")))?;
+
+        audit_event::ActiveModel {
+            action: Set(action),
+            user_id: Set(user_id as i64),
+            game_id: Set(Some(game_key.game_id)),
+            game_key_id: Set(Some(game_key.id)),
+            previous_state: Set(previous_state),
+            previous_value: Set(previous_value),
+            create_date: Set(Utc::now()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+    }
+}
+
+pub struct GiveawayMutation;
+
+impl GiveawayMutation {
+    /// Creates a new running giveaway.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn create(db: &DbConn, giveaway: giveaway::Model) -> Result<giveaway::Model, DbErr> {
+        giveaway::ActiveModel {
+            game_key_id: Set(giveaway.game_key_id),
+            guild_id: Set(giveaway.guild_id),
+            channel_id: Set(giveaway.channel_id),
+            message_id: Set(giveaway.message_id),
+            host_user_id: Set(giveaway.host_user_id),
+            state: Set(GiveawayState::Running),
+            end_date: Set(giveaway.end_date),
+            winner_user_id: Set(None),
+            create_date: Set(giveaway.create_date),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+    }
+
+    /// Sets the id of the message announcing the giveaway.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn set_message_id(
+        db: &DbConn,
+        id: i32,
+        message_id: u64,
+    ) -> Result<giveaway::Model, DbErr> {
+        giveaway::ActiveModel {
+            id: Set(id),
+            message_id: Set(Some(message_id as i64)),
+            ..Default::default()
+        }
+        .update(db)
+        .await
+    }
+
+    /// Enters a user into a running giveaway.
+    /// Returns `false` if the user has already entered the giveaway.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn enter(db: &DbConn, giveaway_id: i32, user_id: u64) -> Result<bool, DbErr> {
+        let existing = giveaway_entry::Entity::find()
+            .filter(giveaway_entry::Column::GiveawayId.eq(giveaway_id))
+            .filter(giveaway_entry::Column::UserId.eq(user_id))
+            .one(db)
+            .await?;
+
+        if existing.is_some() {
+            return Ok(false);
+        }
+
+        giveaway_entry::ActiveModel {
+            giveaway_id: Set(giveaway_id),
+            user_id: Set(user_id as i64),
+            create_date: Set(Utc::now()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        Ok(true)
+    }
+
+    /// Ends a running giveaway with the given state and winner.
+    /// Returns `None` if the giveaway has already ended.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn finish(
+        db: &DbConn,
+        id: i32,
+        state: GiveawayState,
+        winner_user_id: Option<u64>,
+    ) -> Result<Option<giveaway::Model>, DbErr> {
+        let updated = Giveaway::update_many()
+            .col_expr(giveaway::Column::State, Expr::value(state))
+            .col_expr(
+                giveaway::Column::WinnerUserId,
+                Expr::value(winner_user_id.map(|x| x as i64)),
+            )
+            .filter(giveaway::Column::Id.eq(id))
+            .filter(giveaway::Column::State.eq(GiveawayState::Running))
+            .exec(db)
+            .await?;
+
+        if updated.rows_affected == 0 {
+            return Ok(None);
+        }
+
+        Giveaway::find_by_id(id).one(db).await
+    }
+}
+
+pub struct BackupMutation;
+
+impl BackupMutation {
+    /// Restores a backup inside a single transaction.
+    ///
+    /// Platforms and pools are matched by name, games by title and gamekeys by value. Matching rows are kept
+    /// as they are, all other rows are inserted with their original id if it is still free. Keys
+    /// which exist for another game or reference rows missing in the backup are reported as conflicts.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn restore(
+        db: &DbConn,
+        cipher: &KeyCipher,
+        backup: Backup,
+    ) -> Result<RestoreReport, DbErr> {
+        let mut report = RestoreReport::default();
+        let mut platform_ids = HashMap::new();
+        let mut pool_ids = HashMap::new();
+        let mut game_ids = HashMap::new();
+        let txn = db.begin().await?;
+
+        for restored in backup.platforms {
+            let existing = Platform::find()
+                .filter(platform::Column::Name.eq(&restored.name))
+                .one(&txn)
+                .await?;
+            if let Some(existing) = existing {
+                platform_ids.insert(restored.id, existing.id);
+                report.platforms.existing += 1;
+                continue;
+            }
+
+            let id_taken = Platform::find_by_id(restored.id).one(&txn).await?.is_some();
+            let created = platform::ActiveModel {
+                id: if id_taken { NotSet } else { Set(restored.id) },
+                name: Set(restored.name),
+                store_link: Set(restored.store_link),
+            }
+            .insert(&txn)
+            .await?;
+
+            platform_ids.insert(restored.id, created.id);
+            report.platforms.created += 1;
+        }
+
+        for restored in backup.pools {
+            let existing = KeyPool::find()
+                .filter(key_pool::Column::Name.eq(&restored.name))
+                .one(&txn)
+                .await?;
+            if let Some(existing) = existing {
+                pool_ids.insert(restored.id, existing.id);
+                report.pools.existing += 1;
+                continue;
+            }
+
+            let id_taken = KeyPool::find_by_id(restored.id).one(&txn).await?.is_some();
+            let created = key_pool::ActiveModel {
+                id: if id_taken { NotSet } else { Set(restored.id) },
+                name: Set(restored.name),
+                kind: Set(restored.kind),
+                owner_user_id: Set(restored.owner_user_id),
+                guild_id: Set(restored.guild_id),
+                role_id: Set(restored.role_id),
+                create_date: Set(restored.create_date),
+            }
+            .insert(&txn)
+            .await?;
+
+            pool_ids.insert(restored.id, created.id);
+            report.pools.created += 1;
+        }
+
+        for restored in backup.games {
+            let existing = Game::find()
+                .filter(game::Column::Title.eq(&restored.title))
+                .one(&txn)
+                .await?;
+            if let Some(existing) = existing {
+                game_ids.insert(restored.id, existing.id);
+                report.games.existing += 1;
+                continue;
+            }
+
+            let id_taken = Game::find_by_id(restored.id).one(&txn).await?.is_some();
+            let created = game::ActiveModel {
+                id: if id_taken { NotSet } else { Set(restored.id) },
+                title: Set(restored.title),
+                description: Set(restored.description),
+                create_date: Set(restored.create_date),
+                create_user_id: Set(restored.create_user_id),
+                modify_date: Set(restored.modify_date),
+                modify_user_id: Set(restored.modify_user_id),
+                image_link: Set(restored.image_link),
+                deleted_date: Set(restored.deleted_date),
+                deleted_user_id: Set(restored.deleted_user_id),
+                metadata: Set(restored.metadata),
+                steam_appid: Set(restored.steam_appid),
+                gog_id: Set(restored.gog_id),
+                epic_slug: Set(restored.epic_slug),
+            }
+            .insert(&txn)
+            .await?;
+
+            game_ids.insert(restored.id, created.id);
+            report.games.created += 1;
+        }
+
+        for restored in backup.game_keys {
+            let pool_id = match restored.pool_id {
+                Some(id) => pool_ids.get(&id).copied().map(Some),
+                None => Some(None),
+            };
+            let (game_id, platform_id, pool_id) = match (
+                game_ids.get(&restored.game_id),
+                platform_ids.get(&restored.platform_id),
+                pool_id,
+            ) {
+                (Some(g), Some(p), Some(pool_id)) => (*g, *p, pool_id),
+                _ => {
+                    report.conflicts.push(format!(
+                        "Key {} references a game, platform or pool which is missing in the backup.",
+                        restored.id
+                    ));
+                    continue;
+                }
+            };
+
+            let value = cipher.encrypt(&restored.value);
+            let existing = GameKey::find()
+                .filter(game_key::Column::Value.eq(&value))
+                .one(&txn)
+                .await?;
+            if let Some(existing) = existing {
+                if existing.game_id == game_id {
+                    report.game_keys.existing += 1;
+                } else {
+                    report.conflicts.push(format!(
+                        "Key {} already exists as key {} of another game.",
+                        restored.id, existing.id
+                    ));
+                }
+                continue;
+            }
+
+            let id_taken = GameKey::find_by_id(restored.id).one(&txn).await?.is_some();
+            game_key::ActiveModel {
+                id: if id_taken { NotSet } else { Set(restored.id) },
+                game_id: Set(game_id),
+                platform_id: Set(platform_id),
+                value: Set(value),
+                keystate: Set(restored.keystate),
+                page_link: Set(restored.page_link),
+                create_date: Set(restored.create_date),
+                create_user_id: Set(restored.create_user_id),
+                modify_date: Set(restored.modify_date),
+                modify_user_id: Set(restored.modify_user_id),
+                notes: Set(restored.notes),
+                expiration_date: Set(restored.expiration_date),
+                deleted_date: Set(restored.deleted_date),
+                deleted_user_id: Set(restored.deleted_user_id),
+                recipient_user_id: Set(restored.recipient_user_id),
+                source: Set(restored.source),
+                pool_id: Set(pool_id),
+                reserved_user_id: Set(restored.reserved_user_id),
+                reserved_until: Set(restored.reserved_until),
+            }
+            .insert(&txn)
+            .await?;
+
+            report.game_keys.created += 1;
+        }
+
+        txn.commit().await?;
+
+        Ok(report)
+    }
+}
+
+/// Number of steam apps changed by [`SteamAppMutation::sync`].
+#[derive(Clone, Debug, Default)]
+pub struct SteamAppSync {
+    pub created: u64,
+    pub updated: u64,
+    pub deleted: u64,
+}
+
+pub struct SteamAppMutation;
+
+impl SteamAppMutation {
+    /// Number of rows written by a single insert or delete statement.
+    const BATCH_SIZE: usize = 1000;
+
+    /// Replaces the stored snapshot of the steam app list inside a single transaction.
+    /// Only new, renamed and removed apps are written. Repeated app ids are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn sync(db: &DbConn, apps: Vec<steam_app::Model>) -> Result<SteamAppSync, DbErr> {
+        let txn = db.begin().await?;
+        let mut report = SteamAppSync::default();
+
+        let mut existing = SteamApp::find()
+            .all(&txn)
+            .await?
+            .into_iter()
+            .map(|x| (x.appid, x.name))
+            .collect::<HashMap<i64, String>>();
+
+        let mut seen = HashSet::new();
+        let mut created = Vec::new();
+        for app in apps {
+            if !seen.insert(app.appid) {
+                continue;
+            }
+
+            match existing.remove(&app.appid) {
+                Some(name) if name == app.name => {}
+                Some(_) => {
+                    SteamApp::update(steam_app::ActiveModel {
+                        appid: Set(app.appid),
+                        name: Set(app.name),
+                    })
+                    .exec(&txn)
+                    .await?;
+                    report.updated += 1;
+                }
+                None => created.push(steam_app::ActiveModel {
+                    appid: Set(app.appid),
+                    name: Set(app.name),
+                }),
+            }
+        }
+
+        report.created = created.len() as u64;
+        let mut created = created.into_iter().peekable();
+        while created.peek().is_some() {
+            SteamApp::insert_many(created.by_ref().take(Self::BATCH_SIZE))
+                .exec(&txn)
+                .await?;
+        }
+
+        let deleted = existing.into_keys().collect::<Vec<i64>>();
+        for chunk in deleted.chunks(Self::BATCH_SIZE) {
+            report.deleted += SteamApp::delete_many()
+                .filter(steam_app::Column::Appid.is_in(chunk.iter().copied()))
+                .exec(&txn)
+                .await?
+                .rows_affected;
+        }
+
+        txn.commit().await?;
+
+        Ok(report)
+    }
+}
+
+pub struct UserSettingMutation;
+
+impl UserSettingMutation {
+    /// Sets the steam store country code of a user. `None` resets it to the default.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn set_country_code(
+        db: &DbConn,
+        user_id: u64,
+        country_code: Option<String>,
+    ) -> Result<user_setting::Model, DbErr> {
+        Self::save(db, user_id, |x| x.country_code = Set(country_code)).await
+    }
+
+    /// Sets whether keys added by a user are announced to users wishing for the game.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn set_share_keys(
+        db: &DbConn,
+        user_id: u64,
+        share_keys: bool,
+    ) -> Result<user_setting::Model, DbErr> {
+        Self::save(db, user_id, |x| x.share_keys = Set(share_keys)).await
+    }
+
+    /// Lifts the claim limits of a user until the given date. `None` removes the override.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn set_claim_override(
+        db: &DbConn,
+        user_id: u64,
+        until: Option<DateTime<Utc>>,
+    ) -> Result<user_setting::Model, DbErr> {
+        Self::save(db, user_id, |x| x.claim_override_until = Set(until)).await
+    }
+
+    /// Applies a change to the settings of a user. Missing settings are created with defaults.
+    async fn save(
+        db: &DbConn,
+        user_id: u64,
+        change: impl FnOnce(&mut user_setting::ActiveModel),
+    ) -> Result<user_setting::Model, DbErr> {
+        match UserSetting::find_by_id(user_id as i64).one(db).await? {
+            Some(setting) => {
+                let mut setting: user_setting::ActiveModel = setting.into();
+                change(&mut setting);
+                setting.update(db).await
+            }
+            None => {
+                let mut setting = user_setting::ActiveModel {
+                    user_id: Set(user_id as i64),
+                    country_code: Set(None),
+                    share_keys: Set(true),
+                    claim_override_until: Set(None),
+                };
+                change(&mut setting);
+                setting.insert(db).await
+            }
+        }
+    }
+}
+
+pub struct PriceSnapshotMutation;
+
+impl PriceSnapshotMutation {
+    /// Stores the price of a steam app in a store region.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn create(
+        db: &DbConn,
+        snapshot: price_snapshot::Model,
+    ) -> Result<price_snapshot::Model, DbErr> {
+        price_snapshot::ActiveModel {
+            steam_appid: Set(snapshot.steam_appid),
+            country_code: Set(snapshot.country_code),
+            currency: Set(snapshot.currency),
+            initial_price: Set(snapshot.initial_price),
+            final_price: Set(snapshot.final_price),
+            discount_percent: Set(snapshot.discount_percent),
+            create_date: Set(snapshot.create_date),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+    }
+}
+
+pub struct WishlistMutation;
+
+impl WishlistMutation {
+    /// Adds a game or steam app to the wishlist of a user. Returns `None` if it already is on the wishlist.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn add(
+        db: &DbConn,
+        user_id: u64,
+        game_id: Option<i32>,
+        steam_appid: Option<i64>,
+    ) -> Result<Option<wishlist::Model>, DbErr> {
+        let mut condition = Condition::any();
+        if let Some(game_id) = game_id {
+            condition = condition.add(wishlist::Column::GameId.eq(game_id));
+        }
+        if let Some(steam_appid) = steam_appid {
+            condition = condition.add(wishlist::Column::SteamAppid.eq(steam_appid));
+        }
+
+        let existing = Wishlist::find()
+            .filter(wishlist::Column::UserId.eq(user_id))
+            .filter(condition)
+            .one(db)
+            .await?;
+        if existing.is_some() {
+            return Ok(None);
+        }
+
+        wishlist::ActiveModel {
+            user_id: Set(user_id as i64),
+            game_id: Set(game_id),
+            steam_appid: Set(steam_appid),
+            create_date: Set(Utc::now()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .map(Some)
+    }
+
+    /// Removes an entry from the wishlist of a user.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn remove(db: &DbConn, user_id: u64, id: i32) -> Result<DeleteResult, DbErr> {
+        Wishlist::delete_many()
+            .filter(wishlist::Column::Id.eq(id))
+            .filter(wishlist::Column::UserId.eq(user_id))
+            .exec(db)
+            .await
+    }
+}
+
+pub struct KeyPoolMutation;
+
+impl KeyPoolMutation {
+    /// Creates a new key pool.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn create(db: &DbConn, pool: key_pool::Model) -> Result<key_pool::Model, DbErr> {
+        key_pool::ActiveModel {
+            name: Set(pool.name),
+            kind: Set(pool.kind),
+            owner_user_id: Set(pool.owner_user_id),
+            guild_id: Set(pool.guild_id),
+            role_id: Set(pool.role_id),
+            create_date: Set(pool.create_date),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+    }
+
+    /// Moves a gamekey of the member into a pool, or back to their personal keys with `None`.
+    /// Only the creator of a key may move it and only into pools they can access.
+    /// Returns the moved key, or `None` if the key or pool is not accessible.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn move_key(
+        db: &DbConn,
+        cipher: &KeyCipher,
+        game_key_id: i32,
+        pool: Option<&key_pool::Model>,
+        member: &PoolMember,
+    ) -> Result<Option<game_key::Model>, DbErr> {
+        if pool.is_some_and(|x| !member.can_access(x)) {
+            return Ok(None);
+        }
+        let game_key = match GameKeyQuery::get_one(db, cipher, game_key_id, member.user_id).await? {
+            Some(k) => k,
+            None => return Ok(None),
+        };
+
+        let mut moved = game_key::ActiveModel {
+            id: Set(game_key.id),
+            pool_id: Set(pool.map(|x| x.id)),
+            modify_date: Set(Some(Utc::now())),
+            modify_user_id: Set(Some(member.user_id as i64)),
+            ..Default::default()
+        }
+        .update(db)
+        .await?;
+
+        moved.value = cipher.decrypt(&moved.value)?;
+
+        Ok(Some(moved))
+    }
+}
+
+pub struct ExpiryReminderMutation;
+
+impl ExpiryReminderMutation {
+    /// Records that the owner of a key has been reminded about its expiration within a window.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn record(
+        db: &DbConn,
+        game_key_id: i32,
+        window_days: i32,
+    ) -> Result<expiry_reminder::Model, DbErr> {
+        expiry_reminder::ActiveModel {
+            game_key_id: Set(game_key_id),
+            window_days: Set(window_days),
+            create_date: Set(Utc::now()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use sea_orm::PaginatorTrait;
+
+    async fn insert_giveaway(db: &DbConn, game_key_id: i32) -> giveaway::Model {
+        let giveaway = GiveawayMutation::create(
+            db,
+            giveaway::Model {
+                id: 0,
+                game_key_id,
+                guild_id: 1,
+                channel_id: 2,
+                message_id: None,
+                host_user_id: 1,
+                state: GiveawayState::Running,
+                end_date: Utc::now(),
+                winner_user_id: None,
+                create_date: Utc::now(),
+            },
+        )
+        .await
+        .unwrap();
+        GiveawayMutation::enter(db, giveaway.id, 2).await.unwrap();
+        giveaway
+    }
+
+    #[async_std::test]
+    async fn purge_trashed_before_deletes_giveaways_of_purged_keys() {
+        let db = testing::database().await;
+        let trashed = Some(Utc::now() - chrono::Duration::days(40));
+        let platform = testing::insert_platform(&db, "Steam").await;
+        let game = testing::insert_game(&db, "Portal", None).await;
+        let trashed_game = testing::insert_game(&db, "Portal 2", trashed).await;
+        let kept_key =
+            testing::insert_game_key(&db, game.id, platform.id, KeyState::Unused, None).await;
+        let trashed_key =
+            testing::insert_game_key(&db, game.id, platform.id, KeyState::Unused, trashed).await;
+        let trashed_game_key =
+            testing::insert_game_key(&db, trashed_game.id, platform.id, KeyState::Used, trashed)
+                .await;
+        let kept_giveaway = insert_giveaway(&db, kept_key.id).await;
+        insert_giveaway(&db, trashed_key.id).await;
+        insert_giveaway(&db, trashed_game_key.id).await;
+
+        let purged =
+            GameMutation::purge_trashed_before(&db, Utc::now() - chrono::Duration::days(30))
+                .await
+                .unwrap();
+
+        assert_eq!(purged, (2, 1));
+        let giveaways = Giveaway::find().all(&db).await.unwrap();
+        assert_eq!(giveaways, vec![kept_giveaway]);
+        assert_eq!(giveaway_entry::Entity::find().count(&db).await.unwrap(), 1);
+    }
+
+    #[async_std::test]
+    async fn purge_deletes_giveaways_of_the_key() {
+        let db = testing::database().await;
+        let platform = testing::insert_platform(&db, "Steam").await;
+        let game = testing::insert_game(&db, "Portal", None).await;
+        let game_key = testing::insert_game_key(
+            &db,
+            game.id,
+            platform.id,
+            KeyState::Unused,
+            Some(Utc::now()),
+        )
+        .await;
+        insert_giveaway(&db, game_key.id).await;
+
+        let purged = GameKeyMutation::purge(&db, game_key.id, 1).await.unwrap();
+
+        assert_eq!(purged.rows_affected, 1);
+        assert_eq!(Giveaway::find().count(&db).await.unwrap(), 0);
+    }
+
+    #[async_std::test]
+    async fn hand_out_uses_the_key_of_the_giveaway_only_once() {
+        let db = testing::database().await;
+        let cipher = KeyCipher::new("secret");
+        let platform = testing::insert_platform(&db, "Steam").await;
+        let game = testing::insert_game(&db, "Portal", None).await;
+        let game_key =
+            testing::insert_game_key(&db, game.id, platform.id, KeyState::Reserved, None).await;
+
+        let first = GameKeyMutation::hand_out(&db, &cipher, game_key.id, 2)
+            .await
+            .unwrap();
+        let second = GameKeyMutation::hand_out(&db, &cipher, game_key.id, 3)
+            .await
+            .unwrap();
+
+        match first {
+            ClaimOutcome::Claimed(k) => assert_eq!(k.keystate, KeyState::Used),
+            other => panic!("unexpected outcome {other:?}"),
+        }
+        assert!(matches!(second, ClaimOutcome::Unavailable));
+        let reveals = AuditEvent::find()
+            .filter(audit_event::Column::Action.eq(AuditAction::Reveal))
+            .all(&db)
+            .await
+            .unwrap();
+        assert_eq!(reveals.len(), 1);
+        assert_eq!(reveals[0].user_id, 2);
+        assert!(!GameKeyMutation::release_from_giveaway(&db, game_key.id)
             .await
+            .unwrap());
     }
 }
@@ -0,0 +1,398 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, Duration, Utc};
+use entity::{
+    audit_event::{self, Entity as AuditEvent},
+    game_key::{self, Entity as GameKey},
+    sea_orm_active_enums::AuditAction,
+    user_setting::Entity as UserSetting,
+};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+};
+
+use crate::pool::PoolMember;
+
+/// Limits for claiming keys shared by other users. Users can always claim their own keys.
+#[derive(Clone, Debug)]
+pub struct ClaimPolicy {
+    /// Maximum number of shared keys a user may claim within `window`.
+    pub max_claims: Option<u64>,
+    pub window: Duration,
+    /// Maximum number of shared keys a user may claim of a single game.
+    pub max_per_game: Option<u64>,
+    /// Minimum age of the discord account of a user.
+    pub min_account_age: Option<Duration>,
+    /// Minimum time a user has to be a member of the guild the key is claimed in.
+    pub min_membership_age: Option<Duration>,
+}
+
+impl Default for ClaimPolicy {
+    fn default() -> Self {
+        Self {
+            max_claims: None,
+            window: Duration::hours(24),
+            max_per_game: None,
+            min_account_age: None,
+            min_membership_age: None,
+        }
+    }
+}
+
+/// Why a user may not claim a key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ClaimDenial {
+    /// The user claimed `limit` shared keys within the window.
+    Quota {
+        limit: u64,
+        next_claim: DateTime<Utc>,
+    },
+    /// The user claimed `limit` shared keys of the game.
+    GameLimit { limit: u64 },
+    /// The discord account of the user is too new.
+    AccountAge { allowed_from: DateTime<Utc> },
+    /// The user joined the guild too recently.
+    MembershipAge { allowed_from: DateTime<Utc> },
+}
+
+/// Result of claiming a key.
+#[derive(Clone, Debug)]
+pub enum ClaimOutcome {
+    /// The key has been claimed. Contains the decrypted key.
+    Claimed(Box<game_key::Model>),
+    /// The claim policy does not allow the claim.
+    Denied(ClaimDenial),
+    /// The key does not exist, can not be accessed or has already been claimed.
+    Unavailable,
+}
+
+impl ClaimDenial {
+    /// Whether the user may not claim any other shared key either.
+    #[must_use]
+    pub fn blocks_all(&self) -> bool {
+        !matches!(self, ClaimDenial::GameLimit { .. })
+    }
+}
+
+impl ClaimPolicy {
+    /// Checks whether the member may claim a key. Every shared key revealed to the member counts
+    /// towards the limits, which are lifted while an admin override is active. Keys reserved for
+    /// the member are not limited.
+    /// Returns `None` if the claim is allowed. Claims check the policy inside their transaction,
+    /// see [`GameKeyMutation::claim`](crate::mutation::GameKeyMutation::claim).
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn check<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        member: &PoolMember,
+        game_key: &game_key::Model,
+    ) -> Result<Option<ClaimDenial>, DbErr> {
+        if game_key.create_user_id == member.user_id as i64
+            || game_key.reserved_user_id == Some(member.user_id as i64)
+        {
+            return Ok(None);
+        }
+
+        let now = Utc::now();
+        let override_until = UserSetting::find_by_id(member.user_id as i64)
+            .one(db)
+            .await?
+            .and_then(|x| x.claim_override_until);
+        if override_until.is_some_and(|x| x > now) {
+            return Ok(None);
+        }
+
+        let allowed_from = self
+            .min_account_age
+            .zip(member.account_date)
+            .map(|(age, date)| date + age);
+        if let Some(allowed_from) = allowed_from.filter(|x| *x > now) {
+            return Ok(Some(ClaimDenial::AccountAge { allowed_from }));
+        }
+        let allowed_from = self
+            .min_membership_age
+            .zip(member.join_date)
+            .map(|(age, date)| date + age);
+        if let Some(allowed_from) = allowed_from.filter(|x| *x > now) {
+            return Ok(Some(ClaimDenial::MembershipAge { allowed_from }));
+        }
+
+        if self.max_claims.is_none() && self.max_per_game.is_none() {
+            return Ok(None);
+        }
+        let claims = Self::get_shared_claims(db, member.user_id).await?;
+
+        if let Some(limit) = self.max_claims {
+            let since = now - self.window;
+            let in_window = claims
+                .iter()
+                .filter(|x| x.create_date > since)
+                .collect::<Vec<&audit_event::Model>>();
+            if in_window.len() as u64 >= limit {
+                // Claims are ordered by date. A claim is possible again once all but `limit - 1`
+                // claims left the window.
+                let next_claim = in_window
+                    .iter()
+                    .rev()
+                    .nth(limit.saturating_sub(1) as usize)
+                    .map_or(now, |x| x.create_date + self.window);
+                return Ok(Some(ClaimDenial::Quota { limit, next_claim }));
+            }
+        }
+
+        if let Some(limit) = self.max_per_game {
+            let of_game = claims
+                .iter()
+                .filter(|x| x.game_id == Some(game_key.game_id))
+                .count();
+            if of_game as u64 >= limit {
+                return Ok(Some(ClaimDenial::GameLimit { limit }));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Gets the first reveal of every key of another user revealed to a user, oldest first.
+    async fn get_shared_claims<C: ConnectionTrait>(
+        db: &C,
+        user_id: u64,
+    ) -> Result<Vec<audit_event::Model>, DbErr> {
+        let events = AuditEvent::find()
+            .filter(audit_event::Column::Action.eq(AuditAction::Reveal))
+            .filter(audit_event::Column::UserId.eq(user_id))
+            .filter(audit_event::Column::GameKeyId.is_not_null())
+            .order_by_asc(audit_event::Column::CreateDate)
+            .all(db)
+            .await?;
+
+        let own_keys: HashSet<i32> = GameKey::find()
+            .select_only()
+            .column(game_key::Column::Id)
+            .filter(game_key::Column::Id.is_in(events.iter().filter_map(|x| x.game_key_id)))
+            .filter(game_key::Column::CreateUserId.eq(user_id))
+            .into_tuple::<i32>()
+            .all(db)
+            .await?
+            .into_iter()
+            .collect();
+
+        let mut seen = HashSet::new();
+        Ok(events
+            .into_iter()
+            .filter(|x| x.game_key_id.is_some_and(|id| !own_keys.contains(&id)))
+            .filter(|x| seen.insert(x.game_key_id))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use entity::{sea_orm_active_enums::KeyState, user_setting};
+    use sea_orm::{ActiveModelTrait, DbConn, Set};
+
+    const MEMBER: u64 = 2;
+
+    async fn reveal(db: &DbConn, game_key: &game_key::Model, date: DateTime<Utc>) -> DateTime<Utc> {
+        audit_event::ActiveModel {
+            action: Set(AuditAction::Reveal),
+            user_id: Set(MEMBER as i64),
+            game_id: Set(Some(game_key.game_id)),
+            game_key_id: Set(Some(game_key.id)),
+            previous_state: Set(None),
+            previous_value: Set(None),
+            create_date: Set(date),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .unwrap()
+        .create_date
+    }
+
+    async fn shared_key(db: &DbConn, game_id: i32) -> game_key::Model {
+        testing::insert_game_key(db, game_id, 1, KeyState::Unused, None).await
+    }
+
+    async fn setup() -> (DbConn, i32) {
+        let db = testing::database().await;
+        testing::insert_platform(&db, "Steam").await;
+        let game = testing::insert_game(&db, "Portal", None).await;
+        (db, game.id)
+    }
+
+    #[async_std::test]
+    async fn window_quota() {
+        let (db, game_id) = setup().await;
+        let policy = ClaimPolicy {
+            max_claims: Some(2),
+            ..Default::default()
+        };
+        let now = Utc::now();
+        reveal(
+            &db,
+            &shared_key(&db, game_id).await,
+            now - Duration::hours(30),
+        )
+        .await;
+        let oldest = reveal(
+            &db,
+            &shared_key(&db, game_id).await,
+            now - Duration::hours(10),
+        )
+        .await;
+        let key = shared_key(&db, game_id).await;
+        let member = PoolMember::new(MEMBER);
+
+        assert_eq!(policy.check(&db, &member, &key).await.unwrap(), None);
+
+        reveal(
+            &db,
+            &shared_key(&db, game_id).await,
+            now - Duration::hours(2),
+        )
+        .await;
+
+        assert_eq!(
+            policy.check(&db, &member, &key).await.unwrap(),
+            Some(ClaimDenial::Quota {
+                limit: 2,
+                next_claim: oldest + Duration::hours(24),
+            })
+        );
+    }
+
+    #[async_std::test]
+    async fn quota_counts_every_key_once_and_skips_own_keys() {
+        let (db, game_id) = setup().await;
+        let policy = ClaimPolicy {
+            max_claims: Some(2),
+            ..Default::default()
+        };
+        let now = Utc::now();
+        let shared = shared_key(&db, game_id).await;
+        reveal(&db, &shared, now - Duration::hours(3)).await;
+        reveal(&db, &shared, now - Duration::hours(2)).await;
+        let mut own = shared_key(&db, game_id).await;
+        own.create_user_id = MEMBER as i64;
+        let own = game_key::ActiveModel::from(own)
+            .reset_all()
+            .update(&db)
+            .await
+            .unwrap();
+        reveal(&db, &own, now - Duration::hours(1)).await;
+
+        let key = shared_key(&db, game_id).await;
+
+        assert_eq!(
+            policy
+                .check(&db, &PoolMember::new(MEMBER), &key)
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[async_std::test]
+    async fn per_game_limit() {
+        let (db, game_id) = setup().await;
+        let other_game = testing::insert_game(&db, "Portal 2", None).await;
+        let policy = ClaimPolicy {
+            max_per_game: Some(1),
+            ..Default::default()
+        };
+        reveal(
+            &db,
+            &shared_key(&db, game_id).await,
+            Utc::now() - Duration::days(60),
+        )
+        .await;
+        let member = PoolMember::new(MEMBER);
+
+        assert_eq!(
+            policy
+                .check(&db, &member, &shared_key(&db, game_id).await)
+                .await
+                .unwrap(),
+            Some(ClaimDenial::GameLimit { limit: 1 })
+        );
+        assert_eq!(
+            policy
+                .check(&db, &member, &shared_key(&db, other_game.id).await)
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[async_std::test]
+    async fn account_and_membership_age() {
+        let (db, game_id) = setup().await;
+        let policy = ClaimPolicy {
+            min_account_age: Some(Duration::days(30)),
+            min_membership_age: Some(Duration::days(7)),
+            ..Default::default()
+        };
+        let key = shared_key(&db, game_id).await;
+        let now = Utc::now();
+        let mut member = PoolMember::new(MEMBER);
+        member.account_date = Some(now - Duration::days(10));
+        member.join_date = Some(now - Duration::days(1));
+
+        assert_eq!(
+            policy.check(&db, &member, &key).await.unwrap(),
+            Some(ClaimDenial::AccountAge {
+                allowed_from: now + Duration::days(20)
+            })
+        );
+
+        member.account_date = Some(now - Duration::days(100));
+
+        assert_eq!(
+            policy.check(&db, &member, &key).await.unwrap(),
+            Some(ClaimDenial::MembershipAge {
+                allowed_from: now + Duration::days(6)
+            })
+        );
+
+        member.join_date = Some(now - Duration::days(8));
+
+        assert_eq!(policy.check(&db, &member, &key).await.unwrap(), None);
+    }
+
+    #[async_std::test]
+    async fn override_and_reservations_lift_limits() {
+        let (db, game_id) = setup().await;
+        let policy = ClaimPolicy {
+            max_claims: Some(1),
+            ..Default::default()
+        };
+        reveal(&db, &shared_key(&db, game_id).await, Utc::now()).await;
+        let mut key = shared_key(&db, game_id).await;
+        let member = PoolMember::new(MEMBER);
+
+        assert!(policy.check(&db, &member, &key).await.unwrap().is_some());
+
+        key.reserved_user_id = Some(MEMBER as i64);
+
+        assert_eq!(policy.check(&db, &member, &key).await.unwrap(), None);
+
+        key.reserved_user_id = None;
+        user_setting::ActiveModel {
+            user_id: Set(MEMBER as i64),
+            country_code: Set(None),
+            share_keys: Set(true),
+            claim_override_until: Set(Some(Utc::now() + Duration::hours(1))),
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+
+        assert_eq!(policy.check(&db, &member, &key).await.unwrap(), None);
+    }
+}
@@ -0,0 +1,65 @@
+use entity::{game_key, key_pool, sea_orm_active_enums::PoolKind};
+use sea_orm::{
+    sqlx::types::chrono::{DateTime, Utc},
+    ColumnTrait, Condition,
+};
+
+/// The user asking for keys. Membership of guild and role pools depends on the guild the
+/// command is used in and the roles the user has there.
+#[derive(Clone, Debug, Default)]
+pub struct PoolMember {
+    pub user_id: u64,
+    pub guild_id: Option<u64>,
+    pub role_ids: Vec<u64>,
+    /// Creation date of the discord account.
+    pub account_date: Option<DateTime<Utc>>,
+    /// Date the user joined the guild.
+    pub join_date: Option<DateTime<Utc>>,
+}
+
+impl PoolMember {
+    /// Creates a member outside of any guild, e.g. in direct messages.
+    #[must_use]
+    pub fn new(user_id: u64) -> Self {
+        Self {
+            user_id,
+            ..Default::default()
+        }
+    }
+
+    /// Checks whether the member may see and claim the keys of a pool. Owners can always
+    /// access their pools.
+    #[must_use]
+    pub fn can_access(&self, pool: &key_pool::Model) -> bool {
+        if pool.owner_user_id == self.user_id as i64 {
+            return true;
+        }
+
+        let in_guild = pool.guild_id.is_some() && pool.guild_id == self.guild_id.map(|x| x as i64);
+        match pool.kind {
+            PoolKind::Personal => false,
+            PoolKind::Guild => in_guild,
+            PoolKind::Role => {
+                in_guild
+                    && pool
+                        .role_id
+                        .is_some_and(|x| self.role_ids.contains(&(x as u64)))
+            }
+        }
+    }
+
+    /// Condition matching the gamekeys the member may see and claim: their own keys and the keys
+    /// of all accessible pools.
+    #[must_use]
+    pub fn key_condition(&self, pools: &[key_pool::Model]) -> Condition {
+        let pool_ids = pools
+            .iter()
+            .filter(|x| self.can_access(x))
+            .map(|x| x.id)
+            .collect::<Vec<i32>>();
+
+        Condition::any()
+            .add(game_key::Column::CreateUserId.eq(self.user_id))
+            .add(game_key::Column::PoolId.is_in(pool_ids))
+    }
+}
@@ -1,11 +1,32 @@
 use ::entity::{
+    audit_event::{self, Entity as AuditEvent},
+    expiry_reminder::{self, Entity as ExpiryReminder},
     game::{self, Entity as Game},
     game_key::{self, Entity as GameKey},
+    giveaway::{self, Entity as Giveaway},
+    giveaway_entry::{self, Entity as GiveawayEntry},
+    key_pool::{self, Entity as KeyPool},
     platform::{self, Entity as Platform},
+    price_snapshot::{self, Entity as PriceSnapshot},
+    sea_orm_active_enums::{AuditAction, GiveawayState, KeyState},
+    steam_app::{self, Entity as SteamApp},
+    user_setting::{self, Entity as UserSetting},
+    wishlist::{self, Entity as Wishlist},
 };
+use std::collections::{HashMap, HashSet};
+
+use chrono::Duration;
 use sea_orm::{
-    sqlx::types::chrono::Utc, ColumnTrait, DbConn, DbErr, EntityTrait, ModelTrait, PaginatorTrait,
-    QueryFilter, QuerySelect,
+    sqlx::types::chrono::{DateTime, Utc},
+    ColumnTrait, Condition, DbConn, DbErr, EntityTrait, ModelTrait, PaginatorTrait, QueryFilter,
+    QueryOrder, QuerySelect, QueryTrait,
+};
+
+use crate::{
+    backup::{Backup, BACKUP_FORMAT_VERSION},
+    crypto::KeyCipher,
+    pool::PoolMember,
+    value::{Amounts, ClaimedValue, ValueEstimate},
 };
 
 pub struct GameQuery;
@@ -14,6 +35,10 @@ pub struct GameKeyQuery;
 
 pub struct PlatformQuery;
 
+pub struct AuditQuery;
+
+pub struct GiveawayQuery;
+
 impl GameQuery {
     /// Gets all games from the database.
     ///
@@ -21,7 +46,10 @@ impl GameQuery {
     ///
     /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
     pub async fn get_all(db: &DbConn) -> Result<Vec<game::Model>, DbErr> {
-        Game::find().all(db).await
+        Game::find()
+            .filter(game::Column::DeletedDate.is_null())
+            .all(db)
+            .await
     }
 
     /// Gets a game from the database by its id.
@@ -30,7 +58,10 @@ impl GameQuery {
     ///
     /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
     pub async fn get_one(db: &DbConn, id: i32) -> Result<Option<game::Model>, DbErr> {
-        Game::find_by_id(id).one(db).await
+        Game::find_by_id(id)
+            .filter(game::Column::DeletedDate.is_null())
+            .one(db)
+            .await
     }
 
     /// Gets a game by its title from the database.
@@ -40,36 +71,61 @@ impl GameQuery {
     /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
     pub async fn get_by_title(db: &DbConn, title: &str) -> Result<Option<game::Model>, DbErr> {
         Game::find()
+            .filter(game::Column::DeletedDate.is_null())
             .filter(game::Column::Title.eq(title))
             .one(db)
             .await
     }
 
+    /// Gets all games which have a steam app id.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn get_all_with_steam_appid(db: &DbConn) -> Result<Vec<game::Model>, DbErr> {
+        Game::find()
+            .filter(game::Column::DeletedDate.is_null())
+            .filter(game::Column::SteamAppid.is_not_null())
+            .order_by_asc(game::Column::Id)
+            .all(db)
+            .await
+    }
+
     /// Checks whether a game exists by its id.
     ///
     /// # Errors
     ///
     /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
     pub async fn exists(db: &DbConn, id: i32) -> Result<bool, DbErr> {
-        let game = Game::find_by_id(id).one(db).await?;
+        let game = Game::find_by_id(id)
+            .filter(game::Column::DeletedDate.is_null())
+            .one(db)
+            .await?;
 
         Ok(game.is_some())
     }
 
     pub async fn count_total(db: &DbConn) -> Result<u64, DbErr> {
-        Game::find().count(db).await
+        Game::find()
+            .filter(game::Column::DeletedDate.is_null())
+            .count(db)
+            .await
     }
 
     pub async fn get_all_games_with_keys(
         db: &DbConn,
-        user_id: u64,
+        member: &PoolMember,
     ) -> Result<Vec<game::Model>, DbErr> {
+        let access = member.key_condition(&KeyPoolQuery::get_all(db).await?);
+
         Game::find()
+            .filter(game::Column::DeletedDate.is_null())
             .left_join(game_key::Entity)
+            .filter(access)
             .filter(
                 game_key::Column::Keystate
-                    .eq("Unused")
-                    .and(game_key::Column::CreateUserId.eq(user_id))
+                    .eq(KeyState::Unused)
+                    .and(game_key::Column::DeletedDate.is_null())
                     .and(
                         game_key::Column::ExpirationDate
                             .is_null()
@@ -79,6 +135,47 @@ impl GameQuery {
             .all(db)
             .await
     }
+
+    /// Gets all games currently in the trash.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn get_trashed(db: &DbConn) -> Result<Vec<game::Model>, DbErr> {
+        Game::find()
+            .filter(game::Column::DeletedDate.is_not_null())
+            .order_by_asc(game::Column::DeletedDate)
+            .all(db)
+            .await
+    }
+
+    /// Gets a game from the trash by its id.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn get_trashed_one(db: &DbConn, id: i32) -> Result<Option<game::Model>, DbErr> {
+        Game::find_by_id(id)
+            .filter(game::Column::DeletedDate.is_not_null())
+            .one(db)
+            .await
+    }
+
+    /// Gets a game from the trash by its title.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn get_trashed_by_title(
+        db: &DbConn,
+        title: &str,
+    ) -> Result<Option<game::Model>, DbErr> {
+        Game::find()
+            .filter(game::Column::Title.eq(title))
+            .filter(game::Column::DeletedDate.is_not_null())
+            .one(db)
+            .await
+    }
 }
 
 /// Model for querying all data about a gamekey.
@@ -112,8 +209,14 @@ impl GameKeyQuery {
     /// # Errors
     ///
     /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
-    pub async fn get_all(db: &DbConn) -> Result<Vec<game_key::Model>, DbErr> {
-        GameKey::find().all(db).await
+    pub async fn get_all(db: &DbConn, cipher: &KeyCipher) -> Result<Vec<game_key::Model>, DbErr> {
+        GameKey::find()
+            .filter(game_key::Column::DeletedDate.is_null())
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|x| decrypt_value(cipher, x))
+            .collect()
     }
 
     /// Gets a gamekey by its id.
@@ -123,31 +226,79 @@ impl GameKeyQuery {
     /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
     pub async fn get_one(
         db: &DbConn,
+        cipher: &KeyCipher,
         id: i32,
         user_id: u64,
     ) -> Result<Option<game_key::Model>, DbErr> {
         GameKey::find_by_id(id)
+            .filter(game_key::Column::DeletedDate.is_null())
             .filter(game_key::Column::CreateUserId.eq(user_id))
             .one(db)
-            .await
+            .await?
+            .map(|x| decrypt_value(cipher, x))
+            .transpose()
     }
 
-    /// Gets all gamekeys filtered by game.
+    /// Gets a gamekey by its id which the member may see, either their own, one of an accessible
+    /// pool or one reserved for the member.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn get_one_accessible(
+        db: &DbConn,
+        cipher: &KeyCipher,
+        id: i32,
+        member: &PoolMember,
+    ) -> Result<Option<game_key::Model>, DbErr> {
+        GameKey::find_by_id(id)
+            .filter(game_key::Column::DeletedDate.is_null())
+            .filter(
+                member
+                    .key_condition(&KeyPoolQuery::get_all(db).await?)
+                    .add(game_key::Column::ReservedUserId.eq(member.user_id)),
+            )
+            .one(db)
+            .await?
+            .map(|x| decrypt_value(cipher, x))
+            .transpose()
+    }
+
+    /// Gets a gamekey by its id which has been gifted to a user.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn get_one_by_recipient(
+        db: &DbConn,
+        cipher: &KeyCipher,
+        id: i32,
+        recipient_user_id: u64,
+    ) -> Result<Option<game_key::Model>, DbErr> {
+        GameKey::find_by_id(id)
+            .filter(game_key::Column::DeletedDate.is_null())
+            .filter(game_key::Column::RecipientUserId.eq(recipient_user_id))
+            .one(db)
+            .await?
+            .map(|x| decrypt_value(cipher, x))
+            .transpose()
+    }
+
+    /// Gets all gamekeys of a game the member may see, either their own or of accessible pools.
     ///
     /// # Errors
     ///
     /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
     pub async fn get_all_by_game(
         db: &DbConn,
+        cipher: &KeyCipher,
         game_id: i32,
-        user_id: u64,
+        member: &PoolMember,
     ) -> Result<Vec<GameKeyModel>, DbErr> {
         let game_keys = GameKey::find()
-            .filter(
-                game_key::Column::GameId
-                    .eq(game_id)
-                    .and(game_key::Column::CreateUserId.eq(user_id)),
-            )
+            .filter(game_key::Column::DeletedDate.is_null())
+            .filter(game_key::Column::GameId.eq(game_id))
+            .filter(member.key_condition(&KeyPoolQuery::get_all(db).await?))
             .all(db)
             .await?;
 
@@ -168,7 +319,7 @@ impl GameKeyQuery {
                 .ok_or(DbErr::Custom("No game bound to this gamekey".to_owned()))?;
 
             complete_models.push(GameKeyModel {
-                game_key,
+                game_key: decrypt_value(cipher, game_key)?,
                 game,
                 platform,
             });
@@ -177,6 +328,26 @@ impl GameKeyQuery {
         Ok(complete_models)
     }
 
+    /// Gets all gamekeys of a game regardless of their owner.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn get_all_of_game(
+        db: &DbConn,
+        cipher: &KeyCipher,
+        game_id: i32,
+    ) -> Result<Vec<game_key::Model>, DbErr> {
+        GameKey::find()
+            .filter(game_key::Column::DeletedDate.is_null())
+            .filter(game_key::Column::GameId.eq(game_id))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|x| decrypt_value(cipher, x))
+            .collect()
+    }
+
     /// Gets all gamekeys filtered by platform.
     ///
     /// # Errors
@@ -184,13 +355,18 @@ impl GameKeyQuery {
     /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
     pub async fn get_all_by_platform(
         db: &DbConn,
+        cipher: &KeyCipher,
         platform_id: i32,
     ) -> Result<Vec<(game_key::Model, Option<platform::Model>)>, DbErr> {
         GameKey::find()
+            .filter(game_key::Column::DeletedDate.is_null())
             .filter(game_key::Column::PlatformId.eq(platform_id))
             .find_also_related(Platform)
             .all(db)
-            .await
+            .await?
+            .into_iter()
+            .map(|(game_key, platform)| Ok((decrypt_value(cipher, game_key)?, platform)))
+            .collect()
     }
 
     /// Gets all gamekeys filtered by platform and game.
@@ -200,14 +376,61 @@ impl GameKeyQuery {
     /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
     pub async fn get_all_filtered(
         db: &DbConn,
+        cipher: &KeyCipher,
         game_id: i32,
         platform_id: i32,
     ) -> Result<Vec<game_key::Model>, DbErr> {
         GameKey::find()
+            .filter(game_key::Column::DeletedDate.is_null())
             .filter(game_key::Column::GameId.eq(game_id))
             .filter(game_key::Column::PlatformId.eq(platform_id))
             .all(db)
-            .await
+            .await?
+            .into_iter()
+            .map(|x| decrypt_value(cipher, x))
+            .collect()
+    }
+
+    /// Gets all gamekeys of a user together with their game and platform, optionally filtered
+    /// by platform and state. Key values stay encrypted.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn get_all_of_user(
+        db: &DbConn,
+        user_id: u64,
+        platform_id: Option<i32>,
+        keystate: Option<KeyState>,
+    ) -> Result<Vec<(game_key::Model, game::Model, platform::Model)>, DbErr> {
+        let mut select = GameKey::find()
+            .filter(game_key::Column::DeletedDate.is_null())
+            .filter(game_key::Column::CreateUserId.eq(user_id));
+        if let Some(platform_id) = platform_id {
+            select = select.filter(game_key::Column::PlatformId.eq(platform_id));
+        }
+        if let Some(keystate) = keystate {
+            select = select.filter(game_key::Column::Keystate.eq(keystate));
+        }
+
+        let game_keys = select
+            .find_also_related(Game)
+            .filter(game::Column::DeletedDate.is_null())
+            .order_by_asc(game::Column::Title)
+            .all(db)
+            .await?;
+        let platforms = Platform::find().all(db).await?;
+
+        Ok(game_keys
+            .into_iter()
+            .filter_map(|(game_key, game)| {
+                let platform = platforms
+                    .iter()
+                    .find(|x| x.id == game_key.platform_id)?
+                    .clone();
+                Some((game_key, game?, platform))
+            })
+            .collect())
     }
 
     /// Gets the number of gamekeys found for a game id.
@@ -217,29 +440,31 @@ impl GameKeyQuery {
     /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
     pub async fn count_by_game(db: &DbConn, game_id: i32) -> Result<u64, DbErr> {
         GameKey::find()
+            .filter(game_key::Column::DeletedDate.is_null())
             .filter(game_key::Column::GameId.eq(game_id))
             .count(db)
             .await
     }
 
-    /// Gets all unused gamekey ids
+    /// Gets the ids of all unused gamekeys the member may claim.
     ///
     /// # Errors
     ///
     /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
-    pub async fn get_all_ids(db: &DbConn, user_id: u64) -> Result<Vec<i32>, DbErr> {
+    pub async fn get_all_ids(db: &DbConn, member: &PoolMember) -> Result<Vec<i32>, DbErr> {
+        let access = member.key_condition(&KeyPoolQuery::get_all(db).await?);
+
         let res: Vec<i32> = GameKey::find()
+            .filter(game_key::Column::DeletedDate.is_null())
             .select_only()
             .column(game_key::Column::Id)
+            .filter(access)
             .filter(
-                game_key::Column::Keystate
-                    .eq("Unused")
-                    .and(game_key::Column::CreateUserId.eq(user_id))
-                    .and(
-                        game_key::Column::ExpirationDate
-                            .is_null()
-                            .or(game_key::Column::ExpirationDate.gt(Utc::now())),
-                    ),
+                game_key::Column::Keystate.eq(KeyState::Unused).and(
+                    game_key::Column::ExpirationDate
+                        .is_null()
+                        .or(game_key::Column::ExpirationDate.gt(Utc::now())),
+                ),
             )
             .into_tuple()
             .all(db)
@@ -249,11 +474,53 @@ impl GameKeyQuery {
     }
 
     pub async fn count_total(db: &DbConn) -> Result<u64, DbErr> {
-        GameKey::find().count(db).await
+        GameKey::find()
+            .filter(game_key::Column::DeletedDate.is_null())
+            .count(db)
+            .await
+    }
+
+    /// Gets all gamekeys of a user currently in the trash together with their game.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn get_trashed(
+        db: &DbConn,
+        user_id: u64,
+    ) -> Result<Vec<(game_key::Model, Option<game::Model>)>, DbErr> {
+        GameKey::find()
+            .filter(game_key::Column::DeletedDate.is_not_null())
+            .filter(game_key::Column::CreateUserId.eq(user_id))
+            .order_by_asc(game_key::Column::DeletedDate)
+            .find_also_related(Game)
+            .all(db)
+            .await
+    }
+
+    /// Gets a gamekey of a user from the trash by its id.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn get_trashed_one(
+        db: &DbConn,
+        cipher: &KeyCipher,
+        id: i32,
+        user_id: u64,
+    ) -> Result<Option<game_key::Model>, DbErr> {
+        GameKey::find_by_id(id)
+            .filter(game_key::Column::DeletedDate.is_not_null())
+            .filter(game_key::Column::CreateUserId.eq(user_id))
+            .one(db)
+            .await?
+            .map(|x| decrypt_value(cipher, x))
+            .transpose()
     }
 
     pub async fn count_total_of_user(db: &DbConn, user_id: u64) -> Result<u64, DbErr> {
         GameKey::find()
+            .filter(game_key::Column::DeletedDate.is_null())
             .filter(game_key::Column::CreateUserId.eq(user_id))
             .count(db)
             .await
@@ -261,8 +528,9 @@ impl GameKeyQuery {
 
     pub async fn count_unused(db: &DbConn) -> Result<u64, DbErr> {
         GameKey::find()
+            .filter(game_key::Column::DeletedDate.is_null())
             .filter(
-                game_key::Column::Keystate.eq("Unused").and(
+                game_key::Column::Keystate.eq(KeyState::Unused).and(
                     game_key::Column::ExpirationDate
                         .is_null()
                         .or(game_key::Column::ExpirationDate.gt(Utc::now())),
@@ -274,9 +542,10 @@ impl GameKeyQuery {
 
     pub async fn count_unused_of_user(db: &DbConn, user_id: u64) -> Result<u64, DbErr> {
         GameKey::find()
+            .filter(game_key::Column::DeletedDate.is_null())
             .filter(
                 game_key::Column::Keystate
-                    .eq("Unused")
+                    .eq(KeyState::Unused)
                     .and(game_key::Column::CreateUserId.eq(user_id))
                     .and(
                         game_key::Column::ExpirationDate
@@ -290,16 +559,18 @@ impl GameKeyQuery {
 
     pub async fn count_used(db: &DbConn) -> Result<u64, DbErr> {
         GameKey::find()
-            .filter(game_key::Column::Keystate.eq("Used"))
+            .filter(game_key::Column::DeletedDate.is_null())
+            .filter(game_key::Column::Keystate.eq(KeyState::Used))
             .count(db)
             .await
     }
 
     pub async fn count_used_of_user(db: &DbConn, user_id: u64) -> Result<u64, DbErr> {
         GameKey::find()
+            .filter(game_key::Column::DeletedDate.is_null())
             .filter(
                 game_key::Column::Keystate
-                    .eq("Used")
+                    .eq(KeyState::Used)
                     .and(game_key::Column::CreateUserId.eq(user_id)),
             )
             .count(db)
@@ -307,6 +578,98 @@ impl GameKeyQuery {
     }
 }
 
+fn decrypt_value(
+    cipher: &KeyCipher,
+    mut game_key: game_key::Model,
+) -> Result<game_key::Model, DbErr> {
+    game_key.value = cipher.decrypt(&game_key.value)?;
+
+    Ok(game_key)
+}
+
+pub struct KeyPoolQuery;
+
+impl KeyPoolQuery {
+    /// Gets all key pools.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn get_all(db: &DbConn) -> Result<Vec<key_pool::Model>, DbErr> {
+        KeyPool::find()
+            .order_by_asc(key_pool::Column::Name)
+            .all(db)
+            .await
+    }
+
+    /// Gets a key pool by its name.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn get_by_name(db: &DbConn, name: &str) -> Result<Option<key_pool::Model>, DbErr> {
+        KeyPool::find()
+            .filter(key_pool::Column::Name.eq(name))
+            .one(db)
+            .await
+    }
+
+    /// Gets all key pools the member may access.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn get_accessible(
+        db: &DbConn,
+        member: &PoolMember,
+    ) -> Result<Vec<key_pool::Model>, DbErr> {
+        Ok(Self::get_all(db)
+            .await?
+            .into_iter()
+            .filter(|x| member.can_access(x))
+            .collect())
+    }
+
+    /// Gets the keys of a pool together with their game and platform. Key values stay encrypted.
+    /// Returns `None` if the member may not access the pool.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn get_keys(
+        db: &DbConn,
+        pool: &key_pool::Model,
+        member: &PoolMember,
+    ) -> Result<Option<Vec<(game_key::Model, game::Model, platform::Model)>>, DbErr> {
+        if !member.can_access(pool) {
+            return Ok(None);
+        }
+
+        let game_keys = GameKey::find()
+            .filter(game_key::Column::DeletedDate.is_null())
+            .filter(game_key::Column::PoolId.eq(pool.id))
+            .find_also_related(Game)
+            .filter(game::Column::DeletedDate.is_null())
+            .order_by_asc(game::Column::Title)
+            .all(db)
+            .await?;
+        let platforms = Platform::find().all(db).await?;
+
+        Ok(Some(
+            game_keys
+                .into_iter()
+                .filter_map(|(game_key, game)| {
+                    let platform = platforms
+                        .iter()
+                        .find(|x| x.id == game_key.platform_id)?
+                        .clone();
+                    Some((game_key, game?, platform))
+                })
+                .collect(),
+        ))
+    }
+}
+
 impl PlatformQuery {
     /// Gets all platforms in the database.
     ///
@@ -338,3 +701,501 @@ impl PlatformQuery {
             .await
     }
 }
+
+/// Filter options for querying audit events. Unset options match every event.
+#[derive(Clone, Debug, Default)]
+pub struct AuditFilter {
+    pub game_key_id: Option<i32>,
+    pub game_id: Option<i32>,
+    pub user_id: Option<u64>,
+}
+
+impl AuditQuery {
+    /// Gets a page of audit events matching the filter, newest events first.
+    /// Also returns the total number of pages.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn get_page(
+        db: &DbConn,
+        filter: &AuditFilter,
+        page: u64,
+        page_size: u64,
+    ) -> Result<(Vec<audit_event::Model>, u64), DbErr> {
+        let mut query = AuditEvent::find().order_by_desc(audit_event::Column::Id);
+
+        if let Some(game_key_id) = filter.game_key_id {
+            query = query.filter(audit_event::Column::GameKeyId.eq(game_key_id));
+        }
+        if let Some(game_id) = filter.game_id {
+            query = query.filter(audit_event::Column::GameId.eq(game_id));
+        }
+        if let Some(user_id) = filter.user_id {
+            query = query.filter(audit_event::Column::UserId.eq(user_id));
+        }
+
+        let paginator = query.paginate(db, page_size);
+        let num_pages = paginator.num_pages().await?;
+        let events = paginator.fetch_page(page).await?;
+
+        Ok((events, num_pages))
+    }
+}
+
+impl GiveawayQuery {
+    /// Gets a giveaway by its id.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn get_one(db: &DbConn, id: i32) -> Result<Option<giveaway::Model>, DbErr> {
+        Giveaway::find_by_id(id).one(db).await
+    }
+
+    /// Gets the running giveaway of a gamekey if there is one.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn get_running_by_game_key(
+        db: &DbConn,
+        game_key_id: i32,
+    ) -> Result<Option<giveaway::Model>, DbErr> {
+        Giveaway::find()
+            .filter(giveaway::Column::GameKeyId.eq(game_key_id))
+            .filter(giveaway::Column::State.eq(GiveawayState::Running))
+            .one(db)
+            .await
+    }
+
+    /// Gets all running giveaways which ended before the given date.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn get_all_due(
+        db: &DbConn,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<giveaway::Model>, DbErr> {
+        Giveaway::find()
+            .filter(giveaway::Column::State.eq(GiveawayState::Running))
+            .filter(giveaway::Column::EndDate.lte(now))
+            .all(db)
+            .await
+    }
+
+    /// Gets the user ids of all entrants of a giveaway.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn get_entrants(db: &DbConn, giveaway_id: i32) -> Result<Vec<i64>, DbErr> {
+        GiveawayEntry::find()
+            .select_only()
+            .column(giveaway_entry::Column::UserId)
+            .filter(giveaway_entry::Column::GiveawayId.eq(giveaway_id))
+            .into_tuple()
+            .all(db)
+            .await
+    }
+
+    /// Counts the entrants of a giveaway.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn count_entrants(db: &DbConn, giveaway_id: i32) -> Result<u64, DbErr> {
+        GiveawayEntry::find()
+            .filter(giveaway_entry::Column::GiveawayId.eq(giveaway_id))
+            .count(db)
+            .await
+    }
+}
+
+pub struct BackupQuery;
+
+impl BackupQuery {
+    /// Creates a backup of all platforms, pools, games and gamekeys, including trashed ones.
+    /// Gamekey values are decrypted.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail or a value cannot be decrypted. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn create(
+        db: &DbConn,
+        cipher: &KeyCipher,
+        schema_version: &str,
+    ) -> Result<Backup, DbErr> {
+        let game_keys = GameKey::find()
+            .order_by_asc(game_key::Column::Id)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|x| decrypt_value(cipher, x))
+            .collect::<Result<Vec<game_key::Model>, DbErr>>()?;
+
+        Ok(Backup {
+            format_version: BACKUP_FORMAT_VERSION,
+            schema_version: schema_version.to_owned(),
+            create_date: Utc::now(),
+            platforms: Platform::find()
+                .order_by_asc(platform::Column::Id)
+                .all(db)
+                .await?,
+            pools: KeyPool::find()
+                .order_by_asc(key_pool::Column::Id)
+                .all(db)
+                .await?,
+            games: Game::find().order_by_asc(game::Column::Id).all(db).await?,
+            game_keys,
+        })
+    }
+}
+
+pub struct SteamAppQuery;
+
+impl SteamAppQuery {
+    /// Gets the stored snapshot of the steam app list.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn get_all(db: &DbConn) -> Result<Vec<steam_app::Model>, DbErr> {
+        SteamApp::find()
+            .order_by_asc(steam_app::Column::Appid)
+            .all(db)
+            .await
+    }
+}
+
+pub struct UserSettingQuery;
+
+impl UserSettingQuery {
+    /// Gets the settings of a user. Returns `None` if the user has not changed any setting.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn get(db: &DbConn, user_id: u64) -> Result<Option<user_setting::Model>, DbErr> {
+        UserSetting::find_by_id(user_id as i64).one(db).await
+    }
+
+    /// Checks whether keys added by a user are announced to users wishing for the game.
+    /// Users share their keys unless they opted out.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn shares_keys(db: &DbConn, user_id: u64) -> Result<bool, DbErr> {
+        Ok(Self::get(db, user_id)
+            .await?
+            .map(|x| x.share_keys)
+            .unwrap_or(true))
+    }
+}
+
+pub struct PriceSnapshotQuery;
+
+impl PriceSnapshotQuery {
+    /// Gets the latest price snapshot of a steam app in a store region.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn get_latest(
+        db: &DbConn,
+        steam_appid: i64,
+        country_code: &str,
+    ) -> Result<Option<price_snapshot::Model>, DbErr> {
+        PriceSnapshot::find()
+            .filter(price_snapshot::Column::SteamAppid.eq(steam_appid))
+            .filter(price_snapshot::Column::CountryCode.eq(country_code))
+            .order_by_desc(price_snapshot::Column::CreateDate)
+            .order_by_desc(price_snapshot::Column::Id)
+            .one(db)
+            .await
+    }
+
+    /// Gets all price snapshots of the given steam apps in the given store regions, oldest first.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn get_all_of_apps(
+        db: &DbConn,
+        steam_appids: &HashSet<i64>,
+        country_codes: &[String],
+    ) -> Result<Vec<price_snapshot::Model>, DbErr> {
+        if steam_appids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        PriceSnapshot::find()
+            .filter(price_snapshot::Column::SteamAppid.is_in(steam_appids.iter().copied()))
+            .filter(price_snapshot::Column::CountryCode.is_in(country_codes.iter().cloned()))
+            .order_by_asc(price_snapshot::Column::CreateDate)
+            .all(db)
+            .await
+    }
+
+    /// Estimates the value of the unused keys of a user by the latest regular price of their games.
+    /// Prices of the first country code are preferred, the others are used as fallback.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn estimate_unused_value(
+        db: &DbConn,
+        user_id: u64,
+        country_codes: &[String],
+    ) -> Result<ValueEstimate, DbErr> {
+        let game_keys =
+            GameKeyQuery::get_all_of_user(db, user_id, None, Some(KeyState::Unused)).await?;
+        let appids = game_keys
+            .iter()
+            .filter_map(|(_, game, _)| game.steam_appid)
+            .collect::<HashSet<i64>>();
+        let snapshots = Self::get_all_of_apps(db, &appids, country_codes).await?;
+
+        let mut estimate = ValueEstimate::default();
+        for (_, game, platform) in game_keys {
+            let snapshot = game
+                .steam_appid
+                .and_then(|x| find_snapshot(&snapshots, x, country_codes, None));
+
+            match snapshot {
+                Some(s) => {
+                    add_amount(&mut estimate.total, s);
+                    add_amount(estimate.platforms.entry(platform.name).or_default(), s);
+                }
+                None => estimate.unpriced += 1,
+            }
+        }
+
+        Ok(estimate)
+    }
+
+    /// Sums the regular prices of the keys of a user which have been claimed, by month of the claim.
+    /// Each key is valued with the last price known at the time of its claim.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn estimate_claimed_value(
+        db: &DbConn,
+        user_id: u64,
+        country_codes: &[String],
+    ) -> Result<ClaimedValue, DbErr> {
+        let appids = GameKey::find()
+            .filter(game_key::Column::CreateUserId.eq(user_id))
+            .find_also_related(Game)
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|(game_key, game)| (game_key.id, game.and_then(|x| x.steam_appid)))
+            .collect::<HashMap<i32, Option<i64>>>();
+
+        let snapshots = Self::get_all_of_apps(
+            db,
+            &appids.values().flatten().copied().collect(),
+            country_codes,
+        )
+        .await?;
+
+        // Claims, gifts and giveaways reveal the key to its new owner.
+        let events = AuditEvent::find()
+            .filter(audit_event::Column::Action.eq(AuditAction::Reveal))
+            .filter(
+                audit_event::Column::GameKeyId.in_subquery(
+                    GameKey::find()
+                        .select_only()
+                        .column(game_key::Column::Id)
+                        .filter(game_key::Column::CreateUserId.eq(user_id))
+                        .into_query(),
+                ),
+            )
+            .order_by_asc(audit_event::Column::CreateDate)
+            .all(db)
+            .await?;
+
+        let mut claimed = ClaimedValue::default();
+        let mut counted = HashSet::new();
+        for event in events {
+            let appid = match event.game_key_id.and_then(|x| appids.get(&x)) {
+                Some(appid) => appid,
+                None => continue,
+            };
+            if !counted.insert(event.game_key_id) {
+                continue;
+            }
+
+            let snapshot = appid
+                .and_then(|x| find_snapshot(&snapshots, x, country_codes, Some(event.create_date)));
+            match snapshot {
+                Some(s) => add_amount(
+                    claimed
+                        .months
+                        .entry(event.create_date.format("%Y-%m").to_string())
+                        .or_default(),
+                    s,
+                ),
+                None => claimed.unpriced += 1,
+            }
+        }
+
+        Ok(claimed)
+    }
+}
+
+pub struct WishlistQuery;
+
+impl WishlistQuery {
+    /// Gets the wishlist of a user with the wished game or steam app, ordered by the date they were added.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn get_all_of_user(
+        db: &DbConn,
+        user_id: u64,
+    ) -> Result<
+        Vec<(
+            wishlist::Model,
+            Option<game::Model>,
+            Option<steam_app::Model>,
+        )>,
+        DbErr,
+    > {
+        let wishes = Wishlist::find()
+            .filter(wishlist::Column::UserId.eq(user_id))
+            .order_by_asc(wishlist::Column::CreateDate)
+            .find_also_related(Game)
+            .all(db)
+            .await?;
+        let steam_apps = SteamApp::find()
+            .filter(
+                steam_app::Column::Appid.is_in(wishes.iter().filter_map(|(x, _)| x.steam_appid)),
+            )
+            .all(db)
+            .await?;
+
+        Ok(wishes
+            .into_iter()
+            .map(|(wish, game)| {
+                let steam_app = steam_apps
+                    .iter()
+                    .find(|x| Some(x.appid) == wish.steam_appid)
+                    .cloned();
+                (wish, game, steam_app)
+            })
+            .collect())
+    }
+
+    /// Gets the wishes of all users for a game, either by the game itself or its steam app id.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn get_all_of_game(
+        db: &DbConn,
+        game: &game::Model,
+    ) -> Result<Vec<wishlist::Model>, DbErr> {
+        let mut condition = Condition::any().add(wishlist::Column::GameId.eq(game.id));
+        if let Some(steam_appid) = game.steam_appid {
+            condition = condition.add(wishlist::Column::SteamAppid.eq(steam_appid));
+        }
+
+        Wishlist::find().filter(condition).all(db).await
+    }
+}
+
+/// Finds the price snapshot of an app, preferring earlier country codes. Without a date the latest
+/// snapshot is used, otherwise the last one before the date or the first one after it.
+fn find_snapshot<'a>(
+    snapshots: &'a [price_snapshot::Model],
+    steam_appid: i64,
+    country_codes: &[String],
+    date: Option<DateTime<Utc>>,
+) -> Option<&'a price_snapshot::Model> {
+    country_codes.iter().find_map(|country_code| {
+        let of_app = snapshots
+            .iter()
+            .filter(|x| x.steam_appid == steam_appid && &x.country_code == country_code)
+            .collect::<Vec<&price_snapshot::Model>>();
+
+        match date {
+            Some(date) => of_app
+                .iter()
+                .rev()
+                .find(|x| x.create_date <= date)
+                .or(of_app.first())
+                .copied(),
+            None => of_app.last().copied(),
+        }
+    })
+}
+
+fn add_amount(amounts: &mut Amounts, snapshot: &price_snapshot::Model) {
+    *amounts.entry(snapshot.currency.clone()).or_default() += i64::from(snapshot.initial_price);
+}
+
+pub struct ExpiryReminderQuery;
+
+impl ExpiryReminderQuery {
+    /// Gets the unused keys expiring within the largest of the given windows in days, together
+    /// with their game and the smallest window they fall into. Keys which were already reminded
+    /// about for that window are left out. The key values stay encrypted.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if database operation fail. For more information look at [DbErr](https://docs.rs/sea-orm/latest/sea_orm/error/enum.DbErr.html).
+    pub async fn get_pending(
+        db: &DbConn,
+        now: DateTime<Utc>,
+        windows: &[i32],
+    ) -> Result<Vec<(game_key::Model, game::Model, i32)>, DbErr> {
+        let max_window = match windows.iter().max() {
+            Some(w) => *w,
+            None => return Ok(Vec::new()),
+        };
+
+        let keys = GameKey::find()
+            .find_also_related(Game)
+            .filter(game_key::Column::Keystate.eq(KeyState::Unused))
+            .filter(game_key::Column::DeletedDate.is_null())
+            .filter(game_key::Column::ExpirationDate.gt(now.naive_utc()))
+            .filter(
+                game_key::Column::ExpirationDate
+                    .lte((now + Duration::days(max_window.into())).naive_utc()),
+            )
+            .order_by_asc(game_key::Column::ExpirationDate)
+            .all(db)
+            .await?;
+
+        let sent: HashSet<(i32, i32)> = ExpiryReminder::find()
+            .filter(expiry_reminder::Column::GameKeyId.is_in(keys.iter().map(|(x, _)| x.id)))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|x| (x.game_key_id, x.window_days))
+            .collect();
+
+        Ok(keys
+            .into_iter()
+            .filter_map(|(game_key, game)| {
+                let expiration_date = game_key.expiration_date?;
+                let window = windows
+                    .iter()
+                    .copied()
+                    .filter(|w| expiration_date <= (now + Duration::days((*w).into())).naive_utc())
+                    .min()?;
+
+                match sent.contains(&(game_key.id, window)) {
+                    true => None,
+                    false => Some((game_key, game?, window)),
+                }
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,106 @@
+use entity::{
+    audit_event, expiry_reminder, game, game_key, giveaway, giveaway_entry, key_pool, platform,
+    price_snapshot, sea_orm_active_enums::KeyState, steam_app, user_setting, wishlist,
+};
+use sea_orm::{
+    sqlx::types::chrono::{DateTime, Utc},
+    ActiveModelTrait,
+    ActiveValue::NotSet,
+    ConnectionTrait, Database, DbConn, EntityTrait, Schema, Set,
+};
+
+/// Opens an in-memory database with the tables of all entities.
+pub async fn database() -> DbConn {
+    let db = Database::connect("sqlite::memory:").await.unwrap();
+    let schema = Schema::new(db.get_database_backend());
+    create_table(&db, &schema, platform::Entity).await;
+    create_table(&db, &schema, key_pool::Entity).await;
+    create_table(&db, &schema, game::Entity).await;
+    create_table(&db, &schema, game_key::Entity).await;
+    create_table(&db, &schema, giveaway::Entity).await;
+    create_table(&db, &schema, giveaway_entry::Entity).await;
+    create_table(&db, &schema, expiry_reminder::Entity).await;
+    create_table(&db, &schema, audit_event::Entity).await;
+    create_table(&db, &schema, user_setting::Entity).await;
+    create_table(&db, &schema, wishlist::Entity).await;
+    create_table(&db, &schema, steam_app::Entity).await;
+    create_table(&db, &schema, price_snapshot::Entity).await;
+    db
+}
+
+async fn create_table<E: EntityTrait>(db: &DbConn, schema: &Schema, entity: E) {
+    let statement = db
+        .get_database_backend()
+        .build(&schema.create_table_from_entity(entity));
+    db.execute(statement).await.unwrap();
+}
+
+pub async fn insert_game(
+    db: &DbConn,
+    title: &str,
+    deleted_date: Option<DateTime<Utc>>,
+) -> game::Model {
+    game::ActiveModel {
+        id: NotSet,
+        title: Set(title.to_owned()),
+        description: Set(None),
+        create_date: Set(Utc::now()),
+        create_user_id: Set(1),
+        modify_date: Set(None),
+        modify_user_id: Set(None),
+        image_link: Set(None),
+        deleted_date: Set(deleted_date),
+        deleted_user_id: Set(deleted_date.map(|_| 1)),
+        metadata: Set(None),
+        steam_appid: Set(None),
+        gog_id: Set(None),
+        epic_slug: Set(None),
+    }
+    .insert(db)
+    .await
+    .unwrap()
+}
+
+pub async fn insert_platform(db: &DbConn, name: &str) -> platform::Model {
+    platform::ActiveModel {
+        id: NotSet,
+        name: Set(name.to_owned()),
+        store_link: Set(None),
+    }
+    .insert(db)
+    .await
+    .unwrap()
+}
+
+pub async fn insert_game_key(
+    db: &DbConn,
+    game_id: i32,
+    platform_id: i32,
+    keystate: KeyState,
+    deleted_date: Option<DateTime<Utc>>,
+) -> game_key::Model {
+    game_key::ActiveModel {
+        id: NotSet,
+        game_id: Set(game_id),
+        platform_id: Set(platform_id),
+        value: Set("AAAAA-BBBBB-CCCCC".to_owned()),
+        keystate: Set(keystate),
+        page_link: Set(None),
+        create_date: Set(Utc::now()),
+        create_user_id: Set(1),
+        modify_date: Set(None),
+        modify_user_id: Set(None),
+        notes: Set(None),
+        expiration_date: Set(None),
+        deleted_date: Set(deleted_date),
+        deleted_user_id: Set(deleted_date.map(|_| 1)),
+        recipient_user_id: Set(None),
+        source: Set(None),
+        pool_id: Set(None),
+        reserved_user_id: Set(None),
+        reserved_until: Set(None),
+    }
+    .insert(db)
+    .await
+    .unwrap()
+}
@@ -0,0 +1,21 @@
+use std::collections::BTreeMap;
+
+/// Amounts of money by currency, in the smallest unit of the currency as reported by steam.
+pub type Amounts = BTreeMap<String, i64>;
+
+/// Estimated value of the unused keys of a user.
+#[derive(Clone, Debug, Default)]
+pub struct ValueEstimate {
+    pub total: Amounts,
+    pub platforms: BTreeMap<String, Amounts>,
+    /// Number of keys whose game has no known price.
+    pub unpriced: u64,
+}
+
+/// Value of the keys of a user which have been claimed, by month of the claim (`YYYY-MM`).
+#[derive(Clone, Debug, Default)]
+pub struct ClaimedValue {
+    pub months: BTreeMap<String, Amounts>,
+    /// Number of claimed keys whose game has no known price.
+    pub unpriced: u64,
+}